@@ -0,0 +1,104 @@
+use std::env;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// 'ari build' copies this executable and appends the script to the end of the copy:
+//      [executable][script][script name][script length: u64][name length: u64][MAGIC]
+// On startup, the copy finds MAGIC at its end and runs the embedded script instead.
+const MAGIC: &[u8; 8] = b"ARISCRPT";
+const TRAILER_LENGTH: u64 = 8 + 8 + 8;
+
+pub fn build(args: &[String]) {
+    // ari build script.ari [-o output]
+    let (script_name, output) = match args {
+        [script_name] => (script_name, default_output(script_name)),
+        [script_name, flag, output] if flag == "-o" => (script_name, output.to_owned()),
+        _ => {
+            eprintln!("Usage: ari build [script_name] -o [output]");
+            std::process::exit(ari_errors::EXIT_USAGE);
+        }
+    };
+    let contents = match fs::read_to_string(script_name) {
+        Ok(contents) => contents,
+        Err(_) => {
+            ari_errors::print_file_not_found(script_name);
+            return;
+        }
+    };
+    let name = Path::new(script_name).file_name().unwrap().to_string_lossy().into_owned();
+    match write_executable(&output, &contents, &name) {
+        Ok(_) => println!("Built {} from {}", output, script_name),
+        Err(e) => {
+            eprintln!("Failed to build {}: {}", output, e);
+            std::process::exit(ari_errors::EXIT_RUNTIME_ERROR);
+        }
+    }
+}
+
+fn default_output(script_name: &str) -> String {
+    // script.ari => script (or script.exe on Windows)
+    let stem = Path::new(script_name).file_stem().unwrap().to_string_lossy().into_owned();
+    format!("{}{}", stem, env::consts::EXE_SUFFIX)
+}
+
+fn write_executable(output: &str, contents: &str, name: &str) -> std::io::Result<()> {
+    let runtime = runtime_bytes()?;
+    let mut file = fs::File::create(output)?;
+    file.write_all(&runtime)?;
+    file.write_all(contents.as_bytes())?;
+    file.write_all(name.as_bytes())?;
+    file.write_all(&(contents.len() as u64).to_le_bytes())?;
+    file.write_all(&(name.len() as u64).to_le_bytes())?;
+    file.write_all(MAGIC)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(output, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+fn runtime_bytes() -> std::io::Result<Vec<u8>> {
+    // This executable without any script that was embedded in it
+    let mut runtime = fs::read(env::current_exe()?)?;
+    if let Some((contents, name)) = read_trailer(&mut std::io::Cursor::new(&runtime))? {
+        let embedded_length = contents.len() + name.len() + TRAILER_LENGTH as usize;
+        runtime.truncate(runtime.len() - embedded_length);
+    }
+    Ok(runtime)
+}
+
+pub fn embedded_script() -> Option<(String, String)> {
+    // Returns (script name, contents) if this executable was built with 'ari build'
+    let mut file = fs::File::open(env::current_exe().ok()?).ok()?;
+    read_trailer(&mut file).ok()?.map(|(contents, name)| (name, contents))
+}
+
+fn read_trailer<R: Read + Seek>(reader: &mut R) -> std::io::Result<Option<(String, String)>> {
+    let length = reader.seek(SeekFrom::End(0))?;
+    if length < TRAILER_LENGTH {
+        return Ok(None);
+    }
+    let mut trailer = [0u8; TRAILER_LENGTH as usize];
+    reader.seek(SeekFrom::End(-(TRAILER_LENGTH as i64)))?;
+    reader.read_exact(&mut trailer)?;
+    if &trailer[16..] != MAGIC {
+        return Ok(None);
+    }
+    let mut number = [0u8; 8];
+    number.copy_from_slice(&trailer[0..8]);
+    let contents_length = u64::from_le_bytes(number);
+    number.copy_from_slice(&trailer[8..16]);
+    let name_length = u64::from_le_bytes(number);
+    let embedded_length = contents_length + name_length;
+    if embedded_length > length - TRAILER_LENGTH {
+        return Ok(None);
+    }
+    reader.seek(SeekFrom::End(-((embedded_length + TRAILER_LENGTH) as i64)))?;
+    let mut contents = vec![0u8; contents_length as usize];
+    reader.read_exact(&mut contents)?;
+    let mut name = vec![0u8; name_length as usize];
+    reader.read_exact(&mut name)?;
+    Ok(Some((String::from_utf8_lossy(&contents).into_owned(), String::from_utf8_lossy(&name).into_owned())))
+}
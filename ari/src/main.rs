@@ -1,10 +1,37 @@
 use std::env;
 use ari_parser;
 use ari_errors;
+mod build;
+mod watch;
 
 fn main() {
+    // Scripts may nest and recurse deeply, so they run on a thread with a large stack
+    let handle = ari_parser::spawn_with_stack(ari_parser::MAIN_STACK_SIZE, run);
+    if handle.join().is_err() {
+        std::process::exit(ari_errors::EXIT_RUNTIME_ERROR);
+    }
+}
+
+fn run() {
     env::set_var("ROCKET_CLI_COLORS", "off");
-    let args: Vec<String> = env::args().collect();
+    if let Some((script_name, contents)) = build::embedded_script() {
+        ari_parser::run_embedded_script(&script_name, &contents);
+        ari_errors::exit();
+    }
+    let mut args: Vec<String> = env::args().collect();
+    let valid_color = take_option(&mut args, "--color=", |mode| mode.parse().map(ari_parser::set_color_mode).is_ok());
+    let valid_format = take_option(&mut args, "--error-format=", |format| format.parse().map(ari_parser::set_error_format).is_ok());
+    if !valid_color || !valid_format {
+        eprintln!("Usage: --color=[auto|always|never] --error-format=[human|json]");
+        ari_errors::exit_with_code(ari_errors::EXIT_USAGE);
+    }
+    let deny_warnings = args.iter().any(|arg| arg == "--deny-warnings");
+    args.retain(|arg| arg != "--deny-warnings");
+    ari_parser::set_deny_warnings(deny_warnings);
+    if !take_limits(&mut args) {
+        eprintln!("Usage: --max-call-depth=[count] --max-array-length=[count] --max-memory=[bytes] --timeout=[seconds], where 0 means unlimited");
+        ari_errors::exit_with_code(ari_errors::EXIT_USAGE);
+    }
     let arg_length = args.len();
     match arg_length {
         1 =>{
@@ -13,10 +40,134 @@ fn main() {
         2 =>{
             ari_parser::run_script(&args[1])
         },
+        3 if args[1] == "--dump-tokens" || args[1] == "--dump-ast" =>{
+            ari_parser::dump_script(&args[2], args[1] == "--dump-ast");
+            return;
+        },
+        3 if args[1] == "explain" =>{
+            match ari_errors::explain(&args[2]) {
+                Some(error_code) => println!("{}: {}\n\n{}", error_code.code, error_code.title, error_code.explanation),
+                None => {
+                    eprintln!("Unknown error code {}. Codes look like E0009.", args[2]);
+                    std::process::exit(ari_errors::EXIT_USAGE);
+                }
+            }
+            return;
+        },
+        3 if args[1] == "--trace" =>{
+            ari_parser::trace_script(&args[2]);
+        },
+        3 if args[1] == "--profile" =>{
+            ari_parser::profile_script(&args[2]);
+        },
+        3 if args[1] == "watch" =>{
+            watch::watch(&args[2]);
+            return;
+        },
+        3 if args[1] == "debug" =>{
+            ari_parser::debug_script(&args[2]);
+        },
+        3 if args[1] == "check" =>{
+            let valid = ari_parser::check_script(&args[2]);
+            std::process::exit(if valid { 0 } else { ari_errors::EXIT_SYNTAX_ERROR });
+        },
+        3 if args[1] == "fmt" =>{
+            let formatted = ari_parser::format_script(&args[2], false);
+            std::process::exit(if formatted { 0 } else { 1 });
+        },
+        4 if args[1] == "fmt" && args[2] == "--check" =>{
+            let formatted = ari_parser::format_script(&args[3], true);
+            std::process::exit(if formatted { 0 } else { 1 });
+        },
+        _ if args[1] == "lint" =>{
+            // ari lint [--allow rule]... script_name
+            let mut allowed = Vec::<String>::new();
+            let mut rest = args[2..].iter();
+            let mut script_name = None;
+            while let Some(arg) = rest.next() {
+                match (arg.as_str(), rest.len()) {
+                    ("--allow", n) if n > 0 => allowed.push(rest.next().unwrap().to_owned()),
+                    (_, 0) => script_name = Some(arg),
+                    _ => {}
+                }
+            }
+            match script_name {
+                Some(script_name) => {
+                    let clean = ari_parser::lint_script(script_name, &allowed);
+                    std::process::exit(if clean { 0 } else { 1 });
+                },
+                None => {
+                    eprintln!("Usage: ari lint [--allow rule]... [script_name]");
+                    std::process::exit(ari_errors::EXIT_USAGE);
+                }
+            }
+            return;
+        },
+        3 if args[1] == "compile" =>{
+            let compiled = ari_parser::compile_script(&args[2]);
+            std::process::exit(if compiled { 0 } else { ari_errors::EXIT_RUNTIME_ERROR });
+        },
+        _ if args[1] == "build" =>{
+            build::build(&args[2..]);
+            return;
+        },
         _ =>{
-            println!("Too many arguments!\nUsage: ari [script_name]")
+            eprintln!("Too many arguments!\nUsage: ari [script_name]\n       ari --dump-tokens [script_name]\n       ari --dump-ast [script_name]\n       ari --trace [script_name]\n       ari --profile [script_name]\n       ari debug [script_name]\n       ari watch [script_name]\n       ari check [script_name]\n       ari fmt [--check] [script_name]\n       ari lint [--allow rule]... [script_name]\n       ari compile [script_name]\n       ari explain [error_code]\n       ari build [script_name] -o [output]");
+            ari_errors::exit_with_code(ari_errors::EXIT_USAGE);
         }
     }
     ari_errors::exit();
 }
 
+fn take_option<F: FnMut(&str) -> bool>(args: &mut Vec<String>, prefix: &str, mut apply: F) -> bool {
+    // Removes '[prefix][value]' options, which can come before any command. False if a value is invalid
+    let mut valid = true;
+    args.retain(|arg| {
+        match arg.strip_prefix(prefix) {
+            Some(value) => {
+                valid &= apply(value);
+                false
+            },
+            None => true,
+        }
+    });
+    valid
+}
+
+fn take_limits(args: &mut Vec<String>) -> bool {
+    // Removes the limit options, which can come before any command. False if one is invalid
+    let mut limits = ari_parser::Limits::default();
+    let mut valid = true;
+    args.retain(|arg| {
+        let (option, value) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => (option, value),
+            _ => return true,
+        };
+        let parsed = match option {
+            "--max-call-depth" => parse_limit::<usize>(value).map(|limit| limits.max_call_depth = limit),
+            "--max-array-length" => parse_limit::<usize>(value).map(|limit| limits.max_array_length = limit),
+            "--max-memory" => parse_limit::<usize>(value).map(|limit| limits.max_memory = limit),
+            "--timeout" => parse_timeout(value).map(|limit| limits.timeout = limit),
+            _ => return true,
+        };
+        valid &= parsed.is_some();
+        false
+    });
+    ari_parser::set_limits(limits);
+    valid
+}
+
+fn parse_timeout(value: &str) -> Option<Option<std::time::Duration>> {
+    // Seconds as a float, where values which are negative, infinite or too large for a Duration are invalid
+    match parse_limit::<f64>(value)? {
+        Some(seconds) if seconds.is_finite() && seconds > 0.0 => std::time::Duration::try_from_secs_f64(seconds).ok().map(Some),
+        Some(_) => None,
+        None => Some(None),
+    }
+}
+
+fn parse_limit<T: std::str::FromStr + Default + PartialEq>(value: &str) -> Option<Option<T>> {
+    // Some(None) for 0, which means unlimited
+    let limit = value.parse::<T>().ok()?;
+    Some(if limit == T::default() { None } else { Some(limit) })
+}
@@ -0,0 +1,63 @@
+use std::env;
+use std::fs;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// 'ari watch' runs the script in a child process and restarts it whenever the script or one of its imports is modified.
+// A new process starts from a clean environment and frees the ports of any servers the script started.
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+pub fn watch(script_name: &str) {
+    if fs::metadata(script_name).is_err() {
+        ari_errors::print_file_not_found(script_name);
+        return;
+    }
+    let mut files = ari_parser::script_files(script_name);
+    let mut modified = modified_times(&files);
+    let mut child = spawn(script_name);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        if let Some(c) = child.as_mut() {
+            if let Ok(Some(_)) = c.try_wait() {
+                println!("[watch] {} finished, waiting for changes...", script_name);
+                child = None;
+            }
+        }
+        let current = modified_times(&files);
+        if current == modified {
+            continue;
+        }
+        if let Some(mut c) = child.take() {
+            let _ = c.kill();
+            let _ = c.wait();
+        }
+        println!("[watch] Change detected, restarting {}", script_name);
+        // Imports may have been added or removed
+        files = ari_parser::script_files(script_name);
+        modified = modified_times(&files);
+        child = spawn(script_name);
+    }
+}
+
+fn spawn(script_name: &str) -> Option<Child> {
+    let executable = match env::current_exe() {
+        Ok(executable) => executable,
+        Err(e) => {
+            println!("[watch] Failed to find the ari executable: {}", e);
+            return None;
+        }
+    };
+    match Command::new(executable).arg(script_name).spawn() {
+        Ok(child) => Some(child),
+        Err(e) => {
+            println!("[watch] Failed to run {}: {}", script_name, e);
+            None
+        }
+    }
+}
+
+fn modified_times(files: &[String]) -> Vec<Option<SystemTime>> {
+    // None for a file which is missing, such as while an editor replaces it
+    files.iter().map(|file| fs::metadata(file).and_then(|metadata| metadata.modified()).ok()).collect()
+}
@@ -0,0 +1,192 @@
+use crate::ErrorType;
+
+///////////////////////////////////////////
+// Error codes
+///////////////////////////////////////////
+// Every error has a stable code, shown as 'Error[E0009]' and explained by 'ari explain E0009'.
+// Syntax errors have one code per ErrorType. Errors with a custom message are raised with an ErrorClass,
+// which groups them into a code of their own.
+// Codes must never be reused or renumbered, only added.
+
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub explanation: &'static str, // Longer description with an example fix
+}
+
+pub const ERROR_CODES: &[ErrorCode] = &[
+    // Syntax errors
+    ErrorCode { code: "E0001", title: "Unknown character",
+        explanation: "The scanner found a character which is not part of the language.\n\n    let x = 1 @ 2;\n\nRemove the character or replace it with a valid operator:\n\n    let x = 1 + 2;" },
+    ErrorCode { code: "E0002", title: "Unterminated string",
+        explanation: "A string was opened with '\"' but never closed.\n\n    let name = \"Ari;\n\nClose the string on the same line:\n\n    let name = \"Ari\";" },
+    ErrorCode { code: "E0003", title: "Expected an expression",
+        explanation: "A value was expected, such as a number, string, variable or function call.\n\n    let x = ;\n\nGive the missing value:\n\n    let x = 0;" },
+    ErrorCode { code: "E0004", title: "Expected ']'",
+        explanation: "An array literal or array access was not closed.\n\n    let a = [1, 2;\n\nClose it with ']':\n\n    let a = [1, 2];" },
+    ErrorCode { code: "E0005", title: "Expected '('",
+        explanation: "'if', 'while', 'for' and function declarations need parentheses.\n\n    if x > 1 { println x; }\n\nWrap the condition in parentheses:\n\n    if (x > 1) { println x; }" },
+    ErrorCode { code: "E0006", title: "Expected ')'",
+        explanation: "A parenthesis was opened but not closed.\n\n    println (1 + 2;\n\nClose it with ')':\n\n    println (1 + 2);" },
+    ErrorCode { code: "E0007", title: "Expected '{'",
+        explanation: "A function body must be a block.\n\n    fn f() return 1;\n\nWrap the body in braces:\n\n    fn f() { return 1; }" },
+    ErrorCode { code: "E0008", title: "Expected '}'",
+        explanation: "A block was opened with '{' but not closed.\n\n    while (x < 3) { x = x + 1;\n\nClose it with '}':\n\n    while (x < 3) { x = x + 1; }" },
+    ErrorCode { code: "E0009", title: "Expected ';'",
+        explanation: "Statements end with a semicolon.\n\n    let x = 1\n\nAdd the semicolon:\n\n    let x = 1;" },
+    ErrorCode { code: "E0010", title: "Expected a variable name",
+        explanation: "'let' must be followed by the name of the variable.\n\n    let = 1;\n\nName the variable:\n\n    let x = 1;" },
+    ErrorCode { code: "E0011", title: "Expected a function name",
+        explanation: "'fn' must be followed by the name of the function.\n\n    fn (a) { return a; }\n\nName the function:\n\n    fn identity(a) { return a; }" },
+    ErrorCode { code: "E0012", title: "Expected a class name",
+        explanation: "'class' must be followed by the name of the class.\n\n    class { }\n\nName the class:\n\n    class Point { }" },
+    ErrorCode { code: "E0013", title: "Expected an argument name",
+        explanation: "Function arguments must be names.\n\n    fn f(1) { }\n\nUse a name for each argument:\n\n    fn f(x) { }" },
+    ErrorCode { code: "E0014", title: "Invalid assignment",
+        explanation: "Only variables and array elements can be assigned to.\n\n    1 + 2 = x;\n\nAssign to a variable instead:\n\n    x = 1 + 2;" },
+    ErrorCode { code: "E0015", title: "Invalid 'for' loop",
+        explanation: "A 'for' loop has an initialiser, a condition and an increment separated by ';'.\n\n    for (let i = 0, i < 3, i = i + 1) { }\n\nSeparate them with semicolons:\n\n    for (let i = 0; i < 3; i = i + 1) { }" },
+    ErrorCode { code: "E0016", title: "Too many arguments",
+        explanation: "Functions can have at most 255 arguments.\n\nPass the values in an array instead:\n\n    fn total(values) { return sum(values); }" },
+    ErrorCode { code: "E0017", title: "Missing array index",
+        explanation: "An array access needs an index.\n\n    println a[];\n\nGive the index:\n\n    println a[0];" },
+    ErrorCode { code: "E0018", title: "Comma in array index",
+        explanation: "An array access takes a single index.\n\n    println a[0, 1];\n\nAccess one element at a time:\n\n    println a[0];" },
+    ErrorCode { code: "E0019", title: "Expected a module path",
+        explanation: "'import' must be followed by the path of the module as a string.\n\n    import math;\n\nQuote the path:\n\n    import \"math.ari\";" },
+    ErrorCode { code: "E0020", title: "Expected a declaration after 'export'",
+        explanation: "Only functions and variables can be exported.\n\n    export println 1;\n\nExport a declaration:\n\n    export fn one() { return 1; }" },
+    ErrorCode { code: "E0021", title: "Invalid variable definition",
+        explanation: "A variable could not be defined with the given value.\n\n    let x = print 1;\n\nDefine it with an expression:\n\n    let x = 1;" },
+    ErrorCode { code: "E0022", title: "Invalid binary expression",
+        explanation: "A binary expression is incomplete.\n\n    let x = (1 + ;\n\nGive both operands:\n\n    let x = (1 + 2);" },
+    ErrorCode { code: "E0023", title: "Invalid unary expression",
+        explanation: "A unary expression is missing its operand.\n\n    let x = -;\n\nGive the operand:\n\n    let x = -1;" },
+    ErrorCode { code: "E0024", title: "Invalid grouping",
+        explanation: "A parenthesised expression is incomplete.\n\n    let x = ();\n\nPut an expression inside the parentheses:\n\n    let x = (1);" },
+    ErrorCode { code: "E0025", title: "Syntax error",
+        explanation: "The code is not valid Ari, such as a 'return' outside of a function.\n\n    return 1;\n\nMove the statement to where it is allowed:\n\n    fn one() { return 1; }" },
+    ErrorCode { code: "E0026", title: "Warnings denied",
+        explanation: "The script was run with --deny-warnings, and it has warnings such as an unused variable.\n\n    let unused = 1;\n\nFix each warning, such as by removing the variable or starting its name with '_':\n\n    let _unused = 1;" },
+    ErrorCode { code: "E0027", title: "Unterminated comment",
+        explanation: "A block comment was opened with '/*' but never closed. Block comments can be nested, so every '/*' needs its own '*/'.\n\n    /* outer /* inner */\n\nClose each comment:\n\n    /* outer /* inner */ */" },
+    ErrorCode { code: "E0028", title: "Invalid number",
+        explanation: "A number literal has a digit which is not allowed by its prefix, or no digits at all.\n\n    let mask = 0b102;\n\nUse 0-1 after '0b', 0-7 after '0o' and 0-9 or a-f after '0x':\n\n    let mask = 0b101;" },
+
+    // Runtime errors
+    ErrorCode { code: "E0100", title: "Runtime error",
+        explanation: "An error which does not belong to a more specific class was raised while the script was running.\nThe message describes what went wrong." },
+    ErrorCode { code: "E0101", title: "Undefined variable",
+        explanation: "A variable was used before it was defined, or its name is misspelled.\n\n    println totl;\n\nDefine the variable or fix its name:\n\n    let total = 0;\n    println total;" },
+    ErrorCode { code: "E0102", title: "Mismatched types",
+        explanation: "An operator was applied to values of types which it does not support, or to arrays of different types or sizes.\n\n    println 1 + [2, 3];\n\nConvert the values to compatible types first:\n\n    println to_string(1) + \"2\";" },
+    ErrorCode { code: "E0103", title: "Invalid argument to a native function",
+        explanation: "A native function received an argument of the wrong type or value.\n\n    println length(5);\n\nPass the type which the function expects:\n\n    println length([5]);" },
+    ErrorCode { code: "E0104", title: "Wrong number of arguments",
+        explanation: "A function was called with more or fewer arguments than it declares.\n\n    fn add(a, b) { return a + b; }\n    println add(1);\n\nPass every argument:\n\n    println add(1, 2);" },
+    ErrorCode { code: "E0105", title: "Value is not callable",
+        explanation: "Only functions can be called.\n\n    let x = 1;\n    x();\n\nCall a function instead, or check that the variable holds one:\n\n    fn x() { return 1; }\n    x();" },
+    ErrorCode { code: "E0106", title: "Invalid array index",
+        explanation: "Array indices must be non-negative integers smaller than the length of the array, and only arrays can be indexed.\n\n    let a = [1, 2];\n    println a[2];\n\nUse an index within the array:\n\n    println a[1];" },
+    ErrorCode { code: "E0107", title: "Division by zero",
+        explanation: "A number was divided by zero.\n\n    println 1 / 0;\n\nCheck the divisor first:\n\n    if (d != 0) { println 1 / d; }" },
+    ErrorCode { code: "E0108", title: "Module could not be imported",
+        explanation: "The imported module does not exist, or modules import each other in a circle.\n\n    import \"utils.ari\";\n\nCheck that the path is relative to the directory ari was started in, and move shared code into a third module to break circular imports." },
+    ErrorCode { code: "E0109", title: "Maximum call depth exceeded",
+        explanation: "Functions called each other too deeply, which is usually infinite recursion.\n\n    fn f(n) { return f(n + 1); }\n\nAdd a base case which stops the recursion:\n\n    fn f(n) { if (n > 10) { return n; } return f(n + 1); }\n\nThe limit can be raised with --max-call-depth=[count]." },
+    ErrorCode { code: "E0110", title: "Array too long",
+        explanation: "A function returned an array longer than the limit set with --max-array-length=[count].\n\n    let a = range(0, 100000000, 1);\n\nCreate smaller arrays, or raise the limit." },
+    ErrorCode { code: "E0111", title: "Timed out",
+        explanation: "The script ran for longer than the limit set with --timeout=[seconds].\n\n    while (true) { }\n\nMake sure that loops end, or raise the limit." },
+    ErrorCode { code: "E0112", title: "File not found",
+        explanation: "The script or file does not exist.\n\n    ari scrpt.ari\n\nCheck the path, which is relative to the directory ari was started in." },
+    ErrorCode { code: "E0113", title: "Memory limit exceeded",
+        explanation: "A string or array grew larger than the limit set with --max-memory=[bytes], which applies to each value on its own.\n\n    let s = \"-\" * 1000000000;\n\nBuild smaller values, or raise the limit." },
+];
+
+impl ErrorType {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorType::UnknownToken => "E0001",
+            ErrorType::ConsumeStringLexeme => "E0002",
+            ErrorType::ExpectExpression => "E0003",
+            ErrorType::ExpectRightBracket => "E0004",
+            ErrorType::ExpectLeftParen => "E0005",
+            ErrorType::ExpectRightParen => "E0006",
+            ErrorType::ExpectLeftBrace => "E0007",
+            ErrorType::ExpectRightBrace => "E0008",
+            ErrorType::ExpectSemicolon => "E0009",
+            ErrorType::ExpectVariableName => "E0010",
+            ErrorType::ExpectFunctionName => "E0011",
+            ErrorType::ExpectClassName => "E0012",
+            ErrorType::ExpectArgumentName => "E0013",
+            ErrorType::InvalidAssignment => "E0014",
+            ErrorType::InvalidForLoop => "E0015",
+            ErrorType::TooManyArguments => "E0016",
+            ErrorType::NoArrayAccessIndex => "E0017",
+            ErrorType::ArrayAccessComma => "E0018",
+            ErrorType::ExpectModulePath => "E0019",
+            ErrorType::ExpectExportDeclaration => "E0020",
+            ErrorType::InvalidVariableDefinition => "E0021",
+            ErrorType::EvalExprBinary => "E0022",
+            ErrorType::EvalExprUnary => "E0023",
+            ErrorType::EvalExprGrouping => "E0024",
+            ErrorType::UnterminatedComment => "E0027",
+            ErrorType::InvalidNumber => "E0028",
+        }
+    }
+}
+
+pub const SYNTAX_ERROR: &str = "E0025";
+pub const DENIED_WARNINGS: &str = "E0026";
+pub const RUNTIME_ERROR: &str = "E0100";
+pub const FILE_NOT_FOUND: &str = "E0112";
+
+// Class of an error raised with a custom message, chosen where it is raised
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub enum ErrorClass {
+    Syntax,
+    Runtime, // Any error without a more specific class
+    UndefinedVariable,
+    MismatchedTypes,
+    InvalidArgument, // To a native function
+    WrongArgumentCount,
+    NotCallable,
+    InvalidIndex,
+    DivisionByZero,
+    ImportFailed,
+    CallDepth,
+    ArrayTooLong,
+    TimedOut,
+    FileNotFound,
+    MemoryLimit,
+}
+
+impl ErrorClass {
+    pub fn code(&self, parsing: bool) -> &'static str {
+        match self {
+            ErrorClass::Syntax => SYNTAX_ERROR,
+            // Errors raised while a script is parsed, such as by an import, stop it from running at all
+            ErrorClass::Runtime => if parsing { SYNTAX_ERROR } else { RUNTIME_ERROR },
+            ErrorClass::UndefinedVariable => "E0101",
+            ErrorClass::MismatchedTypes => "E0102",
+            ErrorClass::InvalidArgument => "E0103",
+            ErrorClass::WrongArgumentCount => "E0104",
+            ErrorClass::NotCallable => "E0105",
+            ErrorClass::InvalidIndex => "E0106",
+            ErrorClass::DivisionByZero => "E0107",
+            ErrorClass::ImportFailed => "E0108",
+            ErrorClass::CallDepth => "E0109",
+            ErrorClass::ArrayTooLong => "E0110",
+            ErrorClass::TimedOut => "E0111",
+            ErrorClass::FileNotFound => FILE_NOT_FOUND,
+            ErrorClass::MemoryLimit => "E0113",
+        }
+    }
+}
+
+pub fn explain(code: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES.iter().find(|error_code| error_code.code.eq_ignore_ascii_case(code))
+}
@@ -4,15 +4,216 @@ use std::sync::Mutex;
 #[macro_use]
 extern crate lazy_static;
 
+mod codes;
+pub use codes::{ErrorClass, ErrorCode, ERROR_CODES, explain};
+
 lazy_static! {
     pub static ref SCRIPT: Mutex<bool> = {Mutex::new(true)}; // Check if running script or interpreter
     pub static ref BORDER_LENGTH: Mutex<usize> = Mutex::new(0);
+    pub static ref LIBRARY: Mutex<bool> = Mutex::new(false); // Errors are returned to the embedding application instead of exiting
+    pub static ref OUTPUT: Mutex<Option<String>> = Mutex::new(None); // Captures print/println when set
     //pub static ref IS_WEB: bool = false; // A reminder of possbily using WASM for running on the web
+    static ref IO: Mutex<Box<dyn Io>> = Mutex::new(Box::new(StdIo)); // Replaced with set_io(), such as by an embedding application
+    static ref PARSING: Mutex<bool> = Mutex::new(false); // Errors raised while scanning, parsing or resolving are syntax errors
+    static ref ERROR_FORMAT: Mutex<ErrorFormat> = Mutex::new(ErrorFormat::Human);
+    static ref SCRIPT_NAME: Mutex<Option<String>> = Mutex::new(None); // File of diagnostics which are not tied to a token
+    static ref COLOR_MODE: Mutex<ColorMode> = Mutex::new(ColorMode::Auto);
+    static ref DENY_WARNINGS: Mutex<bool> = Mutex::new(false); // Set by --deny-warnings
+    static ref WARNING_COUNT: Mutex<usize> = Mutex::new(0);
+    static ref EXIT_HOOKS: Mutex<Vec<fn()>> = Mutex::new(Vec::new()); // Run once by exit(), such as for the profiler report
+}
+
+// Printed output, diagnostics, the debugger's input, and the script files and read_file()/write_file()
+// go through an Io backend, so that an embedding application can redirect them.
+// The other file natives, the interpreter prompt and the command line tools use std directly.
+pub trait Io: Send {
+    fn write_output(&mut self, s: &str);
+    fn write_coloured(&mut self, s: &str, _color: Color, _bold: bool) {
+        // Backends without colour support print plain text
+        self.write_output(s);
+    }
+    fn write_error(&mut self, s: &str) {
+        // Backends without a separate error stream show diagnostics with the output
+        self.write_output(s);
+    }
+    fn write_error_coloured(&mut self, s: &str, color: Color, bold: bool) {
+        self.write_coloured(s, color, bold);
+    }
+    fn read_line(&mut self) -> Option<String>;
+    fn read_file(&mut self, path: &str) -> io::Result<String>;
+    fn write_file(&mut self, path: &str, contents: &str) -> io::Result<()>;
+}
+
+// Whether the terminal output is coloured, set with '--color=auto/always/never'
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    Auto, // Coloured only when stdout is a terminal and NO_COLOR is not set
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = ();
+    fn from_str(s: &str) -> Result<ColorMode, ()> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn set_color_mode(mode: ColorMode) {
+    *COLOR_MODE.lock().unwrap() = mode;
+}
+
+fn color_choice(is_terminal: bool) -> ColorChoice {
+    // See https://no-color.org, although an explicit '--color=always' still wins
+    match *COLOR_MODE.lock().unwrap() {
+        ColorMode::Always => ColorChoice::Always,
+        ColorMode::Never => ColorChoice::Never,
+        ColorMode::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+            if no_color || !is_terminal {
+                ColorChoice::Never
+            }
+            else {
+                ColorChoice::Auto
+            }
+        }
+    }
+}
+
+fn write_with_colour(stream: &mut StandardStream, s: &str, color: Color, bold: bool) {
+    let mut spec = ColorSpec::new();
+    spec.set_fg(Some(color));
+    if bold {
+        spec.set_bold(true);
+    }
+    stream.set_color(&spec).unwrap();
+    write!(stream, "{}", s).unwrap();
+}
+
+// How errors and warnings are shown, set with '--error-format=human/json'
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorFormat {
+    Human, // Coloured text pointing at the source
+    Json, // One JSON object per line, for editors and CI tools
+}
+
+impl std::str::FromStr for ErrorFormat {
+    type Err = ();
+    fn from_str(s: &str) -> Result<ErrorFormat, ()> {
+        match s {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn set_error_format(format: ErrorFormat) {
+    *ERROR_FORMAT.lock().unwrap() = format;
+}
+
+pub fn set_script_name(name: &str) {
+    *SCRIPT_NAME.lock().unwrap() = Some(name.to_owned());
+}
+
+// Default backend using the terminal and the file system
+pub struct StdIo;
+
+impl Io for StdIo {
+    fn write_output(&mut self, s: &str) {
+        print!("{}", s);
+    }
+    fn write_coloured(&mut self, s: &str, color: Color, bold: bool) {
+        let mut stdout = StandardStream::stdout(color_choice(io::stdout().is_terminal()));
+        write_with_colour(&mut stdout, s, color, bold);
+    }
+    fn write_error(&mut self, s: &str) {
+        eprint!("{}", s);
+    }
+    fn write_error_coloured(&mut self, s: &str, color: Color, bold: bool) {
+        let mut stderr = StandardStream::stderr(color_choice(io::stderr().is_terminal()));
+        write_with_colour(&mut stderr, s, color, bold);
+    }
+    fn read_line(&mut self) -> Option<String> {
+        let mut input_line = String::new();
+        match io::stdin().read_line(&mut input_line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(input_line),
+        }
+    }
+    fn read_file(&mut self, path: &str) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+    fn write_file(&mut self, path: &str, contents: &str) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+}
+
+pub fn set_io(io: Box<dyn Io>) {
+    *IO.lock().unwrap() = io;
+}
+pub fn read_line() -> Option<String> {
+    IO.lock().unwrap().read_line()
+}
+pub fn read_file(path: &str) -> io::Result<String> {
+    IO.lock().unwrap().read_file(path)
+}
+pub fn write_file(path: &str, contents: &str) -> io::Result<()> {
+    IO.lock().unwrap().write_file(path, contents)
 }
 
 // For colourful terminal
-use std::io::Write;
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use std::io::{self, IsTerminal, Write};
+pub use termcolor::Color;
+use termcolor::{ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+// Exit codes, following the BSD sysexits convention
+pub const EXIT_USAGE: i32 = 64;
+pub const EXIT_SYNTAX_ERROR: i32 = 65;
+pub const EXIT_FILE_NOT_FOUND: i32 = 66;
+pub const EXIT_RUNTIME_ERROR: i32 = 70;
+
+pub fn set_parsing(parsing: bool) -> bool {
+    // Returns the previous state, since modules are parsed while their importer is being parsed
+    std::mem::replace(&mut *PARSING.lock().unwrap(), parsing)
+}
+
+// Raised by print_custom_error() when running as a library
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub code: &'static str, // Such as E0009, see 'ari explain'
+    pub message: String,
+    pub file: String, // Empty if the code did not come from a file
+    pub line_number: usize, // 0 if the error is not tied to a line
+    pub column: usize,
+    pub source: String, // Line of code which caused the error
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.line_number == 0 {
+            write!(f, "[{}] {}", self.code, self.message)
+        }
+        else {
+            write!(f, "[{}] {} at {}", self.code, self.message, location(&self.file, self.line_number, self.column))
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Raised by exit() when running as a library, such as after 'bai'
+#[derive(Debug)]
+pub struct Exit;
+
+pub fn is_library() -> bool {
+    *LIBRARY.lock().unwrap()
+}
 
 #[derive(Debug)]
 pub enum ErrorType{
@@ -20,6 +221,8 @@ pub enum ErrorType{
     // Scanner
     UnknownToken,
     ConsumeStringLexeme,
+    UnterminatedComment,
+    InvalidNumber,
 
     // Parser
     ExpectExpression,
@@ -38,6 +241,8 @@ pub enum ErrorType{
     TooManyArguments,
     NoArrayAccessIndex,
     ArrayAccessComma,
+    ExpectModulePath,
+    ExpectExportDeclaration,
 
     // evaluate_statement() in ast.rs
     InvalidVariableDefinition,
@@ -52,6 +257,10 @@ pub enum ErrorType{
 }
 
 pub fn print_error(context:ErrorType, source:&str, index:usize, line_number:usize,){
+    print_error_span(context, "", source, index, index, line_number);
+}
+
+pub fn print_error_span(context:ErrorType, file:&str, source:&str, start:usize, end:usize, line_number:usize){
     let error_name = match context{
 
         // Scanner
@@ -61,6 +270,12 @@ pub fn print_error(context:ErrorType, source:&str, index:usize, line_number:usiz
         ErrorType::ConsumeStringLexeme => {
             "Error parsing (Unterminated string)"
         },
+        ErrorType::UnterminatedComment => {
+            "Error parsing (Unterminated comment)"
+        },
+        ErrorType::InvalidNumber => {
+            "Error parsing (Invalid number)"
+        },
 
         // Parser
         ErrorType::ExpectExpression => {
@@ -111,6 +326,12 @@ pub fn print_error(context:ErrorType, source:&str, index:usize, line_number:usiz
         ErrorType::ArrayAccessComma => {
             "Unwanted comma found at array index"
         },
+        ErrorType::ExpectModulePath => {
+            "Expect module path after 'import'"
+        },
+        ErrorType::ExpectExportDeclaration => {
+            "Expect 'fn' or 'let' after 'export'"
+        },
 
         // evaluate_statement() in ast.rs
         ErrorType::InvalidVariableDefinition => {
@@ -127,31 +348,154 @@ pub fn print_error(context:ErrorType, source:&str, index:usize, line_number:usiz
         ErrorType::EvalExprGrouping => {
             "Expect ')' after expression"
         },
-        
-        _ => {
-            panic!("{:?} not implemented in ari_errors.", context);
-        }
+
 
     };
-    print_custom_error(error_name, source, index, line_number);
+    print_coded_error(context.code(), error_name, file, source, start, end, line_number);
 }
 
-pub fn print_custom_error(message:&str, source:&str, index:usize, line_number:usize){
-    let line_number_len = line_number.to_string().len();
-    let left_spacing = format!("     {} |", (0..line_number_len).map(|_| " ").collect::<String>());
-    let pointer_spacing = (0..index - 1).map(|_| " ").collect::<String>();
-    print_red("\nError: ", false, true);
-    print_white(&format!(": {} at line {}\n{}", message, line_number, left_spacing), true, true);
-    print_yellow(&format!("{} {}", "Line", line_number), false, true);
-    print_white(&format!(" |\t{}\n{}\t{}^", source, left_spacing, pointer_spacing), true, true);
-    exit();
+pub fn print_custom_error(class:ErrorClass, message:&str, source:&str, index:usize, line_number:usize){
+    print_custom_error_span(class, message, "", source, index, index, line_number);
+}
+
+pub fn print_custom_error_span(class:ErrorClass, message:&str, file:&str, source:&str, start:usize, end:usize, line_number:usize){
+    // Underlines the 1-based columns from start to end
+    let parsing = *PARSING.lock().unwrap();
+    print_coded_error(class.code(parsing), message, file, source, start, end, line_number);
+}
+
+fn print_coded_error(code:&'static str, message:&str, file:&str, source:&str, start:usize, end:usize, line_number:usize){
+    if is_library() {
+        std::panic::panic_any(Error { code, message: message.to_owned(), file: file.to_owned(), line_number, column: start.max(1), source: source.to_owned() });
+    }
+    if *ERROR_FORMAT.lock().unwrap() == ErrorFormat::Json {
+        print_json_diagnostic("error", Some(code), message, Some(file), source, start, end, line_number);
+    }
+    else {
+        print_diagnostic(&format!("\nError[{}]", code), Color::Red, false, true);
+        print_source_pointer(message, file, source, start, end, line_number);
+    }
+    let parsing = *PARSING.lock().unwrap();
+    exit_with_code(if parsing { EXIT_SYNTAX_ERROR } else { EXIT_RUNTIME_ERROR });
     // Make sure to print in white before exiting.
     // Otherwise, the terminal colour is permanently affected even after the program exits.
 }
 
+pub fn print_warning(message:&str, file:&str, source:&str, index:usize, line_number:usize){
+    // Same as print_custom_error(), but the program keeps running
+    *WARNING_COUNT.lock().unwrap() += 1;
+    if *ERROR_FORMAT.lock().unwrap() == ErrorFormat::Json {
+        print_json_diagnostic("warning", None, message, Some(file), source, index, index, line_number);
+        return;
+    }
+    print_diagnostic("\nWarning", Color::Yellow, false, true);
+    print_source_pointer(message, file, source, index, index, line_number);
+}
+
+pub fn set_deny_warnings(deny: bool) {
+    *DENY_WARNINGS.lock().unwrap() = deny;
+}
+
+pub fn check_denied_warnings() {
+    // Stops before the script runs if any warnings were printed with --deny-warnings
+    let count = *WARNING_COUNT.lock().unwrap();
+    if count == 0 || !*DENY_WARNINGS.lock().unwrap() {
+        return;
+    }
+    let message = format!("{} warning{} denied by --deny-warnings", count, if count == 1 { "" } else { "s" });
+    if is_library() {
+        std::panic::panic_any(Error { code: codes::DENIED_WARNINGS, message, file: String::new(), line_number: 0, column: 0, source: String::new() });
+    }
+    if *ERROR_FORMAT.lock().unwrap() == ErrorFormat::Json {
+        print_json_diagnostic("error", Some(codes::DENIED_WARNINGS), &message, None, "", 0, 0, 0);
+    }
+    else {
+        print_diagnostic(&format!("\nError[{}]: ", codes::DENIED_WARNINGS), Color::Red, false, true);
+        print_diagnostic(&message, Color::White, true, true);
+    }
+    exit_with_code(EXIT_SYNTAX_ERROR);
+}
+
+fn print_json_diagnostic(severity:&str, code:Option<&str>, message:&str, file:Option<&str>, source:&str, start:usize, end:usize, line_number:usize){
+    // Line, column and span are null for errors which are not tied to a line.
+    // Without a file, such as for errors which are not raised by a token, the running script is assumed.
+    let file = match file {
+        Some(file) if file.is_empty() => None,
+        Some(file) => Some(file.to_owned()),
+        None => SCRIPT_NAME.lock().unwrap().clone(),
+    };
+    let (line, column, span) = if line_number == 0 {
+        (serde_json::Value::Null, serde_json::Value::Null, serde_json::Value::Null)
+    }
+    else {
+        (line_number.into(), start.max(1).into(), serde_json::json!({ "start": start.max(1), "end": end.max(start).max(1) }))
+    };
+    let diagnostic = serde_json::json!({
+        "severity": severity,
+        "code": code,
+        "message": message,
+        "file": file,
+        "line": line,
+        "column": column,
+        "span": span,
+        "source": source,
+    });
+    IO.lock().unwrap().write_error(&format!("{}\n", diagnostic));
+}
+
+fn location(file:&str, line_number:usize, column:usize) -> String {
+    // Such as 'script.ari:3:7', or 'line 3' for code typed into the interpreter
+    if file.is_empty() {
+        format!("line {}", line_number)
+    }
+    else {
+        format!("{}:{}:{}", file, line_number, column.max(1))
+    }
+}
+
+fn print_source_pointer(message:&str, file:&str, source:&str, start:usize, end:usize, line_number:usize){
+    let line_number_len = line_number.to_string().len();
+    let left_spacing = format!("     {} |", (0..line_number_len).map(|_| " ").collect::<String>());
+    let pointer_spacing = (0..start.max(1) - 1).map(|_| " ").collect::<String>();
+    let underline = "^".repeat(end.saturating_sub(start) + 1);
+    print_diagnostic(&format!(": {} at {}\n{}", message, location(file, line_number, start), left_spacing), Color::White, true, true);
+    print_diagnostic(&format!("{} {}", "Line", line_number), Color::Yellow, false, true);
+    print_diagnostic(&format!(" |\t{}\n{}\t{}{}", source, left_spacing, pointer_spacing, underline), Color::White, true, true);
+}
+
 pub fn print_simple_error(message: &str) {
-    println!("{}", message);
-    exit();
+    if is_library() {
+        std::panic::panic_any(Error { code: codes::RUNTIME_ERROR, message: message.to_owned(), file: String::new(), line_number: 0, column: 0, source: String::new() });
+    }
+    if *ERROR_FORMAT.lock().unwrap() == ErrorFormat::Json {
+        print_json_diagnostic("error", Some(codes::RUNTIME_ERROR), message, None, "", 0, 0, 0);
+    }
+    else {
+        IO.lock().unwrap().write_error(&format!("Error[{}]: {}\n", codes::RUNTIME_ERROR, message));
+    }
+    exit_with_code(EXIT_RUNTIME_ERROR);
+}
+
+pub fn print_file_not_found(path: &str) {
+    if is_library() {
+        std::panic::panic_any(Error { code: codes::FILE_NOT_FOUND, message: format!("{} does not exist", path), file: path.to_owned(), line_number: 0, column: 0, source: String::new() });
+    }
+    if *ERROR_FORMAT.lock().unwrap() == ErrorFormat::Json {
+        print_json_diagnostic("error", Some(codes::FILE_NOT_FOUND), &format!("{} does not exist", path), Some(path), "", 0, 0, 0);
+    }
+    else {
+        print_diagnostic(&format!("Error[{}]: ", codes::FILE_NOT_FOUND), Color::Red, false, true);
+        print_diagnostic(&format!("{} does not exist.", path), Color::White, true, true);
+    }
+    exit_with_code(EXIT_FILE_NOT_FOUND);
+}
+
+pub fn print_output(s: &str) {
+    // Output of print/println, which the embedding application may capture
+    match OUTPUT.lock().unwrap().as_mut() {
+        Some(output) => output.push_str(s),
+        None => IO.lock().unwrap().write_output(s),
+    }
 }
 
 pub fn print_white(s: &str, newline: bool, bold: bool) {
@@ -170,34 +514,48 @@ pub fn print_yellow(s: &str, newline: bool, bold: bool) {
 }
 
 pub fn print_colour(s: &str, color: Color, newline: bool, bold: bool) {
-    let mut stdout = StandardStream::stdout(ColorChoice::Always);
-    let mut spec = ColorSpec::new();
-    spec.set_fg(Some(color));
-    if bold {
-        spec.set_bold(true);
-    }
-    stdout.set_color(&spec).unwrap();
+    let mut io = IO.lock().unwrap();
+    io.write_coloured(s, color, bold);
     if newline {
-        writeln!(&mut stdout, "{}", s).unwrap();
+        io.write_output("\n");
     }
-    else {
-        write!(&mut stdout, "{}", s).unwrap();
+}
+
+fn print_diagnostic(s: &str, color: Color, newline: bool, bold: bool) {
+    // Errors and warnings go to stderr, so they are kept apart from the script's output
+    let mut io = IO.lock().unwrap();
+    io.write_error_coloured(s, color, bold);
+    if newline {
+        io.write_error("\n");
     }
 }
 
-use std::io;
+pub fn add_exit_hook(hook: fn()) {
+    EXIT_HOOKS.lock().unwrap().push(hook);
+}
+
 pub fn exit() {
+    exit_with_code(0);
+}
+
+pub fn exit_with_code(code: i32) {
+    if is_library() {
+        std::panic::panic_any(Exit);
+    }
+    let hooks = std::mem::take(&mut *EXIT_HOOKS.lock().unwrap());
+    for hook in hooks {
+        hook();
+    }
     let len_ref : &usize = &BORDER_LENGTH.lock().unwrap();
     let lower = (0..*len_ref).map(|_| "_").collect::<String>();
-    println!("");
+    print_output("\n");
     print_green(&lower, true, true);
     print_white("", false, false);
     let script_ref : &bool = &SCRIPT.lock().unwrap();
     if !script_ref {
         // Is running interpreter
-        println!("Press Enter to exit.");
-        let mut input_line = String::new();
-        io::stdin().read_line(&mut input_line);
+        print_output("Press Enter to exit.\n");
+        read_line();
     }
-    process::exit(0);
+    process::exit(code);
 }
\ No newline at end of file
@@ -1,8 +1,26 @@
 use crate::token;
 use crate::function as func;
 use crate::environment::ENV;
+use crate::debugger;
+use crate::trace;
+use crate::profile;
+use crate::limits;
+use ari_errors::ErrorClass;
 use ari_errors;
 use rayon::prelude::*; // For array operations/fast parallelism
+use serde::{Serialize, Deserialize}; // For caching compiled scripts
+use std::cmp::Ordering;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+
+static DISPLAY_LIMIT: AtomicUsize = AtomicUsize::new(5); // Maximum elements printed of each array, 0 for all of them
+
+pub fn set_display_limit(limit: usize) {
+    DISPLAY_LIMIT.store(limit, atomic::Ordering::SeqCst);
+}
+pub fn display_limit() -> usize {
+    DISPLAY_LIMIT.load(atomic::Ordering::SeqCst)
+}
 
 ///////////////////////////////////////////
 // Literals
@@ -11,6 +29,7 @@ use rayon::prelude::*; // For array operations/fast parallelism
 #[derive(Debug)]
 #[derive(Clone, Copy)]
 #[derive(PartialEq)] // For equality comparisons
+#[derive(Serialize, Deserialize)]
 pub enum LiteralType {
     // 'value'
     None,
@@ -27,19 +46,82 @@ pub enum LiteralType {
     // Loop commands, uses no fields
     Break,
     Continue,
+
+    // Returned by 'return f(x);' instead of the result of the call, which is made by Function::call_bound()
+    TailCall,
     
 }
 
+// Value of a Number literal, kept next to its text so that arithmetic does not parse it again
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub enum Number {
+    Integer(i64), // Exact, such as indices, counts and '9007199254740993'
+    Float(f64), // Written with a decimal point or exponent, or the result of '/' and most math functions
+}
+
+impl Number {
+    pub fn parse(text: &str) -> Option<Number> {
+        // Integers which fit in an i64 stay exact, anything else is a float
+        if let Ok(integer) = text.parse::<i64>() {
+            return Some(Number::Integer(integer));
+        }
+        text.parse::<f64>().ok().map(Number::Float)
+    }
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Number::Integer(integer) => integer as f64,
+            Number::Float(float) => float,
+        }
+    }
+    pub fn to_integer(self) -> Option<i64> {
+        // None if the number has a fractional part or does not fit in an i64
+        match self {
+            Number::Integer(integer) => Some(integer),
+            Number::Float(float) if float.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(&float) => Some(float as i64),
+            Number::Float(_) => None,
+        }
+    }
+    pub fn to_text(self) -> String {
+        // A float always shows a decimal point, so '2.0' is not mistaken for the integer 2
+        match self {
+            Number::Integer(integer) => integer.to_string(),
+            Number::Float(float) => {
+                let text = float.to_string();
+                if float.is_finite() && !text.contains('.') { text + ".0" } else { text }
+            }
+        }
+    }
+}
+
+macro_rules! number_from {
+    ($variant:ident, $($source:ty),*) => {
+        $(impl From<$source> for Number {
+            fn from(value: $source) -> Number {
+                Number::$variant(value as _)
+            }
+        })*
+    };
+}
+number_from!(Integer, i64, i32, u8, u16, u32, usize);
+number_from!(Float, f64, f32);
+
+#[derive(Serialize, Deserialize)]
 pub struct Literal {
     pub literal_type : LiteralType,
     pub value : String,
+    pub number: Number, // Number only, the value of 'value'
 
-    // Array
-    pub array_values: Vec<Literal>,
+    // Array, shared by every copy of the Literal until one of them modifies it with Arc::make_mut()
+    pub array_values: Arc<Vec<Literal>>,
 
     // Function
+    #[serde(skip)] // Functions only exist at runtime
     pub function: Option<func::Function>,
     pub is_return: bool, // Must be manually modified
+    pub is_frozen: bool, // Set by freeze(), the variable holding it cannot be reassigned or index-assigned
 }
 
 impl Clone for Literal { // Enables Literal to be copied
@@ -47,9 +129,11 @@ impl Clone for Literal { // Enables Literal to be copied
         Literal {
             literal_type: self.literal_type,
             value: self.value.clone(),
+            number: self.number,
             array_values: self.array_values.clone(),
             function: self.function.clone(),
             is_return: self.is_return,
+            is_frozen: self.is_frozen,
         }
     }
 }
@@ -59,9 +143,11 @@ impl Literal {
         Literal {
             literal_type,
             value,
-            array_values,
+            number: Number::Integer(0),
+            array_values: Arc::new(array_values),
             function,
             is_return,
+            is_frozen: false,
         }
     }
 
@@ -72,8 +158,11 @@ impl Literal {
     pub fn none() -> Literal {
         Literal::new_value(LiteralType::None, "".to_string())
     }
-    pub fn number(value: String) -> Literal {
-        Literal::new_value(LiteralType::Number, value)
+    pub fn number(value: impl Into<Number>) -> Literal {
+        let number = value.into();
+        let mut literal = Literal::new_value(LiteralType::Number, number.to_text());
+        literal.number = number;
+        literal
     }
     pub fn string(value: String) -> Literal {
         Literal::new_value(LiteralType::String, value)
@@ -95,7 +184,89 @@ impl Literal {
         Literal::new(LiteralType::Function, "".to_string(), Vec::<Literal>::new(), Some(function), false)
     }
 
+    pub fn array_mut(&mut self) -> &mut Vec<Literal> {
+        // Copies the elements first if another Literal shares them
+        Arc::make_mut(&mut self.array_values)
+    }
+    pub fn into_array(self) -> Vec<Literal> {
+        // Takes the elements without copying them, unless another Literal shares them
+        Arc::try_unwrap(self.array_values).unwrap_or_else(|shared| shared.to_vec())
+    }
+
+    pub fn set_frozen(&mut self, is_frozen: bool) {
+        // Elements of arrays too, so 'm[1][2] = 0' fails on a frozen matrix
+        self.is_frozen = is_frozen;
+        for value in self.array_mut().iter_mut() {
+            value.set_frozen(is_frozen);
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        // Readable text of any value, such as '[1, "a", [true]]', for to_string()
+        match self.literal_type {
+            LiteralType::Array => {
+                let values: Vec<String> = self.array_values.iter().map(|value| {
+                    match value.literal_type {
+                        LiteralType::String => format!("\"{}\"", value.value), // Quoted, so '["1"]' differs from '[1]'
+                        _ => value.to_text(),
+                    }
+                }).collect();
+                format!("[{}]", values.join(", "))
+            },
+            LiteralType::Function => {
+                let function = self.function.as_ref().unwrap();
+                let kind = if function.is_native() { "native fn" } else { "fn" };
+                format!("<{} {}>", kind, function.name())
+            },
+            LiteralType::None => "null".to_string(),
+            _ => self.value.to_owned(),
+        }
+    }
+
+    pub fn display(&self, limit: usize) -> String {
+        // Text printed by 'print', such as 'Number(3) => [1, 2, 3]'
+        // Arrays, including nested ones, show at most 'limit' elements each, or all of them if it is 0
+        if self.literal_type != LiteralType::Array {
+            return self.display_element(limit);
+        }
+        let length = self.array_values.len();
+        if length == 0 {
+            return "[]".to_string();
+        }
+        let first_type = self.array_values[0].literal_type;
+        let header = if self.array_values.iter().all(|value| value.literal_type == first_type) {
+            format!("{:?}({})", first_type, length)
+        }
+        else {
+            format!("Mixed({})", length)
+        };
+        format!("{} => {}", header, self.display_element(limit))
+    }
+    fn display_element(&self, limit: usize) -> String {
+        match self.literal_type {
+            LiteralType::Array => {
+                let shown = if limit == 0 { self.array_values.len() } else { limit.min(self.array_values.len()) };
+                let mut values: Vec<String> = self.array_values[..shown].iter().map(|value| value.display_element(limit)).collect();
+                if shown < self.array_values.len() {
+                    values.push("...".to_string());
+                }
+                format!("[{}]", values.join(", "))
+            },
+            LiteralType::Function => self.to_text(),
+            _ => self.value.to_owned(),
+        }
+    }
+
     // Loop commands
+    pub fn describe(&self) -> String {
+        // Type and value, for listing variables
+        match self.literal_type {
+            LiteralType::Array => format!("Array({})", self.array_values.len()),
+            LiteralType::Function => "Function".to_string(),
+            LiteralType::String => format!("String = \"{}\"", self.value),
+            _ => format!("{:?} = {}", self.literal_type, self.value),
+        }
+    }
     pub fn new_break() -> Literal {
         Literal::new_value(LiteralType::Break, "".to_string())
     }
@@ -111,6 +282,7 @@ impl Literal {
 #[derive(Debug)]
 #[derive(Clone, Copy)]
 #[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
 pub enum StatementType {
     Block, // 'statements'
     Expression, // 'expr'
@@ -122,6 +294,7 @@ pub enum StatementType {
     // Control Flow
     If, // 'then_branch', 'else_branch', 'expr'
     While, // 'expr' (condition), 'then_branch' (body)
+    ForIn, // 'token_name' (loop variable), 'expr' (array or string), 'then_branch' (body)
 
 
     // Special
@@ -129,11 +302,13 @@ pub enum StatementType {
     Print, // 'expr'
     Println, // 'expr'
     Bai, // 'expr'
-    
+    Import, // 'statements'/module body and 'token_name'/module path
+
     Break, // Nothing
     Continue, // Nothing
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Statement {
     pub statement_type: StatementType,
     pub statements: Vec<Box<Statement>>,
@@ -141,15 +316,32 @@ pub struct Statement {
     pub else_branch: Option<Box<Statement>>,
     pub expr: Option<Box<Expr>>,
     pub token_name: token::Token,
-    pub tokens: Vec<token::Token>,
+    pub tokens: Vec<token::Token>, // Function arguments, or the names of a destructuring Let
     pub is_function: bool,
+    pub capture_depth: usize, // Function only, number of enclosing local environments to capture. Set by the resolver
+    pub is_exported: bool, // Function and Let only, visible to scripts which import the module
+    pub line_number: usize, // Line of the first token, 0 if unknown
+    pub is_for_loop: bool, // Set on the statements which a 'for' loop is desugared into, so it can be formatted back
+    pub is_const: bool, // Let only, declared with 'const'
+    pub defaults: Vec<Box<Expr>>, // Function only, default value of each argument, ExprType::None if it has none
+    pub is_variadic: bool, // Function only, the last argument collects the remaining arguments into an array, such as '...nums'
+    pub doc: String, // Function only, text of the '///' comments before it, shown by help()
 }
 
 impl Clone for Box<Statement> {
     fn clone(&self) -> Box<Statement> {
-        Box::new(Statement::new(self.statement_type, self.statements.clone(),
+        let mut statement = Statement::new(self.statement_type, self.statements.clone(),
         self.then_branch.clone(), self.else_branch.clone(),
-        self.expr.clone(), self.token_name.clone(), self.tokens.clone(), self.is_function))
+        self.expr.clone(), self.token_name.clone(), self.tokens.clone(), self.is_function);
+        statement.capture_depth = self.capture_depth;
+        statement.is_exported = self.is_exported;
+        statement.line_number = self.line_number;
+        statement.is_for_loop = self.is_for_loop;
+        statement.is_const = self.is_const;
+        statement.defaults = self.defaults.clone();
+        statement.is_variadic = self.is_variadic;
+        statement.doc = self.doc.clone();
+        Box::new(statement)
     }
 }
 
@@ -166,7 +358,14 @@ impl Statement {
             token_name,
             tokens,
             is_function,
-
+            capture_depth: 0,
+            is_exported: false,
+            line_number: 0,
+            is_for_loop: false,
+            is_const: false,
+            defaults: Vec::<Box<Expr>>::new(),
+            is_variadic: false,
+            doc: String::new(),
         }
     }
     pub fn new_block(statements: Vec<Box<Statement>>, is_function: bool) -> Statement {
@@ -198,6 +397,9 @@ impl Statement {
     pub fn new_while(condition_expr : Option<Box<Expr>>, body : Option<Box<Statement>>) -> Statement {
         Statement::new(StatementType::While, Vec::<Box<Statement>>::new(), body, None, condition_expr, token::Token::none(), Vec::<token::Token>::new(), false)
     }
+    pub fn new_for_in(variable : token::Token, iterable_expr : Option<Box<Expr>>, body : Option<Box<Statement>>) -> Statement {
+        Statement::new(StatementType::ForIn, Vec::<Box<Statement>>::new(), body, None, iterable_expr, variable, Vec::<token::Token>::new(), false)
+    }
 
     // Special
     pub fn new_print(expr : Option<Box<Expr>>) -> Statement {
@@ -212,59 +414,84 @@ impl Statement {
     pub fn new_bai(expr : Option<Box<Expr>>) -> Statement {
         Statement::new(StatementType::Bai, Vec::<Box<Statement>>::new(), None, None, expr, token::Token::none(), Vec::<token::Token>::new(), false)
     }
+    pub fn new_import(statements: Vec<Box<Statement>>, token_name : token::Token) -> Statement {
+        Statement::new(StatementType::Import, statements, None, None, None, token_name, Vec::<token::Token>::new(), false)
+    }
+    pub fn exported_names(statements: &[Box<Statement>]) -> Vec<String> {
+        // Names of the top-level declarations which a module makes visible to its importers
+        statements.iter()
+            .filter(|s| s.is_exported)
+            .flat_map(|s| s.declared_names())
+            .collect::<Vec<String>>()
+    }
+    pub fn declared_names(&self) -> Vec<String> {
+        // A destructuring Let declares every name in 'tokens'
+        if self.statement_type == StatementType::Let && !self.tokens.is_empty() {
+            return self.tokens.iter().map(|tok| tok.lexeme.to_string()).collect();
+        }
+        vec![self.token_name.lexeme.to_string()]
+    }
 
     pub fn print(&self, newline: bool) {
-        let max_display = 5; // Maximum elements to display
         let literal = self.expr.as_ref().unwrap().evaluate_expr();
-        if literal.literal_type == LiteralType::Array {
-            let length = literal.array_values.len();
-            if length == 0 {
-                if newline {
-                    print!("\n");
-                }
-                return;
-            }
-            print!("{:?}({}) => [", literal.array_values.get(0).unwrap().literal_type, length);
-            let mut index = 0;
-            for value in literal.array_values {
-                if index >= max_display {
-                    break;
-                }
-                print!("{}", value.value);
-                if index != length - 1 {
-                    print!(", ");
-                }
-                index += 1;
-            }
-            if length >= max_display + 1 {
-                print!(" ...");
-            }
-            print!("]");
+        ari_errors::print_output(&literal.display(display_limit()));
+        if newline {
+            ari_errors::print_output("\n");
+        }
+    }
+
+    fn define_variable(&self, tok: &token::Token, literal: Literal) {
+        let mut env_manager = ENV.lock().unwrap();
+        let env = env_manager.get_env();
+        if env.is_constant(&tok.lexeme) {
+            tok.print_custom_error(ErrorClass::Runtime, &format!("'{}' is a constant and cannot be redeclared", tok.lexeme));
         }
-        else if literal.literal_type == LiteralType::String {
-            //print!("\"{}\"", literal.value);
-            print!("{}", literal.value);
+        if self.is_const {
+            env.define_constant(tok.lexeme.to_string(), literal);
         }
         else {
-            print!("{}", literal.value);
-        }
-        if newline {
-            print!("\n");
+            env.define(tok.lexeme.to_string(), literal);
         }
     }
 
+    fn loop_name(&self) -> String {
+        // The 'while' made from a 'for' loop has no line of its own, so the parenthesis' line is used
+        let line_number = match self.line_number {
+            0 => self.token_name.line_number,
+            line_number => line_number,
+        };
+        let keyword = if self.is_for_loop || self.statement_type == StatementType::ForIn { "for" } else { "while" };
+        format!("{} loop (line {})", keyword, line_number)
+    }
+
     pub fn evaluate_statement(&self) -> Literal {
+        limits::check_stack(&self.token_name);
+        if debugger::is_enabled() {
+            debugger::before_statement(self);
+        }
+        if trace::is_enabled() {
+            return trace::statement(self);
+        }
+        self.execute()
+    }
+
+    pub fn execute(&self) -> Literal {
         match self.statement_type {
             StatementType::Function => {
                 // Declare user-defined function
-                let closure_env = ENV.lock().unwrap().get_env().clone();
-                let new_user_function = func::Function::new_user(self.tokens.clone(), self.then_branch.clone(), closure_env, self.token_name.clone());
-                ENV.lock().unwrap().get_env().define(self.token_name.lexeme.to_owned(), Literal::new_function(new_user_function));
+                let closure_env = ENV.lock().unwrap().capture_env(self.capture_depth);
+                let new_user_function = func::Function::new_user(self.tokens.clone(), self.defaults.clone(), self.is_variadic, self.then_branch.clone(), closure_env, self.token_name.clone(), &self.doc);
+                ENV.lock().unwrap().get_env().define(self.token_name.lexeme.to_string(), Literal::new_function(new_user_function));
                 return Literal::none();
             },
             StatementType::Return => {
                 // Returns from enclosing function
-                let mut literal = self.expr.as_ref().unwrap().evaluate_expr();
+                let expr = self.expr.as_ref().unwrap();
+                let mut literal = match expr.expr_type {
+                    // Traced calls are made in place, so that the trace shows their result
+                    ExprType::Call | ExprType::MethodCall if !trace::is_enabled() => expr.tail_call(),
+                    _ => expr.evaluate_expr(),
+                };
                 literal.is_return = true;
                 return literal;
             },
@@ -305,10 +532,7 @@ impl Statement {
             StatementType::If => {
                 let expr = self.expr.as_ref().unwrap();
                 let condition_literal = expr.evaluate_expr();
-                if !Expr::is_truthy(&condition_literal) {
-                    expr.print_custom_error(&format!("'If' conditional cannot be applied to {:?}", condition_literal.literal_type));
-                }
-                if expr.string_to_bool(&condition_literal) {
+                if expr.condition_to_bool(&condition_literal, "'If' conditional") {
                     let result = self.then_branch.as_ref().unwrap().evaluate_statement();
                     if result.literal_type == LiteralType::Break || result.literal_type == LiteralType::Continue || result.is_return {
                         return result;
@@ -328,16 +552,26 @@ impl Statement {
                 return Literal::none();
             },
             StatementType::While => {
+                let profiled = profile::is_enabled().then(|| {
+                    let name = self.loop_name();
+                    let started = profile::enter(&name);
+                    (name, started)
+                });
+                let mut iterations = 0;
+                let mut result = Literal::none();
                 loop {
                     let expr = self.expr.as_ref().unwrap();
+                    limits::check_time(&self.token_name);
                     let condition_literal = expr.evaluate_expr();
-                    if !Expr::is_truthy(&condition_literal) {
-                        expr.print_custom_error(&format!("'While' conditional cannot be applied to {:?}", condition_literal.literal_type));
-                    }
                     // Evaluate 'then' branch
-                    if expr.string_to_bool(&condition_literal) {
-                        let result = self.then_branch.as_ref().unwrap().evaluate_statement();
-                        if result.literal_type == LiteralType::Break {
+                    if expr.condition_to_bool(&condition_literal, "'While' conditional") {
+                        iterations += 1;
+                        let literal = self.then_branch.as_ref().unwrap().evaluate_statement();
+                        if literal.literal_type == LiteralType::Break {
+                            break;
+                        }
+                        else if literal.is_return {
+                            result = literal;
                             break;
                         }
                     }
@@ -345,7 +579,51 @@ impl Statement {
                         break;
                     }
                 }
-                return Literal::none();
+                if let Some((name, started)) = profiled {
+                    profile::exit(&name, started, iterations);
+                }
+                return result;
+            },
+
+            StatementType::ForIn => {
+                // Runs the body once per element of an array, or per character of a string
+                let expr = self.expr.as_ref().unwrap();
+                let iterable = expr.evaluate_expr();
+                let items = match iterable.literal_type {
+                    LiteralType::Array => iterable.into_array(),
+                    LiteralType::String => iterable.value.chars().map(|c| Literal::string(c.to_string())).collect(),
+                    _ => {
+                        expr.print_custom_error(ErrorClass::Runtime, &format!("{:?} is not an array or string and cannot be iterated", iterable.literal_type));
+                        panic!();
+                    }
+                };
+                let profiled = profile::is_enabled().then(|| {
+                    let name = self.loop_name();
+                    let started = profile::enter(&name);
+                    (name, started)
+                });
+                let mut iterations = 0;
+                let mut result = Literal::none();
+                for item in items {
+                    limits::check_time(&self.token_name);
+                    iterations += 1;
+                    // Each iteration has its own environment, so closures capture the element of their iteration
+                    ENV.lock().unwrap().create_env();
+                    ENV.lock().unwrap().get_env().define(self.token_name.lexeme.to_string(), item);
+                    let literal = self.then_branch.as_ref().unwrap().evaluate_statement();
+                    ENV.lock().unwrap().destroy_env();
+                    if literal.literal_type == LiteralType::Break {
+                        break;
+                    }
+                    else if literal.is_return {
+                        result = literal;
+                        break;
+                    }
+                }
+                if let Some((name, started)) = profiled {
+                    profile::exit(&name, started, iterations);
+                }
+                return result;
             },
 
             // Loop keywords
@@ -372,10 +650,13 @@ impl Statement {
                     return Literal::none();
                 }
                 let mut literal = expr.evaluate_expr();
-                if literal.literal_type == LiteralType::Function {
-                    literal.function.as_mut().unwrap().variable_token = self.token_name.clone();
+                if !self.tokens.is_empty() {
+                    for (tok, value) in self.tokens.iter().zip(expr.destructure(&literal, self.tokens.len())) {
+                        self.define_variable(tok, value);
+                    }
+                    return literal;
                 }
-                ENV.lock().unwrap().get_env().define(self.token_name.lexeme.to_owned(), literal.clone());
+                self.define_variable(&self.token_name, literal.clone());
                 return literal;
             },
             StatementType::Bai => {
@@ -392,6 +673,34 @@ impl Statement {
                 ari_errors::exit();
                 return Literal::none();
             },
+            StatementType::Import => {
+                // The module runs in its own environment so that only exported bindings leak out
+                ENV.lock().unwrap().create_env();
+                for s in &self.statements {
+                    s.evaluate_statement();
+                }
+                let mut exported = Vec::<(String, Literal, bool)>::new();
+                {
+                    let mut env_manager = ENV.lock().unwrap();
+                    let module_env = env_manager.get_env();
+                    for name in Statement::exported_names(&self.statements) {
+                        if let Some(literal) = module_env.get_value(&name) {
+                            exported.push((name.to_owned(), literal, module_env.is_constant(&name)));
+                        }
+                    }
+                    env_manager.destroy_env();
+                }
+                let mut env_manager = ENV.lock().unwrap();
+                for (name, literal, is_const) in exported {
+                    if is_const {
+                        env_manager.get_env().define_constant(name, literal);
+                    }
+                    else {
+                        env_manager.get_env().define(name, literal);
+                    }
+                }
+                return Literal::none();
+            },
             _ => {
                 return Literal::none();
             }
@@ -400,8 +709,8 @@ impl Statement {
     fn print_error(&self, error: ari_errors::ErrorType){
         self.token_name.print_error(error);
     }
-    fn print_custom_error(&self, message: &str){
-        self.token_name.print_custom_error(message);
+    fn print_custom_error(&self, class: ErrorClass, message: &str){
+        self.token_name.print_custom_error(class, message);
     }
 }
 
@@ -412,25 +721,32 @@ impl Statement {
 #[derive(Debug)]
 #[derive(Clone, Copy)]
 #[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
 pub enum ExprType {
     Binary, // Uses 'left', 'right', 'operator'
     Logical, // (or, and) // Uses 'left', 'right', 'operator'
     ArrayCreation, // Uses 'arguments' for values
     ArrayAccess, // Uses 'left' for array reference, 'right' for array index, 'operator' for error purposes
+    Slice, // Uses 'right' for the sliced value, 'arguments' for start, end and step (None when omitted), 'operator' for error purposes
     Unary, // Uses 'right' and 'operator' field
     Literal, // Uses 'literal' field
     Grouping, // Uses 'right' field
     
     Variable, // Uses 'operator' field to represent token
     Assign, // Uses 'operator' field to represent variable token, 'right' field for expression
-    ArrayAssign, // Uses 'operator' field to represent variable token, 'left' field for index, 'right' field for expression
+    ArrayAssign, // Uses 'operator' field to represent variable token, 'arguments' for the indexes from outermost to innermost, 'right' field for expression
+    MultipleAssign, // Uses 'arguments' for the Variable targets, 'right' for the Array of values, 'operator' for the '=' token
 
     Call, // Uses 'right' (callee), 'operator' (closing parentheses), 'arguments'
+    MethodCall, // Same as Call, with the receiver as the first of 'arguments', such as 'arr.map(f)'
+    Spread, // Call arguments only, uses 'right' for the array to spread, 'operator' for the '...' token
+    NamedArgument, // Call arguments only, uses 'operator' for the argument name, 'right' for the value
 
     // Empty placeholder
     None,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Expr {
     pub expr_type: ExprType,
     pub arguments: Vec<Box<Expr>>,
@@ -438,13 +754,16 @@ pub struct Expr {
     pub right: Option<Box<Expr>>,
     pub operator: token::Token,
     pub literal: Literal,
+    pub depth: Option<usize>, // Variable, Assign, ArrayAssign only. Set by the resolver, None refers to the global environment
 }
 
 impl Clone for Box<Expr> {
     fn clone(&self) -> Box<Expr> {
-        Box::new(Expr::new(self.expr_type, self.arguments.clone(),
+        let mut expr = Expr::new(self.expr_type, self.arguments.clone(),
         self.left.clone(), self.right.clone(),
-        self.operator.clone(), self.literal.clone()))
+        self.operator.clone(), self.literal.clone());
+        expr.depth = self.depth;
+        Box::new(expr)
     }
 }
 
@@ -456,7 +775,8 @@ impl Expr {
             left,
             right,
             operator,
-            literal
+            literal,
+            depth: None,
         }
     }
     pub fn none() -> Expr {
@@ -483,8 +803,12 @@ impl Expr {
     pub fn assign(right : Option<Box<Expr>>, tok : token::Token) -> Expr {
         Expr::new(ExprType::Assign, Vec::<Box<Expr>>::new(), None, right, tok, Literal::none())
     }
-    pub fn array_assign(left : Option<Box<Expr>>, right : Option<Box<Expr>>, tok : token::Token) -> Expr {
-        Expr::new(ExprType::ArrayAssign, Vec::<Box<Expr>>::new(), left, right, tok, Literal::none())
+    pub fn array_assign(indices: Vec<Box<Expr>>, right : Option<Box<Expr>>, tok : token::Token) -> Expr {
+        Expr::new(ExprType::ArrayAssign, indices, None, right, tok, Literal::none())
+    }
+
+    pub fn multiple_assign(targets: Vec<Box<Expr>>, right : Option<Box<Expr>>, tok : token::Token) -> Expr {
+        Expr::new(ExprType::MultipleAssign, targets, None, right, tok, Literal::none())
     }
 
     // Array
@@ -494,16 +818,33 @@ impl Expr {
     pub fn array_access(left : Option<Box<Expr>>, right : Option<Box<Expr>>, tok : token::Token) -> Expr {
         Expr::new(ExprType::ArrayAccess, Vec::<Box<Expr>>::new(), left, right, tok, Literal::none())
     }
+    pub fn slice(right : Option<Box<Expr>>, bounds: Vec<Box<Expr>>, tok : token::Token) -> Expr {
+        Expr::new(ExprType::Slice, bounds, None, right, tok, Literal::none())
+    }
 
     // Function
     pub fn call(right : Option<Box<Expr>>, tok : token::Token, arguments: Vec<Box<Expr>>) -> Expr {
         Expr::new(ExprType::Call, arguments, None, right, tok, Literal::none())
     }
+    pub fn spread(right : Option<Box<Expr>>, tok : token::Token) -> Expr {
+        Expr::new(ExprType::Spread, Vec::<Box<Expr>>::new(), None, right, tok, Literal::none())
+    }
+    pub fn named_argument(right : Option<Box<Expr>>, tok : token::Token) -> Expr {
+        Expr::new(ExprType::NamedArgument, Vec::<Box<Expr>>::new(), None, right, tok, Literal::none())
+    }
 
     // Helper functions
     pub fn is_valid_arithmetic(left_type : LiteralType, right_type : LiteralType) -> bool{
         return (left_type == right_type) && (left_type == LiteralType::Number || left_type == LiteralType::Array);
     }
+    pub fn is_broadcastable(operator : token::TokenType, array_type : LiteralType, scalar_type : LiteralType) -> bool{
+        // Numbers for every arithmetic operator, and strings for concatenation
+        array_type == LiteralType::Array && match operator {
+            token::TokenType::Plus => scalar_type == LiteralType::Number || scalar_type == LiteralType::String,
+            token::TokenType::Minus | token::TokenType::Star | token::TokenType::Slash | token::TokenType::Percent => scalar_type == LiteralType::Number,
+            _ => false
+        }
+    }
     pub fn add_or_concat(left_type : LiteralType, right_type : LiteralType) -> Result<bool, ()>{
         let left_is_number = left_type == LiteralType::Number;
         let left_is_string = left_type == LiteralType::String;
@@ -528,102 +869,423 @@ impl Expr {
             return Literal::new_value(LiteralType::String, result.to_string());
         }
         else {
-            let result = Expr::string_to_float(&left) + Expr::string_to_float(&right);
-            return Literal::new_value(LiteralType::Number, result.to_string());
+            return Expr::arithmetic(left, right, i64::checked_add, |l, r| l + r);
         }
     }
     pub fn is_truthy(literal : &Literal) -> bool{
-        return (literal.literal_type == LiteralType::Bool) || (literal.literal_type == LiteralType::Null)
+        // false, null, 0, "" and [] are false, every other value is true
+        match literal.literal_type {
+            LiteralType::Bool => literal.value == "true",
+            LiteralType::Null => false,
+            LiteralType::Number => Expr::to_float(literal) != 0.0,
+            LiteralType::String => !literal.value.is_empty(),
+            LiteralType::Array => !literal.array_values.is_empty(),
+            _ => true,
+        }
     }
-    pub fn string_to_bool(&self, literal : &Literal) -> bool {
-        // Converts bool and null
-        // &mut self is included for the purpose of tracking down the error location
-        let result = match literal.value.as_str() {
-            "true" => {
-                true
-            },
-            "false" => {
-                false
-            }
-            "null" => {
-                false
-            }
-            _ => {
-                self.print_custom_error("Boolean operation only handles 'true', 'false', 'null' values.");
+    pub fn condition_to_bool(&self, literal : &Literal, op_name: &str) -> bool {
+        // Values without truthiness, such as the result of a function which returns nothing, cannot be conditions
+        // &self is included for the purpose of tracking down the error location
+        match literal.literal_type {
+            LiteralType::None | LiteralType::Break | LiteralType::Continue => {
+                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("{} cannot be applied to {:?}", op_name, literal.literal_type));
                 panic!();
-            }
-        };
-        return result;
+            },
+            _ => Expr::is_truthy(literal)
+        }
     }
-    pub fn is_equal(&self, op_name: &str, left_type: LiteralType, right_type: LiteralType, left_string: &str, right_string: &str) -> bool {
+    pub fn is_equal(&self, op_name: &str, left: &Literal, right: &Literal) -> bool {
         // &mut self is included for the purpose of tracking down the error location
-        if left_type != right_type {
+        if left.literal_type != right.literal_type {
             return false;
-            //self.print_custom_error(&format!("{} cannot be applied to {:?} and {:?}", op_name, left_type, right_type));
+            //self.print_custom_error(ErrorClass::MismatchedTypes, &format!("{} cannot be applied to {:?} and {:?}", op_name, left.literal_type, right.literal_type));
         }
-        match left_type {
+        match left.literal_type {
             LiteralType::Number => {
-                return left_string.parse::<f32>().unwrap() == right_string.parse::<f32>().unwrap();
+                return Expr::numbers_equal(left, right);
             },
             LiteralType::String | LiteralType::Bool | LiteralType::Null => {
-                return left_string == right_string;
+                return left.value == right.value;
+            },
+            LiteralType::Array => {
+                // Deep equality, element by element
+                return left.array_values.len() == right.array_values.len()
+                    && left.array_values.iter().zip(right.array_values.iter()).all(|(l, r)| self.is_equal(op_name, l, r));
+            },
+            LiteralType::Function => {
+                // Identity, so only copies of the same function are equal
+                return match (left.function.as_ref(), right.function.as_ref()) {
+                    (Some(l), Some(r)) => l.is_same(r),
+                    _ => false
+                };
             },
             //////// Cover classes here onwards
             // 
             ////////
             _ => {
-                self.print_custom_error(&format!("{} cannot be applied to {:?} and {:?}", op_name, left_type, right_type));
+                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("{} cannot be applied to {:?} and {:?}", op_name, left.literal_type, right.literal_type));
                 panic!();
             }
         };
     }
 
-    pub fn string_to_float(literal: &Literal) -> f32 {
-        return literal.value.parse::<f32>().unwrap();
+    pub fn reverse_sign(literal: &Literal) -> Option<Literal> {
+        // None unless the literal is a number or an array of numbers, which may be nested
+        match literal.literal_type {
+            LiteralType::Number => Some(Expr::arithmetic(literal, literal, |l, _| l.checked_neg(), |l, _| -l)),
+            LiteralType::Array => {
+                // Reverse using rayon's iteration
+                literal.array_values.par_iter()
+                    .map(Expr::reverse_sign)
+                    .collect::<Option<Vec<Literal>>>()
+                    .map(Literal::new_array)
+            },
+            _ => None
+        }
+    }
+    fn element_type(literal: &Literal) -> LiteralType {
+        // Type of the first element of a nested array which is not an array, for error messages
+        match literal.array_values.first() {
+            Some(element) if literal.literal_type == LiteralType::Array => Expr::element_type(element),
+            _ => literal.literal_type
+        }
+    }
+    pub fn destructure(&self, literal: &Literal, count: usize) -> Vec<Literal> {
+        // Elements of an array which is assigned to several variables at once
+        if literal.literal_type != LiteralType::Array {
+            self.print_custom_error(ErrorClass::Runtime, &format!("{:?} cannot be destructured into {} variables. Only an Array of {} elements can", literal.literal_type, count, count));
+            panic!();
+        }
+        if literal.array_values.len() != count {
+            self.print_custom_error(ErrorClass::Runtime, &format!("Array of {} elements cannot be destructured into {} variables", literal.array_values.len(), count));
+        }
+        literal.array_values.to_vec()
+    }
+    fn call_arguments(&self) -> (func::Function, Vec<Option<Literal>>) {
+        // Callee of a call and its arguments, with the named ones in their place. None for those left out
+        let callee = self.right.as_ref().unwrap().evaluate_expr();
+        self.call_arguments_with(callee, None)
+    }
+    fn call_arguments_with(&self, callee: Literal, moved: Option<&Expr>) -> (func::Function, Vec<Option<Literal>>) {
+        // 'moved' is the variable given as the first argument, which is moved into the call if the function allows it
+        let mut arguments = Vec::<Literal>::new();
+        let mut named = Vec::<(token::Token, Literal)>::new(); // Such as 'n = 50', always after the positional arguments
+        for arg in &self.arguments {
+            if arg.expr_type == ExprType::NamedArgument {
+                named.push((arg.operator.clone(), arg.right.as_ref().unwrap().evaluate_expr()));
+            }
+            else if arg.expr_type == ExprType::Spread {
+                // 'f(...arr)' passes the elements of 'arr' as separate arguments
+                let spread = arg.right.as_ref().unwrap().evaluate_expr();
+                if spread.literal_type != LiteralType::Array {
+                    arg.print_custom_error(ErrorClass::Runtime, &format!("Only an Array can be spread into arguments, not {:?}", spread.literal_type));
+                }
+                arguments.extend(spread.into_array());
+            }
+            else {
+                arguments.push(arg.evaluate_expr());
+            }
+        }
+        if callee.literal_type != LiteralType::Function {
+            self.print_custom_error(ErrorClass::NotCallable, &format!("{:?} is not a function that can be called", callee.literal_type));
+        }
+        let function = callee.function.unwrap();
+        if !function.accepts(arguments.len() + named.len()) {
+            let expected = if function.is_variadic() {
+                format!("at least {}", function.min_arg_length())
+            }
+            else if function.min_arg_length() == function.arg_length() {
+                format!("{}", function.arg_length())
+            }
+            else {
+                format!("{} to {}", function.min_arg_length(), function.arg_length())
+            };
+            self.print_custom_error(ErrorClass::WrongArgumentCount, &format!("Function expects {} arguments, but received {} arguments instead", expected, arguments.len() + named.len()));
+        }
+        if let Some(variable) = moved {
+            if named.is_empty() && function.moves_first_argument(&arguments) {
+                // Only if the variable still holds the evaluated array, as the other arguments may have reassigned it
+                let shared = std::mem::replace(&mut arguments[0], Literal::null());
+                let taken = ENV.lock().unwrap().take_variable(&variable.operator, variable.depth, |current| {
+                    current.literal_type == LiteralType::Array && Arc::ptr_eq(&current.array_values, &shared.array_values)
+                });
+                arguments[0] = taken.unwrap_or(shared);
+            }
+        }
+        let bound = if named.is_empty() {
+            arguments.into_iter().map(Some).collect()
+        }
+        else {
+            function.bind_arguments(arguments, named, &self.operator)
+        };
+        (function, bound)
+    }
+    fn evaluate_assigned(&self, targets: &[(&token::Token, Option<usize>)]) -> Literal {
+        // Value of the right side of an assignment to 'targets'
+        // In 'arr = push(arr, x)', 'arr' is moved into push() rather than shared with it, so the array is not copied
+        // Traced calls are evaluated as usual, so that every expression is shown
+        if (self.expr_type == ExprType::Call || self.expr_type == ExprType::MethodCall) && !trace::is_enabled() {
+            let moved = self.arguments.first().map(|first| first.as_ref()).filter(|first| {
+                first.expr_type == ExprType::Variable &&
+                targets.iter().any(|(tok, depth)| tok.lexeme == first.operator.lexeme && *depth == first.depth)
+            });
+            if moved.is_some() {
+                let callee = self.right.as_ref().unwrap().evaluate_expr();
+                let (function, arguments) = self.call_arguments_with(callee, moved);
+                return self.call_result(function.call_bound(arguments, &self.operator));
+            }
+        }
+        self.evaluate_expr()
+    }
+    fn call_result(&self, result: Option<Literal>) -> Literal {
+        match result {
+            Some(literal) => {
+                literal
+            },
+            None => {
+                self.print_custom_error(ErrorClass::NotCallable, &format!("Cannot invoke Function of type 'None'"));
+                Literal::none()
+            }
+        }
+    }
+    fn tail_call(&self) -> Literal {
+        // 'return f(x);' has nothing left to do after f returns, so a user-defined f is called after the current function
+        // has returned instead of from inside it, and tail recursion does not grow the Rust stack
+        let (function, arguments) = self.call_arguments();
+        if function.is_native() {
+            return self.call_result(function.call_bound(arguments, &self.operator));
+        }
+        function.tail_call(arguments, &self.operator)
+    }
+    fn assign_element(&self, array_reference: &mut Literal, indices: &[Literal], literal_value: Literal) {
+        if array_reference.literal_type != LiteralType::Array {
+            self.print_custom_error(ErrorClass::InvalidIndex, &format!("{:?} is not an array and cannot be indexed", array_reference.literal_type));
+            return;
+        }
+        let index = self.to_index(&indices[0]);
+        if indices.len() > 1 {
+            // Nested array such as 'm[1][2] = 0'
+            match array_reference.array_mut().get_mut(index) {
+                Some(element) => self.assign_element(element, &indices[1..], literal_value),
+                None => self.print_custom_error(ErrorClass::InvalidIndex, &format!("Attempt to modify non-existent index in array with {}", index)),
+            }
+            return;
+        }
+        if array_reference.array_values.len() == 0 {
+            if index == 0 {
+                // Push to empty array
+                array_reference.array_mut().push(literal_value);
+            }
+            else {
+                self.print_custom_error(ErrorClass::InvalidIndex, &format!("Attempt to modify empty array with index {}. Can only modify with index 0", index));
+            }
+        }
+        else {
+            if array_reference.array_values.get(index).is_none() {
+                self.print_custom_error(ErrorClass::InvalidIndex, &format!("Attempt to modify non-existent index in array with {}", index));
+            }
+            array_reference.array_mut()[index] = literal_value;
+        }
+    }
+    fn uniform_type(&self, values: &[Literal]) -> LiteralType {
+        // Arrays may mix types, but elementwise arithmetic needs every element to be of the same type
+        let first_type = values[0].literal_type;
+        if let Some((index, value)) = values.iter().enumerate().find(|(_, value)| value.literal_type != first_type) {
+            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Array values are not of the same type. Index 0 is of type {:?} but index {} is of type {:?}", first_type, index, value.literal_type));
+        }
+        first_type
+    }
+    fn to_index(&self, index_literal: &Literal) -> usize {
+        if index_literal.literal_type != LiteralType::Number {
+            self.print_custom_error(ErrorClass::InvalidIndex, &format!("{:?} is not a valid array index type. Only positive integers are allowed", index_literal.literal_type));
+        }
+        let index_integer = match Expr::to_integer(index_literal) {
+            Some(integer) => integer,
+            None => {
+                self.print_custom_error(ErrorClass::InvalidIndex, &format!("{} is a float and is not a valid array index. Only positive integers are allowed", index_literal.value));
+                panic!();
+            }
+        };
+        if index_integer < 0 {
+            self.print_custom_error(ErrorClass::InvalidIndex, &format!("{} is negative and is not a valid array index. Only positive integers are allowed", index_integer));
+        }
+        index_integer as usize
+    }
+    fn slice_indices(&self, length: usize) -> Vec<usize> {
+        // Python semantics: negative bounds count from the end, and bounds past either end are clamped
+        let length = length as i64;
+        let step = self.slice_bound(&self.arguments[2]).unwrap_or(1);
+        if step == 0 {
+            self.print_custom_error(ErrorClass::InvalidIndex, "Slice step cannot be zero");
+        }
+        let clamp = |bound: i64| {
+            let bound = if bound < 0 { bound + length } else { bound };
+            if step > 0 { bound.max(0).min(length) } else { bound.max(-1).min(length - 1) }
+        };
+        let (default_start, default_end) = if step > 0 { (0, length) } else { (length - 1, -1) };
+        let start = self.slice_bound(&self.arguments[0]).map_or(default_start, clamp);
+        let end = self.slice_bound(&self.arguments[1]).map_or(default_end, clamp);
+        let mut indices = Vec::<usize>::new();
+        let mut i = start;
+        while (step > 0 && i < end) || (step < 0 && i > end) {
+            indices.push(i as usize);
+            i += step;
+        }
+        indices
+    }
+    fn slice_bound(&self, bound: &Expr) -> Option<i64> {
+        if bound.expr_type == ExprType::None {
+            return None;
+        }
+        let literal = bound.evaluate_expr();
+        if literal.literal_type != LiteralType::Number {
+            self.print_custom_error(ErrorClass::InvalidIndex, &format!("{:?} is not a valid slice bound. Only integers are allowed", literal.literal_type));
+        }
+        match Expr::to_integer(&literal) {
+            Some(integer) => Some(integer),
+            None => {
+                self.print_custom_error(ErrorClass::InvalidIndex, &format!("{} is a float and is not a valid slice bound. Only integers are allowed", literal.value));
+                panic!();
+            }
+        }
+    }
+    fn integer_operands(&self, op_name: &str, left: &Literal, right: &Literal) -> (i64, i64) {
+        // Bitwise operators only apply to numbers without a fractional part
+        if left.literal_type != LiteralType::Number || right.literal_type != LiteralType::Number {
+            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("{} cannot be applied to {:?} and {:?}", op_name, left.literal_type, right.literal_type));
+            panic!();
+        }
+        match Expr::to_integer(left).zip(Expr::to_integer(right)) {
+            Some(integers) => integers,
+            None => {
+                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("{} expects integers, but received {} and {} instead", op_name, left.value, right.value));
+                panic!();
+            }
+        }
+    }
+    fn compare(&self, op_name: &str, left: &Literal, right: &Literal) -> Option<Ordering> {
+        // Numbers are compared by value and strings lexicographically. None if a number is NaN
+        match (left.literal_type, right.literal_type) {
+            (LiteralType::Number, LiteralType::Number) => match (left.number, right.number) {
+                (Number::Integer(l), Number::Integer(r)) => Some(l.cmp(&r)),
+                _ => Expr::to_float(left).partial_cmp(&Expr::to_float(right))
+            },
+            (LiteralType::String, LiteralType::String) => Some(left.value.cmp(&right.value)),
+            _ => {
+                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("{} cannot be applied to {:?} and {:?}", op_name, left.literal_type, right.literal_type));
+                panic!();
+            }
+        }
+    }
+
+    pub fn to_float(literal: &Literal) -> f64 {
+        literal.number.to_f64()
+    }
+
+    pub fn to_integer(literal: &Literal) -> Option<i64> {
+        // Also accepts floats without a fractional part, such as an index of '2.0'
+        literal.number.to_integer()
     }
 
-    pub fn divide(left: &Literal, right: &Literal) -> Result<f32, ()> {
-        let result = Expr::string_to_float(&left) / Expr::string_to_float(&right);
+    pub fn numbers_equal(left: &Literal, right: &Literal) -> bool {
+        match (left.number, right.number) {
+            (Number::Integer(l), Number::Integer(r)) => l == r,
+            _ => Expr::to_float(left) == Expr::to_float(right)
+        }
+    }
+
+    pub fn arithmetic(left: &Literal, right: &Literal, integer_operation: fn(i64, i64) -> Option<i64>, float_operation: fn(f64, f64) -> f64) -> Literal {
+        // Integers are calculated exactly, and only become floats when the result overflows an i64
+        // A float on either side gives a float, so '2.0 + 1' is 3.0
+        if let (Number::Integer(l), Number::Integer(r)) = (left.number, right.number) {
+            if let Some(result) = integer_operation(l, r) {
+                return Literal::number(result);
+            }
+        }
+        return Literal::number(float_operation(Expr::to_float(left), Expr::to_float(right)));
+    }
+
+    pub fn divide(left: &Literal, right: &Literal) -> Result<f64, ()> {
+        let result = Expr::to_float(&left) / Expr::to_float(&right);
         if result.is_infinite() {
            return Err(());
         }
         return Ok(result);
     }
 
+    pub fn remainder(left: &Literal, right: &Literal) -> Result<Literal, ()> {
+        // Has the sign of the left operand, like modulo()
+        if Expr::to_float(&right) == 0.0 {
+           return Err(());
+        }
+        return Ok(Expr::arithmetic(left, right, i64::checked_rem, |l, r| l % r));
+    }
+
+    pub fn floor_divide(left: &Literal, right: &Literal) -> Result<Literal, ()> {
+        // Rounds towards negative infinity, so '-7 ~/ 2' is -4
+        let floored = |l: i64, r: i64| {
+            let quotient = l.checked_div(r)?;
+            Some(if l % r != 0 && (l < 0) != (r < 0) { quotient - 1 } else { quotient })
+        };
+        let result = Expr::divide(left, right)?;
+        if let (Number::Integer(l), Number::Integer(r)) = (left.number, right.number) {
+            if let Some(quotient) = floored(l, r) {
+                return Ok(Literal::number(quotient));
+            }
+        }
+        return Ok(Literal::number(result.floor()));
+    }
+
     // Evaluate expression
     pub fn evaluate_expr(&self) -> Literal {
+        if limits::is_stack_exhausted() {
+            self.print_custom_error(ErrorClass::CallDepth, limits::stack_error());
+        }
+        if trace::is_enabled() {
+            return trace::expr(self);
+        }
+        self.execute()
+    }
+
+    pub fn execute(&self) -> Literal {
         match self.expr_type {
             ExprType::Binary => {
                 let mut left = self.left.as_ref().unwrap().evaluate_expr();
                 let mut right = self.right.as_ref().unwrap().evaluate_expr();
+                // Broadcasting, where a scalar is repeated to the length of the array on the other side
+                if Expr::is_broadcastable(self.operator.token_type, left.literal_type, right.literal_type) {
+                    right = Literal::new_array(vec![right; left.array_values.len()]);
+                }
+                else if Expr::is_broadcastable(self.operator.token_type, right.literal_type, left.literal_type) {
+                    left = Literal::new_array(vec![left; right.array_values.len()]);
+                }
 
                 match self.operator.token_type {
                     // Arithmetic/Concatenation operators
                     token::TokenType::Minus => {
                         if !Expr::is_valid_arithmetic(left.literal_type, right.literal_type) {
-                            self.print_custom_error(&format!("Subtraction cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Subtraction cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
                             panic!();
                         }
                         if left.literal_type == LiteralType::Number {
                             // Normal subtraction
-                            let r = Expr::string_to_float(&left) - Expr::string_to_float(&right);
-                            return Literal::new_value(left.literal_type, r.to_string());
+                            return Expr::arithmetic(&left, &right, i64::checked_sub, |l, r| l - r);
                         }
                         else {
                             // Array subtraction
                             let (left_array, right_array) = (left.array_values, right.array_values);
                             if left_array.len() != right_array.len() {
-                                self.print_custom_error(&format!("Cannot subtract array of different sizes, {} and {},", left_array.len(), right_array.len()));
+                                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Cannot subtract array of different sizes, {} and {},", left_array.len(), right_array.len()));
                             }
                             
                             if left_array.len() == 0 {
                                 return Literal::new_array(Vec::<Literal>::new());
                             }
                             else{
-                                let left_array_type = left_array.get(0).unwrap().literal_type;
-                                let right_array_type = right_array.get(0).unwrap().literal_type;
+                                let left_array_type = self.uniform_type(&left_array);
+                                let right_array_type = self.uniform_type(&right_array);
                                 if left_array_type != right_array_type {
-                                    self.print_custom_error(&format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
                                 }
                                 if left_array_type == LiteralType::Number && right_array_type == LiteralType::Number {
                                     // Subtract using rayon's iteration
@@ -631,13 +1293,13 @@ impl Expr {
                                                         .zip(right_array.par_iter())
                                                         .map(
                                                             |(a, b)|
-                                                            Literal::number((Expr::string_to_float(&a) - Expr::string_to_float(&b)).to_string())
+                                                            Expr::arithmetic(&a, &b, i64::checked_sub, |l, r| l - r)
                                                         )
                                                         .collect();
                                     return Literal::new_array(result_array);
                                 }
                                 else {
-                                    self.print_custom_error(&format!("Array subtraction cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Array subtraction cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
                                     panic!();
                                 }
                             }
@@ -645,7 +1307,7 @@ impl Expr {
                     },
                     token::TokenType::Slash => {
                         if !Expr::is_valid_arithmetic(left.literal_type, right.literal_type) {
-                            self.print_custom_error(&format!("Division cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Division cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
                             panic!();
                         }
                         if left.literal_type == LiteralType::Number {
@@ -653,27 +1315,27 @@ impl Expr {
                             let r = match Expr::divide(&left, &right) {
                                 Ok(v) => v,
                                 Err(()) => {
-                                    self.print_custom_error("Division by zero occurs");
+                                    self.print_custom_error(ErrorClass::DivisionByZero, "Division by zero occurs");
                                     panic!();
                                 }
                             };
-                            return Literal::new_value(left.literal_type, r.to_string());
+                            return Literal::number(r);
                         }
                         else {
                             // Array division
                             let (left_array, right_array) = (&mut left.array_values, &mut right.array_values);
                             if left_array.len() != right_array.len() {
-                                self.print_custom_error(&format!("Cannot divide array of different sizes, {} and {},", left_array.len(), right_array.len()));
+                                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Cannot divide array of different sizes, {} and {},", left_array.len(), right_array.len()));
                             }
                             
                             if left_array.len() == 0 {
                                 return Literal::new_array(Vec::<Literal>::new());
                             }
                             else{
-                                let left_array_type = left_array.get(0).unwrap().literal_type;
-                                let right_array_type = right_array.get(0).unwrap().literal_type;
+                                let left_array_type = self.uniform_type(&left_array);
+                                let right_array_type = self.uniform_type(&right_array);
                                 if left_array_type != right_array_type {
-                                    self.print_custom_error(&format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
                                 }
                                 if left_array_type == LiteralType::Number && right_array_type == LiteralType::Number {
                                     // Divide using rayon's iteration
@@ -682,7 +1344,7 @@ impl Expr {
                                                         .map(
                                                             |(a, b)| -> Result<Literal, ()> {
                                                                 match Expr::divide(&a, &b) {
-                                                                    Ok(v) => Ok(Literal::number(v.to_string())),
+                                                                    Ok(v) => Ok(Literal::number(v)),
                                                                     Err(()) => Err(())
                                                                 }
                                                                 
@@ -692,44 +1354,120 @@ impl Expr {
                                                         {
                                                             Ok(arr) => arr,
                                                             Err(_) => {
-                                                                self.print_custom_error("Division by zero in one of the array elements occurs");
+                                                                self.print_custom_error(ErrorClass::DivisionByZero, "Division by zero in one of the array elements occurs");
                                                                 panic!();
                                                             }
                                                         };
                                     return Literal::new_array(result_array);
                                 }
                                 else {
-                                    self.print_custom_error(&format!("Array division cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Array division cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
+                                    panic!();
+                                }
+                            }
+                        }
+                    },
+                    token::TokenType::Percent => {
+                        if !Expr::is_valid_arithmetic(left.literal_type, right.literal_type) {
+                            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Modulo cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                            panic!();
+                        }
+                        if left.literal_type == LiteralType::Number {
+                            // Normal modulo
+                            return match Expr::remainder(&left, &right) {
+                                Ok(v) => v,
+                                Err(()) => {
+                                    self.print_custom_error(ErrorClass::DivisionByZero, "Modulo by zero occurs");
+                                    panic!();
+                                }
+                            };
+                        }
+                        else {
+                            // Array modulo
+                            let (left_array, right_array) = (left.array_values, right.array_values);
+                            if left_array.len() != right_array.len() {
+                                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Cannot take modulo of array of different sizes, {} and {},", left_array.len(), right_array.len()));
+                            }
+                            
+                            if left_array.len() == 0 {
+                                return Literal::new_array(Vec::<Literal>::new());
+                            }
+                            else{
+                                let left_array_type = self.uniform_type(&left_array);
+                                let right_array_type = self.uniform_type(&right_array);
+                                if left_array_type != right_array_type {
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
+                                }
+                                if left_array_type == LiteralType::Number && right_array_type == LiteralType::Number {
+                                    // Modulo using rayon's iteration
+                                    let result_array = match left_array.par_iter()
+                                                        .zip(right_array.par_iter())
+                                                        .map(|(a, b)| Expr::remainder(&a, &b))
+                                                        .collect()
+                                                        {
+                                                            Ok(arr) => arr,
+                                                            Err(()) => {
+                                                                self.print_custom_error(ErrorClass::DivisionByZero, "Modulo by zero in one of the array elements occurs");
+                                                                panic!();
+                                                            }
+                                                        };
+                                    return Literal::new_array(result_array);
+                                }
+                                else {
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Array modulo cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
                                     panic!();
                                 }
                             }
                         }
                     },
                     token::TokenType::Star => {
+                        // String repetition, such as '"-" * 20' or '3 * "ab"'
+                        let repetition = match (left.literal_type, right.literal_type) {
+                            (LiteralType::String, LiteralType::Number) => Some((&left, &right)),
+                            (LiteralType::Number, LiteralType::String) => Some((&right, &left)),
+                            _ => None,
+                        };
+                        if let Some((text, count)) = repetition {
+                            let count = match Expr::to_integer(count).filter(|count| *count >= 0) {
+                                Some(count) => count as usize,
+                                None => {
+                                    self.print_custom_error(ErrorClass::Runtime, &format!("A String can only be repeated a non-negative integer number of times, but received {} instead", count.value));
+                                    panic!();
+                                }
+                            };
+                            // The length is checked against the limits before the String is created
+                            let length = text.value.len().saturating_mul(count);
+                            limits::check_string_length(length, &self.operator);
+                            let mut repeated = String::new();
+                            if repeated.try_reserve_exact(length).is_err() {
+                                self.print_custom_error(ErrorClass::Runtime, &format!("String of {} bytes is too large to be created", length));
+                            }
+                            repeated.extend(std::iter::repeat(text.value.as_str()).take(count));
+                            return Literal::string(repeated);
+                        }
                         if !Expr::is_valid_arithmetic(left.literal_type, right.literal_type) {
-                            self.print_custom_error(&format!("Multiplication cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Multiplication cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
                             panic!();
                         }
                         if left.literal_type == LiteralType::Number {
                             // Normal multiplication
-                            let r = Expr::string_to_float(&left) * Expr::string_to_float(&right);
-                            return Literal::new_value(left.literal_type, r.to_string());
+                            return Expr::arithmetic(&left, &right, i64::checked_mul, |l, r| l * r);
                         }
                         else {
                             // Array multiplication
                             let (left_array, right_array) = (left.array_values, right.array_values);
                             if left_array.len() != right_array.len() {
-                                self.print_custom_error(&format!("Cannot multiply array of different sizes, {} and {},", left_array.len(), right_array.len()));
+                                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Cannot multiply array of different sizes, {} and {},", left_array.len(), right_array.len()));
                             }
                             
                             if left_array.len() == 0 {
                                 return Literal::new_array(Vec::<Literal>::new());
                             }
                             else{
-                                let left_array_type = left_array.get(0).unwrap().literal_type;
-                                let right_array_type = right_array.get(0).unwrap().literal_type;
+                                let left_array_type = self.uniform_type(&left_array);
+                                let right_array_type = self.uniform_type(&right_array);
                                 if left_array_type != right_array_type {
-                                    self.print_custom_error(&format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
                                 }
                                 if left_array_type == LiteralType::Number && right_array_type == LiteralType::Number {
                                     // Subtract using rayon's iteration
@@ -737,13 +1475,13 @@ impl Expr {
                                                         .zip(right_array.par_iter())
                                                         .map(
                                                             |(a, b)|
-                                                            Literal::number((Expr::string_to_float(&a) * Expr::string_to_float(&b)).to_string())
+                                                            Expr::arithmetic(&a, &b, i64::checked_mul, |l, r| l * r)
                                                         )
                                                         .collect();
                                     return Literal::new_array(result_array);
                                 }
                                 else {
-                                    self.print_custom_error(&format!("Array multiplication cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Array multiplication cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
                                     panic!();
                                 }
                             }
@@ -751,41 +1489,43 @@ impl Expr {
                     },
                     token::TokenType::Plus => {
                         // Applies to number, string, mixed, and their array counterparts
-                        ///////////////////////////////////////////////////////////////////////////////////
-                        // Should I implement array concatenation? Maybe not here. Try the native functions
-                        ///////////////////////////////////////////////////////////////////////////////////
+                        // Arrays are added elementwise, '++' concatenates them instead
 
                         let mixed_concat = match Expr::add_or_concat(left.literal_type, right.literal_type) {
                             Ok(v) => v,
                             Err(_) => {
-                                self.print_custom_error(&format!("Addition cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Addition cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
                                 panic!();
                             }
                         };
                         match left.literal_type {
                             LiteralType::Number => {
+                                if mixed_concat {
+                                    limits::check_string_length(left.value.len() + right.value.len(), &self.operator);
+                                }
                                 return Expr::add(&left, &right, mixed_concat);
                             },
                             LiteralType::String => {
+                                limits::check_string_length(left.value.len() + right.value.len(), &self.operator);
                                 return Expr::add(&left, &right, true);
                             },
                             LiteralType::Array => {
                                 // Array addition
                                 let (left_array, right_array) = (left.array_values, right.array_values);
                                 if left_array.len() != right_array.len() {
-                                    self.print_custom_error(&format!("Cannot add array of different sizes, {} and {},", left_array.len(), right_array.len()));
+                                    self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Cannot add array of different sizes, {} and {},", left_array.len(), right_array.len()));
                                 }
                                 
                                 if left_array.len() == 0 {
                                     return Literal::new_array(Vec::<Literal>::new());
                                 }
                                 else{
-                                    let left_array_type = left_array.get(0).unwrap().literal_type;
-                                    let right_array_type = right_array.get(0).unwrap().literal_type;
+                                    let left_array_type = self.uniform_type(&left_array);
+                                    let right_array_type = self.uniform_type(&right_array);
                                     let mixed_concat = match Expr::add_or_concat(left_array_type, right_array_type) {
                                         Ok(v) => v,
                                         Err(_) => {
-                                            self.print_custom_error(&format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
+                                            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Arrays are not of the same type. Left array is of type {:?} but right array is of type {:?}", left_array_type, right_array_type));
                                             panic!();
                                         }
                                     };
@@ -812,62 +1552,128 @@ impl Expr {
                                         return Literal::new_array(result_array);
                                     }
                                     else {
-                                        self.print_custom_error(&format!("Array addition cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
+                                        self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Array addition cannot be applied to {:?} and {:?}", left_array_type, right_array_type));
                                         panic!();
                                     }
                                 }
                             },
                             _ => {
-                                self.print_custom_error(&format!("Addition cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("Addition cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
                                 panic!();
                             }
                         };
                     },
-
-                    // Equality operators
-                    token::TokenType::Greater => {
-                        if !Expr::is_valid_arithmetic(left.literal_type, right.literal_type) {
-                            self.print_custom_error(&format!("'Greater than' (>) cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                    token::TokenType::TildeSlash => {
+                        if left.literal_type != LiteralType::Number || right.literal_type != LiteralType::Number {
+                            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("'Floor division' (~/) cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
                             panic!();
                         }
-                        let result : bool = Expr::string_to_float(&left) > Expr::string_to_float(&right);
-                        return Literal::bool(result);
+                        return match Expr::floor_divide(&left, &right) {
+                            Ok(v) => v,
+                            Err(()) => {
+                                self.print_custom_error(ErrorClass::DivisionByZero, "Division by zero occurs");
+                                panic!();
+                            }
+                        };
                     },
-                    token::TokenType::GreaterEqual => {
-                        if !Expr::is_valid_arithmetic(left.literal_type, right.literal_type) {
-                            self.print_custom_error(&format!("'Greater-or-equal than' (>=) cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
-                            panic!();
+                    // Bitwise operators
+                    token::TokenType::Ampersand => {
+                        let (l, r) = self.integer_operands("'Bitwise and' (&)", &left, &right);
+                        return Literal::number(l & r);
+                    },
+                    token::TokenType::Pipe => {
+                        let (l, r) = self.integer_operands("'Bitwise or' (|)", &left, &right);
+                        return Literal::number(l | r);
+                    },
+                    token::TokenType::Caret => {
+                        let (l, r) = self.integer_operands("'Bitwise xor' (^)", &left, &right);
+                        return Literal::number(l ^ r);
+                    },
+                    token::TokenType::LessLess | token::TokenType::GreaterGreater => {
+                        let op_name = if self.operator.token_type == token::TokenType::LessLess { "'Left shift' (<<)" } else { "'Right shift' (>>)" };
+                        let (l, r) = self.integer_operands(op_name, &left, &right);
+                        if r < 0 || r > 63 {
+                            self.print_custom_error(ErrorClass::Runtime, &format!("{} expects a shift between 0 and 63, but received {} instead", op_name, r));
                         }
-                        let result : bool = Expr::string_to_float(&left) >= Expr::string_to_float(&right);
-                        return Literal::bool(result);
+                        let result = if self.operator.token_type == token::TokenType::LessLess { l << r } else { l >> r };
+                        return Literal::number(result);
                     },
-                    token::TokenType::Less => {
-                        if !Expr::is_valid_arithmetic(left.literal_type, right.literal_type) {
-                            self.print_custom_error(&format!("'Lesser than' (<) cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                    token::TokenType::DotDot | token::TokenType::DotDotEqual => {
+                        // Counts up in steps of 1, and is empty when the end is before the start
+                        if left.literal_type != LiteralType::Number || right.literal_type != LiteralType::Number {
+                            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("'Range' ({}) cannot be applied to {:?} and {:?}", self.operator.lexeme, left.literal_type, right.literal_type));
                             panic!();
                         }
-                        let result : bool = Expr::string_to_float(&left) < Expr::string_to_float(&right);
-                        return Literal::bool(result);
+                        let inclusive = self.operator.token_type == token::TokenType::DotDotEqual;
+                        let integers = Expr::to_integer(&left).zip(Expr::to_integer(&right));
+                        // The length is checked against the limits before any element is created
+                        let length = match integers {
+                            Some((start, end)) => (end as i128 - start as i128 + inclusive as i128).max(0).min(usize::MAX as i128) as usize,
+                            None => {
+                                let span = Expr::to_float(&right) - Expr::to_float(&left);
+                                let extra = if inclusive && span.fract() == 0.0 { 1.0 } else { 0.0 };
+                                (span.ceil() + extra).max(0.0) as usize
+                            }
+                        };
+                        limits::check_array_length(length, &self.operator);
+                        let mut values = Vec::<Literal>::new();
+                        if values.try_reserve_exact(length).is_err() {
+                            self.print_custom_error(ErrorClass::Runtime, &format!("Range of {} elements is too large to be created", length));
+                        }
+                        if let Some((start, end)) = integers {
+                            // Counted with integers, so that the values stay exact however large they are
+                            let end = if inclusive { end.checked_add(1) } else { Some(end) };
+                            values.extend((start..end.unwrap_or(i64::MAX)).map(|value| Literal::number(value)));
+                        }
+                        else {
+                            let (start, end) = (Expr::to_float(&left), Expr::to_float(&right));
+                            let mut value = start;
+                            while value < end || (inclusive && value == end) {
+                                values.push(Literal::number(value));
+                                value += 1.0;
+                            }
+                        }
+                        return Literal::new_array(values);
                     },
-                    token::TokenType::LessEqual => {
-                        if !Expr::is_valid_arithmetic(left.literal_type, right.literal_type) {
-                            self.print_custom_error(&format!("'Lesser-or-equal than' (<=) cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
+                    token::TokenType::PlusPlus => {
+                        // Array concatenation, the same as concat()
+                        if left.literal_type != LiteralType::Array || right.literal_type != LiteralType::Array {
+                            self.print_custom_error(ErrorClass::MismatchedTypes, &format!("'Concatenation' (++) cannot be applied to {:?} and {:?}", left.literal_type, right.literal_type));
                             panic!();
                         }
-                        let result : bool = Expr::string_to_float(&left) <= Expr::string_to_float(&right);
-                        return Literal::bool(result);
+                        limits::check_array_length(left.array_values.len() + right.array_values.len(), &self.operator);
+                        left.array_mut().extend(right.into_array());
+                        return left;
+                    },
+
+                    // Equality operators
+                    token::TokenType::Greater => {
+                        let ordering = self.compare("'Greater than' (>)", &left, &right);
+                        return Literal::bool(ordering == Some(Ordering::Greater));
+                    },
+                    token::TokenType::GreaterEqual => {
+                        let ordering = self.compare("'Greater-or-equal than' (>=)", &left, &right);
+                        return Literal::bool(ordering == Some(Ordering::Greater) || ordering == Some(Ordering::Equal));
+                    },
+                    token::TokenType::Less => {
+                        let ordering = self.compare("'Lesser than' (<)", &left, &right);
+                        return Literal::bool(ordering == Some(Ordering::Less));
+                    },
+                    token::TokenType::LessEqual => {
+                        let ordering = self.compare("'Lesser-or-equal than' (<=)", &left, &right);
+                        return Literal::bool(ordering == Some(Ordering::Less) || ordering == Some(Ordering::Equal));
                     },
                     token::TokenType::BangEqual => {
-                        let result = !self.is_equal("'Not equals' (!=)", left.literal_type, right.literal_type, &left.value, &right.value);
+                        let result = !self.is_equal("'Not equals' (!=)", &left, &right);
                         return Literal::bool(result);
                     },
                     token::TokenType::EqualEqual => {
-                        // Compare, numbers, strings, bools, null, classes
-                        let result = self.is_equal("'Equals' (==)", left.literal_type, right.literal_type, &left.value, &right.value);
+                        // Compare, numbers, strings, bools, null, arrays, functions, classes
+                        let result = self.is_equal("'Equals' (==)", &left, &right);
                         return Literal::bool(result);
                     },
                     _ => {
-                        self.print_custom_error(&format!("{:?} is not a binary operation.", self.operator.token_type));
+                        self.print_custom_error(ErrorClass::Runtime, &format!("{:?} is not a binary operation.", self.operator.token_type));
                         panic!();
                     }
                 };
@@ -876,22 +1682,20 @@ impl Expr {
                 // (or, and)
                 let left_literal = self.left.as_ref().unwrap().evaluate_expr();
                 let right_literal = self.right.as_ref().unwrap().evaluate_expr();
-                if !Expr::is_truthy(&left_literal) || !Expr::is_truthy(&left_literal) {
-                    self.print_custom_error(&format!("'Logical' {:?} cannot be applied to {:?} and {:?}", self.operator.token_type, left_literal.literal_type, right_literal.literal_type));
-                }
+                let op_name = format!("'Logical' {:?}", self.operator.token_type);
                 match self.operator.token_type {
                     token::TokenType::Or => {
-                        if self.string_to_bool(&left_literal) {
+                        if self.condition_to_bool(&left_literal, &op_name) {
                             return left_literal;
                         }
                     },
                     token::TokenType::And => {
-                        if !self.string_to_bool(&left_literal) {
+                        if !self.condition_to_bool(&left_literal, &op_name) {
                             return left_literal;
                         }
                     },
                     _ => {
-                        self.print_custom_error(&format!("{:?} is not a logical operation.", self.operator.token_type));
+                        self.print_custom_error(ErrorClass::Runtime, &format!("{:?} is not a logical operation.", self.operator.token_type));
                     }
                 }
                 return right_literal;
@@ -900,44 +1704,25 @@ impl Expr {
                 let literal = self.right.as_ref().unwrap().evaluate_expr();
                 match self.operator.token_type {
                     token::TokenType::Minus => {
-                        if literal.literal_type != LiteralType::Number {
-                            self.print_custom_error(&format!("'Sign reversal' (-) cannot be applied to {:?}", literal.literal_type));
-                        }
-                        /*
-                        if right_string.chars().nth(0).unwrap() == '-' {
-                            right_string.retain(|c| !r#"-"#.contains(c));
-                        }
-                        else{
-                            right_string = "-".to_string() + &right_string;
+                        // Arrays are reversed elementwise
+                        match Expr::reverse_sign(&literal) {
+                            Some(result) => return result,
+                            None => {
+                                let described = match literal.literal_type {
+                                    LiteralType::Array => format!("Array of {:?}", Expr::element_type(&literal)),
+                                    literal_type => format!("{:?}", literal_type)
+                                };
+                                self.print_custom_error(ErrorClass::MismatchedTypes, &format!("'Sign reversal' (-) cannot be applied to {}", described));
+                                panic!();
+                            }
                         }
-                        */
-                        let value = - Expr::string_to_float(&literal);
-                        return Literal::new_value(literal.literal_type, value.to_string());
                     },
                     token::TokenType::Bang => {
-                        //let right_string = literal.value.to_owned();
-                        if !Expr::is_truthy(&literal) {
-                            self.print_custom_error(&format!("'Negation' (!) cannot be applied to {:?}", literal.literal_type));
-                        }
-                        let result = match literal.value.as_str() {
-                            "true" => {
-                                false
-                            },
-                            "false" => {
-                                true
-                            }
-                            "null" => {
-                                true
-                            }
-                            _ => {
-                                self.print_custom_error("'Boolean reversal' (!) only handles 'true', 'false', 'null' values.");
-                                panic!();
-                            }
-                        };
-                        return Literal::bool(result);
+                        // Logical negation for every type, so '!items' is true when an array is empty
+                        return Literal::bool(!self.condition_to_bool(&literal, "'Negation' (!)"));
                     },
                     _ => {
-                        self.print_custom_error(&format!("{:?} is not a unary operation.", self.operator.token_type));
+                        self.print_custom_error(ErrorClass::Runtime, &format!("{:?} is not a unary operation.", self.operator.token_type));
                         panic!();
                     }
                 };
@@ -951,97 +1736,46 @@ impl Expr {
             },
 
             ExprType::Variable => {
-                return ENV.lock().unwrap().get_variable(&self.operator);
+                return ENV.lock().unwrap().get_variable(&self.operator, self.depth);
             },
 
             ExprType::Assign => {
-                let literal_value = self.right.as_ref().unwrap().evaluate_expr();
-                ENV.lock().unwrap().assign_variable(&self.operator, literal_value.clone());
+                let literal_value = self.right.as_ref().unwrap().evaluate_assigned(&[(&self.operator, self.depth)]);
+                ENV.lock().unwrap().assign_variable(&self.operator, self.depth, literal_value.clone());
+                return Literal::none();
+            },
+
+            ExprType::MultipleAssign => {
+                let targets: Vec<(&token::Token, Option<usize>)> = self.arguments.iter().map(|target| (&target.operator, target.depth)).collect();
+                let literal_value = self.right.as_ref().unwrap().evaluate_assigned(&targets);
+                let values = self.right.as_ref().unwrap().destructure(&literal_value, self.arguments.len());
+                for (target, value) in self.arguments.iter().zip(values) {
+                    ENV.lock().unwrap().assign_variable(&target.operator, target.depth, value);
+                }
                 return Literal::none();
             },
 
             // For assigning specific value to array
             ExprType::ArrayAssign => {
                 // self.operator refers to the variable token
-                let mut array_reference = ENV.lock().unwrap().get_variable(&self.operator);
-
-                if array_reference.literal_type == LiteralType::Array {
-                    let index_literal = self.left.as_ref().unwrap().evaluate_expr();
-                    // Do some index checks
-                    if index_literal.literal_type != LiteralType::Number {
-                        self.print_custom_error(&format!("{:?} is not a valid array index type. Only positive integers are allowed", index_literal.literal_type));
-                    }
-                    let index_float = Expr::string_to_float(&index_literal);
-                    if index_float.fract() != 0.0 {
-                        self.print_custom_error(&format!("{} is a float and is not a valid array index. Only positive integers are allowed", index_float));
-                    }
-                    let index_integer = index_float as i32;
-                    if index_integer < 0 {
-                        self.print_custom_error(&format!("{} is negative and is not a valid array index. Only positive integers are allowed", index_float));
-                    }
-
-                    // Set new value
-                    let literal_value = self.right.as_ref().unwrap().evaluate_expr();
-
-                    if array_reference.array_values.len() == 0 {
-                        if index_integer == 0 {
-                            // Push to empty array
-                            array_reference.array_values.push(literal_value);
-                        }
-                        else {
-                            self.print_custom_error(&format!("Attempt to modify empty array with index {}. Can only modify with index 0", index_float));
-                        }
-                    }
-                    else {
-                        match array_reference.array_values.get(index_integer as usize) {
-                            Some(_) => {},
-                            None => {
-                                self.print_custom_error(&format!("Attempt to modify non-existent index in array with {}", index_float));
-                            }
-                        };
-                        let original_type = array_reference.array_values.get(0).unwrap();
-                        if original_type.literal_type != literal_value.literal_type {
-                            self.print_custom_error(&format!("Array values are not of the same type. Index 0 is of type {:?} but new value is of type {:?}", original_type.literal_type, literal_value.literal_type));
-                        }
-                        let _= std::mem::replace(&mut array_reference.array_values[index_integer as usize], literal_value);
-                    }
-                    ENV.lock().unwrap().assign_variable(&self.operator, array_reference);
-                }
-                else {
-                    self.print_custom_error(&format!("{:?} is not an array and cannot be indexed", array_reference.literal_type));
-                }
-
+                let indices: Vec<Literal> = self.arguments.iter().map(|index| index.evaluate_expr()).collect();
+                // Set new value
+                let literal_value = self.right.as_ref().unwrap().evaluate_expr();
+                // Modified where it is stored, so the elements are only copied if another variable shares them
+                ENV.lock().unwrap().modify_variable(&self.operator, self.depth, |array_reference| {
+                    self.assign_element(array_reference, &indices, literal_value);
+                });
                 return Literal::none();
             },
 
             // For Array creation
             ExprType::ArrayCreation => {
                 if self.arguments.len() == 0 {
-                    //self.print_custom_error(&format!("Cannot declare empty array"));
+                    //self.print_custom_error(ErrorClass::Runtime, &format!("Cannot declare empty array"));
                 }
-                let mut values = Vec::<Literal>::new();
-                let mut value_type = LiteralType::None; // Keep track of array type
-                let mut index = 0 ;
-                let mut error = false;
-                let mut error_literal_type = LiteralType::None;
+                // Values may be of different types, such as '["a", 1]' or '[[1, 2], [3, 4]]'
                 // Avoid cloning the arguments/values, because they can be large
-                for value_expr in &self.arguments {
-                    let value = value_expr.evaluate_expr();
-                    if index == 0 {
-                        value_type = value.literal_type;
-                    }
-                    else if value_type != value.literal_type {
-                        error = true;
-                        error_literal_type = value.literal_type;
-                        break;
-                    }
-                    values.push(value);
-                    index += 1;
-                }
-                if error {
-                    self.print_custom_error(&format!("Array values are not of the same type. Index 0 is of type {:?} but index {} is of type {:?}", value_type, index, error_literal_type));
-                }
-
+                let values: Vec<Literal> = self.arguments.iter().map(|value_expr| value_expr.evaluate_expr()).collect();
                 return Literal::new_array(values);
             },
             // For Array access
@@ -1049,61 +1783,71 @@ impl Expr {
                 let array_reference = self.left.as_ref().unwrap().evaluate_expr();
                 if array_reference.literal_type == LiteralType::Array {
                     let index_literal = self.right.as_ref().unwrap().evaluate_expr();
-                    if index_literal.literal_type != LiteralType::Number {
-                        self.print_custom_error(&format!("{:?} is not a valid array index type. Only positive integers are allowed", index_literal.literal_type));
-                    }
-                    let index_float = Expr::string_to_float(&index_literal);
-                    if index_float.fract() != 0.0 {
-                        self.print_custom_error(&format!("{} is a float and is not a valid array index. Only positive integers are allowed", index_float));
-                    }
-                    let index_integer = index_float as i32;
-                    if index_integer < 0 {
-                        self.print_custom_error(&format!("{} is negative and is not a valid array index. Only positive integers are allowed", index_float));
-                    }
-                    match array_reference.array_values.get(index_integer as usize) {
+                    let index = self.to_index(&index_literal);
+                    match array_reference.array_values.get(index) {
                         Some(result) => result.clone(),
                         None => {
-                            self.print_custom_error(&format!("Attempt to access non-existent index in array with {}", index_float));
+                            self.print_custom_error(ErrorClass::InvalidIndex, &format!("Attempt to access non-existent index in array with {}", index));
+                            panic!();
+                        }
+                    }
+                }
+                else if array_reference.literal_type == LiteralType::String {
+                    // Indexes count characters, not bytes
+                    let index_literal = self.right.as_ref().unwrap().evaluate_expr();
+                    let index = self.to_index(&index_literal);
+                    match array_reference.value.chars().nth(index) {
+                        Some(c) => Literal::string(c.to_string()),
+                        None => {
+                            self.print_custom_error(ErrorClass::InvalidIndex, &format!("Attempt to access non-existent index in string with {}", index));
                             panic!();
                         }
                     }
                 }
                 else {
-                    self.print_custom_error(&format!("{:?} is not an array and cannot be indexed", array_reference.literal_type));
+                    self.print_custom_error(ErrorClass::InvalidIndex, &format!("{:?} is not an array and cannot be indexed", array_reference.literal_type));
                     panic!();
                 }
-            }
-
-            // For function calling/invocation, not declaration 
-            ExprType::Call => {
-                let callee = self.right.as_ref().unwrap().evaluate_expr();
-                let mut arguments = Vec::<Literal>::new();
-                for arg in &self.arguments {
-                    arguments.push(arg.evaluate_expr());
-                }
-                if callee.literal_type != LiteralType::Function {
-                    self.print_custom_error(&format!("{:?} is not a function that can be called", callee.literal_type));
-                }
-                let function = callee.function.unwrap();
-                if function.arg_length() != arguments.len() {
-                    self.print_custom_error(&format!("Function expects {} arguments, but received {} arguments instead", function.arg_length(), arguments.len()));
-                }
-                match function.call(arguments, &self.operator) {
-                    Some(literal) => {
-                        literal
+            },
+            ExprType::Slice => {
+                let reference = self.right.as_ref().unwrap().evaluate_expr();
+                match reference.literal_type {
+                    LiteralType::Array => {
+                        let indices = self.slice_indices(reference.array_values.len());
+                        let values: Vec<Literal> = indices.iter().map(|&i| reference.array_values[i].clone()).collect();
+                        return Literal::new_array(values);
+                    },
+                    LiteralType::String => {
+                        let characters: Vec<char> = reference.value.chars().collect();
+                        let indices = self.slice_indices(characters.len());
+                        return Literal::string(indices.iter().map(|&i| characters[i]).collect());
                     },
-                    None => {
-                        self.print_custom_error(&format!("Cannot invoke Function of type 'None'"));
-                        Literal::none()
+                    _ => {
+                        self.print_custom_error(ErrorClass::InvalidIndex, &format!("{:?} is not an array or string and cannot be sliced", reference.literal_type));
+                        panic!();
                     }
                 }
             },
+
+            // For function calling/invocation, not declaration 
+            ExprType::Call | ExprType::MethodCall => {
+                let (function, arguments) = self.call_arguments();
+                self.call_result(function.call_bound(arguments, &self.operator))
+            },
+            ExprType::Spread => {
+                self.print_custom_error(ErrorClass::Runtime, "'...' can only spread an array into the arguments of a call");
+                Literal::none()
+            },
+            ExprType::NamedArgument => {
+                self.print_custom_error(ErrorClass::Runtime, "Named arguments can only be given to a call");
+                Literal::none()
+            },
             ExprType::None => {
                 return Literal::none();
             }
 
             _ => {
-                self.print_custom_error(&format!("evaluateExpr() does not account for {:?}", self.expr_type));
+                self.print_custom_error(ErrorClass::Runtime, &format!("evaluateExpr() does not account for {:?}", self.expr_type));
                 panic!();
             }
         }
@@ -1111,7 +1855,39 @@ impl Expr {
     fn print_error(&self, error: ari_errors::ErrorType){
         self.operator.print_error(error);
     }
-    fn print_custom_error(&self, message: &str){
-        self.operator.print_custom_error(message);
+    fn print_custom_error(&self, class: ErrorClass, message: &str){
+        // Underlines the whole expression, or the part of it on its first line if it spans many lines
+        let mut span: Option<(token::Token, usize, usize)> = None;
+        self.visit_tokens(&mut |tok| {
+            match span.as_mut() {
+                Some((first, start, end)) if tok.line_number == first.line_number => {
+                    *start = (*start).min(tok.start);
+                    *end = (*end).max(tok.index);
+                },
+                Some((first, start, end)) if tok.line_number != 0 && tok.line_number < first.line_number => {
+                    *first = tok.clone();
+                    *start = tok.start;
+                    *end = tok.index;
+                },
+                None if tok.line_number != 0 => span = Some((tok.clone(), tok.start, tok.index)),
+                _ => {}
+            }
+        });
+        match span {
+            Some((first, start, end)) => ari_errors::print_custom_error_span(class, message, &first.file, &first.source, start, end, first.line_number),
+            None => self.operator.print_custom_error(class, message)
+        }
+    }
+    fn visit_tokens<F: FnMut(&token::Token)>(&self, f: &mut F) {
+        f(&self.operator);
+        for argument in &self.arguments {
+            argument.visit_tokens(f);
+        }
+        if let Some(left) = self.left.as_ref() {
+            left.visit_tokens(f);
+        }
+        if let Some(right) = self.right.as_ref() {
+            right.visit_tokens(f);
+        }
     }
 }
\ No newline at end of file
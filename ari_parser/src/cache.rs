@@ -0,0 +1,54 @@
+use crate::ast;
+use serde::{Serialize, Deserialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+///////////////////////////////////////////
+// Compiled script cache
+///////////////////////////////////////////
+// 'ari compile script.ari' writes the parsed and resolved statements to script.aric.
+// Running script.ari then skips scanning, parsing and resolving if script.aric was compiled
+// from the same source by the same version of Ari.
+// Imported modules are compiled into the cache, so recompile after changing them.
+
+#[derive(Serialize, Deserialize)]
+struct CompiledScript {
+    version: String,
+    source_hash: u64,
+    statements: Vec<Box<ast::Statement>>,
+}
+
+pub fn cache_path(script_name: &str) -> String {
+    Path::new(script_name).with_extension("aric").to_string_lossy().into_owned()
+}
+
+fn source_hash(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn save(script_name: &str, contents: &str, statements: Vec<Box<ast::Statement>>) -> Result<String, String> {
+    // Returns the path of the cache
+    let compiled = CompiledScript {
+        version: crate::get_version(),
+        source_hash: source_hash(contents),
+        statements,
+    };
+    let bytes = bincode::serialize(&compiled).map_err(|e| e.to_string())?;
+    let path = cache_path(script_name);
+    fs::write(&path, bytes).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+pub fn load(script_name: &str, contents: &str) -> Option<Vec<Box<ast::Statement>>> {
+    // None if there is no cache, or it is outdated
+    let bytes = fs::read(cache_path(script_name)).ok()?;
+    let compiled: CompiledScript = bincode::deserialize(&bytes).ok()?;
+    if compiled.version != crate::get_version() || compiled.source_hash != source_hash(contents) {
+        return None;
+    }
+    Some(compiled.statements)
+}
@@ -0,0 +1,39 @@
+use crate::environment::ENV;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+///////////////////////////////////////////
+// Completion
+///////////////////////////////////////////
+// Pressing Tab in the interpreter completes the identifier before the cursor
+// with the variables, native functions and prelude functions which are currently defined.
+
+pub struct ReplHelper {}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |index| index + 1);
+        let prefix = &line[start..pos];
+        // Identifiers cannot start with a digit, and an empty prefix would list every native
+        if prefix.is_empty() || prefix.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok((start, Vec::new()));
+        }
+        let candidates = ENV.lock().unwrap().names().into_iter()
+                            .filter(|name| name.starts_with(prefix))
+                            .map(|name| Pair { display: name.to_owned(), replacement: name })
+                            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+impl Highlighter for ReplHelper {}
+impl Validator for ReplHelper {}
+impl Helper for ReplHelper {}
@@ -0,0 +1,202 @@
+use crate::ast;
+use crate::environment::ENV;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+///////////////////////////////////////////
+// Debugger
+///////////////////////////////////////////
+// 'ari debug script.ari' pauses before statements to accept commands.
+// evaluate_statement() calls before_statement() only while ENABLED is set, so normal runs are not slowed down.
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+struct Debugger {
+    source_lines: Vec<String>,
+    breakpoints: HashSet<usize>,
+    stepping: bool, // Pause before the next statement
+}
+
+lazy_static! {
+    static ref DEBUGGER: Mutex<Debugger> = Mutex::new(Debugger {
+        source_lines: Vec::new(),
+        breakpoints: HashSet::new(),
+        stepping: false,
+    });
+}
+
+pub fn start(source: &str) {
+    // Pauses before the first statement
+    {
+        let mut debugger = DEBUGGER.lock().unwrap();
+        debugger.source_lines = source.lines().map(|line| line.to_owned()).collect();
+        debugger.stepping = true;
+    }
+    ENABLED.store(true, Ordering::SeqCst);
+    print_help();
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn before_statement(statement: &ast::Statement) {
+    // Blocks only group other statements, so pausing on them would stop twice on the same line
+    if statement.statement_type == ast::StatementType::Block || statement.line_number == 0 {
+        return;
+    }
+    let line_number = statement.line_number;
+    {
+        let mut debugger = DEBUGGER.lock().unwrap();
+        if !debugger.stepping && !debugger.breakpoints.contains(&line_number) {
+            return;
+        }
+        debugger.stepping = false;
+        print_source(&debugger.source_lines, line_number, 0);
+    }
+    prompt(line_number);
+}
+
+fn prompt(line_number: usize) {
+    loop {
+        ari_errors::print_output("(debug) ");
+        let input = match ari_errors::read_line() {
+            Some(input) => input,
+            None => {
+                // No more input, so run to the end
+                ENABLED.store(false, Ordering::SeqCst);
+                return;
+            }
+        };
+        let mut parts = input.split_whitespace();
+        let command = parts.next().unwrap_or("");
+        let argument = parts.next();
+        let mut debugger = DEBUGGER.lock().unwrap();
+        match command {
+            "s" | "step" | "" => {
+                debugger.stepping = true;
+                return;
+            },
+            "c" | "continue" => {
+                return;
+            },
+            "b" | "break" | "d" | "delete" => {
+                match argument.and_then(|a| a.parse::<usize>().ok()) {
+                    Some(line) if command.starts_with('b') => {
+                        debugger.breakpoints.insert(line);
+                        println!("Breakpoint set at line {}", line);
+                    },
+                    Some(line) => {
+                        if debugger.breakpoints.remove(&line) {
+                            println!("Breakpoint removed from line {}", line);
+                        }
+                        else {
+                            println!("No breakpoint at line {}", line);
+                        }
+                    },
+                    None => println!("Usage: {} [line]", command),
+                }
+            },
+            "bl" | "breakpoints" => {
+                let mut lines = debugger.breakpoints.iter().cloned().collect::<Vec<usize>>();
+                lines.sort_unstable();
+                if lines.is_empty() {
+                    println!("No breakpoints.");
+                }
+                for line in lines {
+                    println!("Line {}", line);
+                }
+            },
+            "l" | "list" => {
+                print_source(&debugger.source_lines, line_number, 5);
+            },
+            "v" | "vars" => {
+                std::mem::drop(debugger);
+                print_environments();
+            },
+            "p" | "print" => {
+                std::mem::drop(debugger);
+                match argument {
+                    Some(name) => print_variable(name),
+                    None => println!("Usage: print [variable]"),
+                }
+            },
+            "q" | "quit" => {
+                std::mem::drop(debugger);
+                ari_errors::exit();
+            },
+            "h" | "help" => {
+                print_help();
+            },
+            _ => {
+                println!("Unknown command {}. Type help for a list of commands.", command);
+            }
+        }
+    }
+}
+
+fn print_source(source_lines: &[String], line_number: usize, context: usize) {
+    let start = line_number.saturating_sub(context).max(1);
+    let end = (line_number + context).min(source_lines.len());
+    for line in start..=end {
+        let marker = if line == line_number { "->" } else { "  " };
+        println!("{} {:>4} | {}", marker, line, source_lines[line - 1]);
+    }
+}
+
+fn print_environments() {
+    // Globals first, then every local environment from the outermost inwards
+    let (globals, locals) = {
+        let mut env_manager = ENV.lock().unwrap();
+        (env_manager.global_variables(), env_manager.local_variables())
+    };
+    println!("Globals:");
+    print_variables(&globals);
+    for (depth, variables) in locals.iter().enumerate() {
+        println!("Local environment {}:", depth + 1);
+        print_variables(variables);
+    }
+}
+
+fn print_variables(variables: &[(String, ast::Literal)]) {
+    if variables.is_empty() {
+        println!("    (empty)");
+    }
+    for (name, literal) in variables {
+        println!("    {}: {}", name, literal.describe());
+    }
+}
+
+fn print_variable(name: &str) {
+    // Innermost definition first
+    let (globals, locals) = {
+        let mut env_manager = ENV.lock().unwrap();
+        (env_manager.global_variables(), env_manager.local_variables())
+    };
+    let found = locals.iter().rev().chain(std::iter::once(&globals))
+                    .flat_map(|variables| variables.iter())
+                    .find(|(key, _)| key == name);
+    match found {
+        Some((_, literal)) if literal.literal_type == ast::LiteralType::Array => {
+            let values: Vec<&str> = literal.array_values.iter().map(|value| value.value.as_str()).collect();
+            println!("{}: {} [{}]", name, literal.describe(), values.join(", "));
+        },
+        Some((_, literal)) => println!("{}: {}", name, literal.describe()),
+        None => println!("'{}' is not defined", name),
+    }
+}
+
+fn print_help() {
+    println!("Debugger commands:");
+    println!("  step (s, Enter)   Run until the next statement");
+    println!("  continue (c)      Run until the next breakpoint");
+    println!("  break (b) [line]  Set a breakpoint");
+    println!("  delete (d) [line] Remove a breakpoint");
+    println!("  breakpoints (bl)  List breakpoints");
+    println!("  list (l)          Show the source around the current line");
+    println!("  vars (v)          Show the environment stack");
+    println!("  print (p) [name]  Show a variable");
+    println!("  quit (q)          Stop the script");
+    println!("  help (h)          Show this message");
+}
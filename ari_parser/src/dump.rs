@@ -0,0 +1,125 @@
+use crate::token;
+use crate::ast;
+
+///////////////////////////////////////////
+// Debug dumps
+///////////////////////////////////////////
+// Renders the output of the scanner and the parser for 'ari --dump-tokens' and 'ari --dump-ast'
+
+pub fn dump_tokens(tokens: &[token::Token]) -> String {
+    let mut output = String::new();
+    for tok in tokens {
+        output.push_str(&format!("{:>4}:{:<4} {:<14}", tok.line_number, tok.start, format!("{:?}", tok.token_type)));
+        if !tok.lexeme.is_empty() {
+            output.push_str(&format!(" {}", tok.lexeme));
+        }
+        if !tok.literal.is_empty() && tok.literal != tok.lexeme {
+            output.push_str(&format!(" => {:?}", tok.literal));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+pub fn dump_statements(statements: &[Box<ast::Statement>]) -> String {
+    let mut output = String::new();
+    for s in statements {
+        dump_statement(s, 0, &mut output);
+    }
+    output
+}
+
+fn push_line(label: &str, depth: usize, output: &mut String) {
+    output.push_str(&"  ".repeat(depth));
+    output.push_str(label);
+    output.push('\n');
+}
+
+fn dump_statement(statement: &ast::Statement, depth: usize, output: &mut String) {
+    let export = if statement.is_exported { "export " } else { "" };
+    let label = match statement.statement_type {
+        ast::StatementType::Function => {
+            let mut arguments: Vec<String> = statement.tokens.iter().map(|t| t.lexeme.to_string()).collect();
+            if statement.is_variadic {
+                if let Some(last) = arguments.last_mut() {
+                    last.insert_str(0, "...");
+                }
+            }
+            format!("{}Function {}({}) captures {}", export, statement.token_name.lexeme, arguments.join(", "), statement.capture_depth)
+        },
+        ast::StatementType::Let if statement.is_const => format!("{}Const {}", export, statement.declared_names().join(", ")),
+        ast::StatementType::Let => format!("{}Let {}", export, statement.declared_names().join(", ")),
+        ast::StatementType::ForIn => format!("ForIn {}", statement.token_name.lexeme),
+        ast::StatementType::Import => format!("Import {:?}", statement.token_name.literal),
+        ast::StatementType::Block if statement.is_function => "Block (function body)".to_string(),
+        _ => format!("{:?}", statement.statement_type),
+    };
+    push_line(&label, depth, output);
+    if let Some(expr) = statement.expr.as_ref() {
+        if expr.expr_type != ast::ExprType::None {
+            dump_expr(expr, depth + 1, output);
+        }
+    }
+    for (tok, default) in statement.tokens.iter().zip(&statement.defaults) {
+        if default.expr_type != ast::ExprType::None {
+            push_line(&format!("Default {}", tok.lexeme), depth + 1, output);
+            dump_expr(default, depth + 2, output);
+        }
+    }
+    for s in &statement.statements {
+        dump_statement(s, depth + 1, output);
+    }
+    if let Some(then_branch) = statement.then_branch.as_ref() {
+        dump_statement(then_branch, depth + 1, output);
+    }
+    if let Some(else_branch) = statement.else_branch.as_ref() {
+        push_line("Else", depth, output);
+        dump_statement(else_branch, depth + 1, output);
+    }
+}
+
+fn variable_depth(expr: &ast::Expr) -> String {
+    match expr.depth {
+        Some(d) => format!("depth {}", d),
+        None => "global".to_string(),
+    }
+}
+
+fn dump_expr(expr: &ast::Expr, depth: usize, output: &mut String) {
+    let label = match expr.expr_type {
+        ast::ExprType::Binary | ast::ExprType::Logical | ast::ExprType::Unary => format!("{:?} {}", expr.expr_type, expr.operator.lexeme),
+        ast::ExprType::Literal => {
+            if expr.literal.literal_type == ast::LiteralType::String {
+                format!("Literal String {:?}", expr.literal.value)
+            }
+            else {
+                format!("Literal {:?} {}", expr.literal.literal_type, expr.literal.value)
+            }
+        },
+        ast::ExprType::NamedArgument => format!("NamedArgument {}", expr.operator.lexeme),
+        ast::ExprType::Variable | ast::ExprType::Assign | ast::ExprType::ArrayAssign => {
+            format!("{:?} {} ({})", expr.expr_type, expr.operator.lexeme, variable_depth(expr))
+        },
+        _ => format!("{:?}", expr.expr_type),
+    };
+    push_line(&label, depth, output);
+    if expr.expr_type == ast::ExprType::Call || expr.expr_type == ast::ExprType::MethodCall || expr.expr_type == ast::ExprType::Slice {
+        // Callee or sliced value, then the arguments (starting with the receiver of a method) or bounds (None when omitted)
+        if let Some(right) = expr.right.as_ref() {
+            dump_expr(right, depth + 1, output);
+        }
+        for argument in &expr.arguments {
+            dump_expr(argument, depth + 1, output);
+        }
+        return;
+    }
+    for argument in &expr.arguments {
+        dump_expr(argument, depth + 1, output);
+    }
+    if let Some(left) = expr.left.as_ref() {
+        dump_expr(left, depth + 1, output);
+    }
+    if let Some(right) = expr.right.as_ref() {
+        dump_expr(right, depth + 1, output);
+    }
+}
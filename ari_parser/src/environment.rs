@@ -1,174 +1,678 @@
 use crate::token;
 use crate::ast;
 use crate::function as func;
+use ari_errors::ErrorClass;
 
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
 
 lazy_static! {
-    pub static ref ENV: Mutex<EnvManager> = {
-        let m = Mutex::new(EnvManager::new());
-        // Add native functions
-        let power = func::Function::new_native(func::NativeType::Power);
-        m.lock().unwrap().get_env().define("power".to_string(), ast::Literal::new_function(power));
-        let log = func::Function::new_native(func::NativeType::Log);
-        m.lock().unwrap().get_env().define("log".to_string(), ast::Literal::new_function(log));
-        let modulo = func::Function::new_native(func::NativeType::Modulo);
-        m.lock().unwrap().get_env().define("modulo".to_string(), ast::Literal::new_function(modulo));
-        let absolute = func::Function::new_native(func::NativeType::Absolute);
-        m.lock().unwrap().get_env().define("absolute".to_string(), ast::Literal::new_function(absolute));
-        let floor = func::Function::new_native(func::NativeType::Floor);
-        m.lock().unwrap().get_env().define("floor".to_string(), ast::Literal::new_function(floor));
-        let ceiling = func::Function::new_native(func::NativeType::Ceiling);
-        m.lock().unwrap().get_env().define("ceiling".to_string(), ast::Literal::new_function(ceiling));
-        let max = func::Function::new_native(func::NativeType::Max);
-        m.lock().unwrap().get_env().define("max".to_string(), ast::Literal::new_function(max));
-        let min = func::Function::new_native(func::NativeType::Min);
-        m.lock().unwrap().get_env().define("min".to_string(), ast::Literal::new_function(min));
-
-        let to_string = func::Function::new_native(func::NativeType::ToString);
-        m.lock().unwrap().get_env().define("to_string".to_string(), ast::Literal::new_function(to_string));
-        let to_number = func::Function::new_native(func::NativeType::ToNumber);
-        m.lock().unwrap().get_env().define("to_number".to_string(), ast::Literal::new_function(to_number));
-
-        let split = func::Function::new_native(func::NativeType::Split);
-        m.lock().unwrap().get_env().define("split".to_string(), ast::Literal::new_function(split));
-        let to_lowercase = func::Function::new_native(func::NativeType::ToLowercase);
-        m.lock().unwrap().get_env().define("to_lowercase".to_string(), ast::Literal::new_function(to_lowercase));
-        let to_uppercase = func::Function::new_native(func::NativeType::ToUpperCase);
-        m.lock().unwrap().get_env().define("to_uppercase".to_string(), ast::Literal::new_function(to_uppercase));
-
-        let length = func::Function::new_native(func::NativeType::Length);
-        m.lock().unwrap().get_env().define("length".to_string(), ast::Literal::new_function(length));
-        let insert = func::Function::new_native(func::NativeType::Insert);
-        m.lock().unwrap().get_env().define("insert".to_string(), ast::Literal::new_function(insert));
-        let remove = func::Function::new_native(func::NativeType::Remove);
-        m.lock().unwrap().get_env().define("remove".to_string(), ast::Literal::new_function(remove));
-        let map = func::Function::new_native(func::NativeType::Map);
-        m.lock().unwrap().get_env().define("map".to_string(), ast::Literal::new_function(map));
-        let filter = func::Function::new_native(func::NativeType::Filter);
-        m.lock().unwrap().get_env().define("filter".to_string(), ast::Literal::new_function(filter));
-        let reduce = func::Function::new_native(func::NativeType::Reduce);
-        m.lock().unwrap().get_env().define("reduce".to_string(), ast::Literal::new_function(reduce));
-        let range = func::Function::new_native(func::NativeType::Range);
-        m.lock().unwrap().get_env().define("range".to_string(), ast::Literal::new_function(range));
-        let linspace = func::Function::new_native(func::NativeType::Linspace);
-        m.lock().unwrap().get_env().define("linspace".to_string(), ast::Literal::new_function(linspace));
-        let repeat = func::Function::new_native(func::NativeType::Repeat);
-        m.lock().unwrap().get_env().define("repeat".to_string(), ast::Literal::new_function(repeat));
-
-        let random_choose = func::Function::new_native(func::NativeType::RandomChoose);
-        m.lock().unwrap().get_env().define("random_choose".to_string(), ast::Literal::new_function(random_choose));
-        let random_normal = func::Function::new_native(func::NativeType::RandomNormal);
-        m.lock().unwrap().get_env().define("random_normal".to_string(), ast::Literal::new_function(random_normal));
-
-        let read_file = func::Function::new_native(func::NativeType::ReadFile);
-        m.lock().unwrap().get_env().define("read_file".to_string(), ast::Literal::new_function(read_file));
-        let write_file = func::Function::new_native(func::NativeType::WriteFile);
-        m.lock().unwrap().get_env().define("write_file".to_string(), ast::Literal::new_function(write_file));
+    pub static ref ENV: Mutex<EnvManager> = Mutex::new(EnvManager::new());
+}
+
+fn define_natives(env: &mut Environment) {
+    // Add native functions
+    let power = func::Function::new_native(func::NativeType::Power);
+    env.define("power".to_string(), ast::Literal::new_function(power));
+    let log = func::Function::new_native(func::NativeType::Log);
+    env.define("log".to_string(), ast::Literal::new_function(log));
+    let modulo = func::Function::new_native(func::NativeType::Modulo);
+    env.define("modulo".to_string(), ast::Literal::new_function(modulo));
+    let absolute = func::Function::new_native(func::NativeType::Absolute);
+    env.define("absolute".to_string(), ast::Literal::new_function(absolute));
+    let floor = func::Function::new_native(func::NativeType::Floor);
+    env.define("floor".to_string(), ast::Literal::new_function(floor));
+    let ceiling = func::Function::new_native(func::NativeType::Ceiling);
+    env.define("ceiling".to_string(), ast::Literal::new_function(ceiling));
+    let max = func::Function::new_native(func::NativeType::Max);
+    env.define("max".to_string(), ast::Literal::new_function(max));
+    let min = func::Function::new_native(func::NativeType::Min);
+    env.define("min".to_string(), ast::Literal::new_function(min));
+    let round = func::Function::new_native(func::NativeType::Round);
+    env.define("round".to_string(), ast::Literal::new_function(round));
+    let clamp = func::Function::new_native(func::NativeType::Clamp);
+    env.define("clamp".to_string(), ast::Literal::new_function(clamp));
+
+    let sin = func::Function::new_native(func::NativeType::Sin);
+    env.define("sin".to_string(), ast::Literal::new_function(sin));
+    let cos = func::Function::new_native(func::NativeType::Cos);
+    env.define("cos".to_string(), ast::Literal::new_function(cos));
+    let tan = func::Function::new_native(func::NativeType::Tan);
+    env.define("tan".to_string(), ast::Literal::new_function(tan));
+    let asin = func::Function::new_native(func::NativeType::Asin);
+    env.define("asin".to_string(), ast::Literal::new_function(asin));
+    let acos = func::Function::new_native(func::NativeType::Acos);
+    env.define("acos".to_string(), ast::Literal::new_function(acos));
+    let atan = func::Function::new_native(func::NativeType::Atan);
+    env.define("atan".to_string(), ast::Literal::new_function(atan));
+    let atan2 = func::Function::new_native(func::NativeType::Atan2);
+    env.define("atan2".to_string(), ast::Literal::new_function(atan2));
+    let sqrt = func::Function::new_native(func::NativeType::Sqrt);
+    env.define("sqrt".to_string(), ast::Literal::new_function(sqrt));
+    let exp = func::Function::new_native(func::NativeType::Exp);
+    env.define("exp".to_string(), ast::Literal::new_function(exp));
+    let pi = func::Function::new_native(func::NativeType::Pi);
+    env.define("pi".to_string(), ast::Literal::new_function(pi));
+    let e = func::Function::new_native(func::NativeType::E);
+    env.define("e".to_string(), ast::Literal::new_function(e));
+
+    let type_of = func::Function::new_native(func::NativeType::TypeOf);
+    env.define("typeof".to_string(), ast::Literal::new_function(type_of));
+    let is_number = func::Function::new_native(func::NativeType::IsNumber);
+    env.define("is_number".to_string(), ast::Literal::new_function(is_number));
+    let is_integer = func::Function::new_native(func::NativeType::IsInteger);
+    env.define("is_integer".to_string(), ast::Literal::new_function(is_integer));
+    let is_string = func::Function::new_native(func::NativeType::IsString);
+    env.define("is_string".to_string(), ast::Literal::new_function(is_string));
+    let is_bool = func::Function::new_native(func::NativeType::IsBool);
+    env.define("is_bool".to_string(), ast::Literal::new_function(is_bool));
+    let is_null = func::Function::new_native(func::NativeType::IsNull);
+    env.define("is_null".to_string(), ast::Literal::new_function(is_null));
+    let is_array = func::Function::new_native(func::NativeType::IsArray);
+    env.define("is_array".to_string(), ast::Literal::new_function(is_array));
+    let is_function = func::Function::new_native(func::NativeType::IsFunction);
+    env.define("is_function".to_string(), ast::Literal::new_function(is_function));
+
+    let print_full = func::Function::new_native(func::NativeType::PrintFull);
+    env.define("print_full".to_string(), ast::Literal::new_function(print_full));
+    let set_display_limit = func::Function::new_native(func::NativeType::SetDisplayLimit);
+    env.define("set_display_limit".to_string(), ast::Literal::new_function(set_display_limit));
+
+    let eval = func::Function::new_native(func::NativeType::Eval);
+    env.define("eval".to_string(), ast::Literal::new_function(eval));
+    let vars = func::Function::new_native(func::NativeType::Vars);
+    env.define("vars".to_string(), ast::Literal::new_function(vars));
+    let is_defined = func::Function::new_native(func::NativeType::IsDefined);
+    env.define("is_defined".to_string(), ast::Literal::new_function(is_defined));
+    let help = func::Function::new_native(func::NativeType::Help);
+    env.define("help".to_string(), ast::Literal::new_function(help));
+
+    let copy = func::Function::new_native(func::NativeType::Copy);
+    env.define("copy".to_string(), ast::Literal::new_function(copy));
+    let freeze = func::Function::new_native(func::NativeType::Freeze);
+    env.define("freeze".to_string(), ast::Literal::new_function(freeze));
+
+    let to_string = func::Function::new_native(func::NativeType::ToString);
+    env.define("to_string".to_string(), ast::Literal::new_function(to_string));
+    let to_number = func::Function::new_native(func::NativeType::ToNumber);
+    env.define("to_number".to_string(), ast::Literal::new_function(to_number));
+    let string_to_bytes = func::Function::new_native(func::NativeType::StringToBytes);
+    env.define("string_to_bytes".to_string(), ast::Literal::new_function(string_to_bytes));
+    let bytes_to_string = func::Function::new_native(func::NativeType::BytesToString);
+    env.define("bytes_to_string".to_string(), ast::Literal::new_function(bytes_to_string));
+
+    let split = func::Function::new_native(func::NativeType::Split);
+    env.define("split".to_string(), ast::Literal::new_function(split));
+    let join = func::Function::new_native(func::NativeType::Join);
+    env.define("join".to_string(), ast::Literal::new_function(join));
+    let to_lowercase = func::Function::new_native(func::NativeType::ToLowercase);
+    env.define("to_lowercase".to_string(), ast::Literal::new_function(to_lowercase));
+    let to_uppercase = func::Function::new_native(func::NativeType::ToUpperCase);
+    env.define("to_uppercase".to_string(), ast::Literal::new_function(to_uppercase));
+    let trim = func::Function::new_native(func::NativeType::Trim);
+    env.define("trim".to_string(), ast::Literal::new_function(trim));
+    let replace = func::Function::new_native(func::NativeType::Replace);
+    env.define("replace".to_string(), ast::Literal::new_function(replace));
+    let substring = func::Function::new_native(func::NativeType::Substring);
+    env.define("substring".to_string(), ast::Literal::new_function(substring));
+
+    let regex_match = func::Function::new_native(func::NativeType::RegexMatch);
+    env.define("regex_match".to_string(), ast::Literal::new_function(regex_match));
+    let regex_find_all = func::Function::new_native(func::NativeType::RegexFindAll);
+    env.define("regex_find_all".to_string(), ast::Literal::new_function(regex_find_all));
+    let regex_replace = func::Function::new_native(func::NativeType::RegexReplace);
+    env.define("regex_replace".to_string(), ast::Literal::new_function(regex_replace));
+
+    let length = func::Function::new_native(func::NativeType::Length);
+    env.define("length".to_string(), ast::Literal::new_function(length));
+    let insert = func::Function::new_native(func::NativeType::Insert);
+    env.define("insert".to_string(), ast::Literal::new_function(insert));
+    let remove = func::Function::new_native(func::NativeType::Remove);
+    env.define("remove".to_string(), ast::Literal::new_function(remove));
+    let push = func::Function::new_native(func::NativeType::Push);
+    env.define("push".to_string(), ast::Literal::new_function(push));
+    let pop = func::Function::new_native(func::NativeType::Pop);
+    env.define("pop".to_string(), ast::Literal::new_function(pop));
+    let concat = func::Function::new_native(func::NativeType::Concat);
+    env.define("concat".to_string(), ast::Literal::new_function(concat));
+    let index_of = func::Function::new_native(func::NativeType::IndexOf);
+    env.define("index_of".to_string(), ast::Literal::new_function(index_of));
+    let contains = func::Function::new_native(func::NativeType::Contains);
+    env.define("contains".to_string(), ast::Literal::new_function(contains));
+    let flatten = func::Function::new_native(func::NativeType::Flatten);
+    env.define("flatten".to_string(), ast::Literal::new_function(flatten));
+    let unique = func::Function::new_native(func::NativeType::Unique);
+    env.define("unique".to_string(), ast::Literal::new_function(unique));
+    let zip = func::Function::new_native(func::NativeType::Zip);
+    env.define("zip".to_string(), ast::Literal::new_function(zip));
+    let enumerate = func::Function::new_native(func::NativeType::Enumerate);
+    env.define("enumerate".to_string(), ast::Literal::new_function(enumerate));
+    let take = func::Function::new_native(func::NativeType::Take);
+    env.define("take".to_string(), ast::Literal::new_function(take));
+    let drop = func::Function::new_native(func::NativeType::Drop);
+    env.define("drop".to_string(), ast::Literal::new_function(drop));
+    let chunk = func::Function::new_native(func::NativeType::Chunk);
+    env.define("chunk".to_string(), ast::Literal::new_function(chunk));
+    let windows = func::Function::new_native(func::NativeType::Windows);
+    env.define("windows".to_string(), ast::Literal::new_function(windows));
+    let map = func::Function::new_native(func::NativeType::Map);
+    env.define("map".to_string(), ast::Literal::new_function(map));
+    let filter = func::Function::new_native(func::NativeType::Filter);
+    env.define("filter".to_string(), ast::Literal::new_function(filter));
+    let reduce = func::Function::new_native(func::NativeType::Reduce);
+    env.define("reduce".to_string(), ast::Literal::new_function(reduce));
+    let par_map = func::Function::new_native(func::NativeType::ParMap);
+    env.define("par_map".to_string(), ast::Literal::new_function(par_map));
+    let par_filter = func::Function::new_native(func::NativeType::ParFilter);
+    env.define("par_filter".to_string(), ast::Literal::new_function(par_filter));
+    let par_reduce = func::Function::new_native(func::NativeType::ParReduce);
+    env.define("par_reduce".to_string(), ast::Literal::new_function(par_reduce));
+    let find = func::Function::new_native(func::NativeType::Find);
+    env.define("find".to_string(), ast::Literal::new_function(find));
+    let any = func::Function::new_native(func::NativeType::Any);
+    env.define("any".to_string(), ast::Literal::new_function(any));
+    let all = func::Function::new_native(func::NativeType::All);
+    env.define("all".to_string(), ast::Literal::new_function(all));
+    let count_if = func::Function::new_native(func::NativeType::CountIf);
+    env.define("count_if".to_string(), ast::Literal::new_function(count_if));
+    let range = func::Function::new_native(func::NativeType::Range);
+    env.define("range".to_string(), ast::Literal::new_function(range));
+    let linspace = func::Function::new_native(func::NativeType::Linspace);
+    env.define("linspace".to_string(), ast::Literal::new_function(linspace));
+    let repeat = func::Function::new_native(func::NativeType::Repeat);
+    env.define("repeat".to_string(), ast::Literal::new_function(repeat));
+
+    let sum = func::Function::new_native(func::NativeType::Sum);
+    env.define("sum".to_string(), ast::Literal::new_function(sum));
+    let mean = func::Function::new_native(func::NativeType::Mean);
+    env.define("mean".to_string(), ast::Literal::new_function(mean));
+    let median = func::Function::new_native(func::NativeType::Median);
+    env.define("median".to_string(), ast::Literal::new_function(median));
+    let variance = func::Function::new_native(func::NativeType::Variance);
+    env.define("variance".to_string(), ast::Literal::new_function(variance));
+    let std_dev = func::Function::new_native(func::NativeType::StdDev);
+    env.define("std_dev".to_string(), ast::Literal::new_function(std_dev));
+    let cumsum = func::Function::new_native(func::NativeType::Cumsum);
+    env.define("cumsum".to_string(), ast::Literal::new_function(cumsum));
+    let cumprod = func::Function::new_native(func::NativeType::Cumprod);
+    env.define("cumprod".to_string(), ast::Literal::new_function(cumprod));
+    let diff = func::Function::new_native(func::NativeType::Diff);
+    env.define("diff".to_string(), ast::Literal::new_function(diff));
+
+    let dot = func::Function::new_native(func::NativeType::Dot);
+    env.define("dot".to_string(), ast::Literal::new_function(dot));
+    let matmul = func::Function::new_native(func::NativeType::Matmul);
+    env.define("matmul".to_string(), ast::Literal::new_function(matmul));
+    let transpose = func::Function::new_native(func::NativeType::Transpose);
+    env.define("transpose".to_string(), ast::Literal::new_function(transpose));
 
+    let random_choose = func::Function::new_native(func::NativeType::RandomChoose);
+    env.define("random_choose".to_string(), ast::Literal::new_function(random_choose));
+    let random_normal = func::Function::new_native(func::NativeType::RandomNormal);
+    env.define("random_normal".to_string(), ast::Literal::new_function(random_normal));
+    let random_uniform = func::Function::new_native(func::NativeType::RandomUniform);
+    env.define("random_uniform".to_string(), ast::Literal::new_function(random_uniform));
+    let random_int = func::Function::new_native(func::NativeType::RandomInt);
+    env.define("random_int".to_string(), ast::Literal::new_function(random_int));
+    let shuffle = func::Function::new_native(func::NativeType::Shuffle);
+    env.define("shuffle".to_string(), ast::Literal::new_function(shuffle));
+    let random_seed = func::Function::new_native(func::NativeType::RandomSeed);
+    env.define("random_seed".to_string(), ast::Literal::new_function(random_seed));
+
+    let sleep = func::Function::new_native(func::NativeType::Sleep);
+    env.define("sleep".to_string(), ast::Literal::new_function(sleep));
+    let clock = func::Function::new_native(func::NativeType::Clock);
+    env.define("clock".to_string(), ast::Literal::new_function(clock));
+
+    let spawn = func::Function::new_native(func::NativeType::Spawn);
+    env.define("spawn".to_string(), ast::Literal::new_function(spawn));
+    let join_thread = func::Function::new_native(func::NativeType::JoinThread);
+    env.define("join_thread".to_string(), ast::Literal::new_function(join_thread));
+    let channel = func::Function::new_native(func::NativeType::Channel);
+    env.define("channel".to_string(), ast::Literal::new_function(channel));
+    let send = func::Function::new_native(func::NativeType::Send);
+    env.define("send".to_string(), ast::Literal::new_function(send));
+    let receive = func::Function::new_native(func::NativeType::Receive);
+    env.define("receive".to_string(), ast::Literal::new_function(receive));
+    let set_timeout = func::Function::new_native(func::NativeType::SetTimeout);
+    env.define("set_timeout".to_string(), ast::Literal::new_function(set_timeout));
+    let set_interval = func::Function::new_native(func::NativeType::SetInterval);
+    env.define("set_interval".to_string(), ast::Literal::new_function(set_interval));
+    let clear_timer = func::Function::new_native(func::NativeType::ClearTimer);
+    env.define("clear_timer".to_string(), ast::Literal::new_function(clear_timer));
+
+    #[cfg(feature = "os")]
+    {
+        let env_get = func::Function::new_native(func::NativeType::EnvGet);
+        env.define("env_get".to_string(), ast::Literal::new_function(env_get));
+        let env_set = func::Function::new_native(func::NativeType::EnvSet);
+        env.define("env_set".to_string(), ast::Literal::new_function(env_set));
+
+        let exec = func::Function::new_native(func::NativeType::Exec);
+        env.define("exec".to_string(), ast::Literal::new_function(exec));
+        let exec_capture = func::Function::new_native(func::NativeType::ExecCapture);
+        env.define("exec_capture".to_string(), ast::Literal::new_function(exec_capture));
+    }
+
+    let read_file = func::Function::new_native(func::NativeType::ReadFile);
+    env.define("read_file".to_string(), ast::Literal::new_function(read_file));
+    let write_file = func::Function::new_native(func::NativeType::WriteFile);
+    env.define("write_file".to_string(), ast::Literal::new_function(write_file));
+    #[cfg(feature = "os")]
+    {
+        let read_bytes = func::Function::new_native(func::NativeType::ReadBytes);
+        env.define("read_bytes".to_string(), ast::Literal::new_function(read_bytes));
+        let write_bytes = func::Function::new_native(func::NativeType::WriteBytes);
+        env.define("write_bytes".to_string(), ast::Literal::new_function(write_bytes));
+        let append_file = func::Function::new_native(func::NativeType::AppendFile);
+        env.define("append_file".to_string(), ast::Literal::new_function(append_file));
+        let file_exists = func::Function::new_native(func::NativeType::FileExists);
+        env.define("file_exists".to_string(), ast::Literal::new_function(file_exists));
+        let delete_file = func::Function::new_native(func::NativeType::DeleteFile);
+        env.define("delete_file".to_string(), ast::Literal::new_function(delete_file));
+        let copy_file = func::Function::new_native(func::NativeType::CopyFile);
+        env.define("copy_file".to_string(), ast::Literal::new_function(copy_file));
+        let list_dir = func::Function::new_native(func::NativeType::ListDir);
+        env.define("list_dir".to_string(), ast::Literal::new_function(list_dir));
+        let make_dir = func::Function::new_native(func::NativeType::MakeDir);
+        env.define("make_dir".to_string(), ast::Literal::new_function(make_dir));
+
+        let store_set = func::Function::new_native(func::NativeType::StoreSet);
+        env.define("store_set".to_string(), ast::Literal::new_function(store_set));
+        let store_get = func::Function::new_native(func::NativeType::StoreGet);
+        env.define("store_get".to_string(), ast::Literal::new_function(store_get));
+    }
+
+    let gzip_compress = func::Function::new_native(func::NativeType::GzipCompress);
+    env.define("gzip_compress".to_string(), ast::Literal::new_function(gzip_compress));
+    let gzip_decompress = func::Function::new_native(func::NativeType::GzipDecompress);
+    env.define("gzip_decompress".to_string(), ast::Literal::new_function(gzip_decompress));
+    #[cfg(feature = "os")]
+    {
+        let zip_create = func::Function::new_native(func::NativeType::ZipCreate);
+        env.define("zip_create".to_string(), ast::Literal::new_function(zip_create));
+        let zip_extract = func::Function::new_native(func::NativeType::ZipExtract);
+        env.define("zip_extract".to_string(), ast::Literal::new_function(zip_extract));
+    }
+
+    let path_join = func::Function::new_native(func::NativeType::PathJoin);
+    env.define("path_join".to_string(), ast::Literal::new_function(path_join));
+    let basename = func::Function::new_native(func::NativeType::Basename);
+    env.define("basename".to_string(), ast::Literal::new_function(basename));
+    let dirname = func::Function::new_native(func::NativeType::Dirname);
+    env.define("dirname".to_string(), ast::Literal::new_function(dirname));
+    let extension = func::Function::new_native(func::NativeType::Extension);
+    env.define("extension".to_string(), ast::Literal::new_function(extension));
+
+    #[cfg(feature = "network")]
+    {
         let serve_static_folder = func::Function::new_native(func::NativeType::ServeStaticFolder);
-        m.lock().unwrap().get_env().define("serve_static_folder".to_string(), ast::Literal::new_function(serve_static_folder));
+        env.define("serve_static_folder".to_string(), ast::Literal::new_function(serve_static_folder));
+        let route = func::Function::new_native(func::NativeType::Route);
+        env.define("route".to_string(), ast::Literal::new_function(route));
+        let response = func::Function::new_native(func::NativeType::Response);
+        env.define("response".to_string(), ast::Literal::new_function(response));
+        let serve_routes = func::Function::new_native(func::NativeType::ServeRoutes);
+        env.define("serve_routes".to_string(), ast::Literal::new_function(serve_routes));
+        let server_stop = func::Function::new_native(func::NativeType::ServerStop);
+        env.define("server_stop".to_string(), ast::Literal::new_function(server_stop));
+        let server_wait = func::Function::new_native(func::NativeType::ServerWait);
+        env.define("server_wait".to_string(), ast::Literal::new_function(server_wait));
         let web_get = func::Function::new_native(func::NativeType::WebGet);
-        m.lock().unwrap().get_env().define("web_get".to_string(), ast::Literal::new_function(web_get));
+        env.define("web_get".to_string(), ast::Literal::new_function(web_get));
+        let web_get_bytes = func::Function::new_native(func::NativeType::WebGetBytes);
+        env.define("web_get_bytes".to_string(), ast::Literal::new_function(web_get_bytes));
+        let web_get_full = func::Function::new_native(func::NativeType::WebGetFull);
+        env.define("web_get_full".to_string(), ast::Literal::new_function(web_get_full));
         let web_post = func::Function::new_native(func::NativeType::WebPost);
-        m.lock().unwrap().get_env().define("web_post".to_string(), ast::Literal::new_function(web_post));
+        env.define("web_post".to_string(), ast::Literal::new_function(web_post));
+        let web_post_form = func::Function::new_native(func::NativeType::WebPostForm);
+        env.define("web_post_form".to_string(), ast::Literal::new_function(web_post_form));
+        let web_put = func::Function::new_native(func::NativeType::WebPut);
+        env.define("web_put".to_string(), ast::Literal::new_function(web_put));
+        let web_patch = func::Function::new_native(func::NativeType::WebPatch);
+        env.define("web_patch".to_string(), ast::Literal::new_function(web_patch));
+        let web_delete = func::Function::new_native(func::NativeType::WebDelete);
+        env.define("web_delete".to_string(), ast::Literal::new_function(web_delete));
+        let web_send_raw = func::Function::new_native(func::NativeType::WebSendRaw);
+        env.define("web_send_raw".to_string(), ast::Literal::new_function(web_send_raw));
+        let web_request = func::Function::new_native(func::NativeType::WebRequest);
+        env.define("web_request".to_string(), ast::Literal::new_function(web_request));
+        let download_file = func::Function::new_native(func::NativeType::DownloadFile);
+        env.define("download_file".to_string(), ast::Literal::new_function(download_file));
+    }
+    let url_encode = func::Function::new_native(func::NativeType::UrlEncode);
+    env.define("url_encode".to_string(), ast::Literal::new_function(url_encode));
+    let url_decode = func::Function::new_native(func::NativeType::UrlDecode);
+    env.define("url_decode".to_string(), ast::Literal::new_function(url_decode));
+    let build_query = func::Function::new_native(func::NativeType::BuildQuery);
+    env.define("build_query".to_string(), ast::Literal::new_function(build_query));
 
-        m
-    };
+    // Plugins
+    #[cfg(feature = "plugins")]
+    {
+        let load_plugin = func::Function::new_native(func::NativeType::LoadPlugin);
+        env.define("load_plugin".to_string(), ast::Literal::new_function(load_plugin));
+        let ffi_call = func::Function::new_native(func::NativeType::FfiCall);
+        env.define("ffi_call".to_string(), ast::Literal::new_function(ffi_call));
+    }
+
+    // Natives registered by an embedding application
+    for (name, native_type) in func::host_natives() {
+        env.define(name, ast::Literal::new_function(func::Function::new_native(native_type)));
+    }
+
+    // Natives are named after their variable, for the profiler report
+    for (name, mut literal) in env.entries() {
+        if let Some(function) = literal.function.as_mut() {
+            function.set_name(&name);
+            env.define(name, literal);
+        }
+    }
 }
 
 pub struct EnvManager{
-    envs: Vec<Environment>,
+    globals: Environment,
+    // Every thread evaluates on its own stack of local environments and only shares the globals,
+    // so spawned threads and server handlers can run at the same time
+    stacks: HashMap<ThreadId, Vec<Environment>>,
+    frames: HashMap<ThreadId, Vec<usize>>, // Stack length when each user function call began, the caller's environments are below it
+    prelude_names: HashSet<String>, // Globals defined by the prelude, hidden like the natives
 }
 impl EnvManager {
     pub fn new() -> EnvManager {
+        let mut global_env = Environment::new();
+        define_natives(&mut global_env);
         EnvManager {
-            envs : vec![Environment::new()],
+            globals: global_env,
+            stacks: HashMap::new(),
+            frames: HashMap::new(),
+            prelude_names: HashSet::new(),
+        }
+    }
+    pub fn define_global(&mut self, key: String, value: ast::Literal) {
+        self.globals.define(key, value);
+    }
+    pub fn mark_prelude(&mut self) {
+        // Called once the prelude has run, so that its functions are not listed as user-defined
+        self.prelude_names = self.globals.keys().into_iter().collect();
+    }
+    pub fn reset(&mut self) {
+        // Removes every user-defined variable, leaving only the native functions
+        *self = EnvManager::new();
+    }
+
+    fn stack(&mut self) -> &mut Vec<Environment> {
+        // Local environments of the current thread
+        self.stacks.entry(thread::current().id()).or_insert_with(Vec::new)
+    }
+    pub fn remove_stack(&mut self) {
+        // Called when a spawned thread finishes
+        self.stacks.remove(&thread::current().id());
+        self.frames.remove(&thread::current().id());
+    }
+    pub fn enter_frame(&mut self) {
+        // Called before a user function pushes its environments
+        let start = self.stack().len();
+        self.frames.entry(thread::current().id()).or_insert_with(Vec::new).push(start);
+    }
+    pub fn exit_frame(&mut self) {
+        if let Some(frames) = self.frames.get_mut(&thread::current().id()) {
+            frames.pop();
         }
     }
+    fn visible_envs(&mut self) -> Vec<Environment> {
+        // Local environments of the innermost function call, or every one outside of functions, from the outermost inwards
+        let start = self.frames.get(&thread::current().id()).and_then(|frames| frames.last().copied()).unwrap_or(0);
+        let stack = self.stack();
+        stack[start.min(stack.len())..].to_vec()
+    }
+    fn len(&mut self) -> usize {
+        // Number of environments visible to the current thread, including the globals
+        self.stack().len() + 1
+    }
     
     pub fn get_env(&mut self) -> &mut Environment {
-        let index = self.envs.len() - 1;
-        return &mut self.envs[index]
+        if self.stack().is_empty() {
+            return &mut self.globals;
+        }
+        return self.stack().last_mut().unwrap();
     }
     pub fn get_nth_env(&mut self, index:usize) -> &mut Environment {
-        return &mut self.envs[index];
+        if index == 0 {
+            return &mut self.globals;
+        }
+        return &mut self.stack()[index - 1];
     }
     pub fn add_env(&mut self, env: Environment) {
-        self.envs.push(env);
+        self.stack().push(env);
     }
     pub fn create_env(&mut self) {
-        self.envs.push(Environment::new());
+        self.stack().push(Environment::new());
     }
     pub fn destroy_env(&mut self) {
-        let final_length = self.envs.len().saturating_sub(1);
-        self.envs.truncate(final_length);
+        self.stack().pop();
     }
     
-    pub fn get_variable(&mut self, token_key: &token::Token) -> ast::Literal {
-        let mut len = self.envs.len();
-        while len > 0 {
-            match self.get_nth_env(len - 1).get(token_key) {
-                Ok(literal) => {
-                    return literal.clone();
-                },
-                Err(_) =>{
-                    len -= 1;
-                }
+    pub fn global_variables(&mut self) -> Vec<(String, ast::Literal)> {
+        // User-defined global variables sorted by name, excluding native functions
+        let prelude_names = &self.prelude_names;
+        let mut variables = self.globals.entries().into_iter()
+                            .filter(|(key, _)| !prelude_names.contains(key))
+                            .filter(|(_, literal)| match literal.function.as_ref() {
+                                Some(function) => !function.is_native(),
+                                None => true,
+                            })
+                            .collect::<Vec<(String, ast::Literal)>>();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+        return variables;
+    }
+
+    pub fn local_variables(&mut self) -> Vec<Vec<(String, ast::Literal)>> {
+        // Variables of every local environment of the current thread, from the outermost inwards
+        self.stack().iter().map(|env| {
+            let mut variables = env.entries();
+            variables.sort_by(|a, b| a.0.cmp(&b.0));
+            variables
+        }).collect()
+    }
+
+    pub fn local_names(&mut self) -> Vec<HashSet<String>> {
+        // Names in every visible local environment, from the outermost inwards, for resolving code at runtime
+        self.visible_envs().iter().map(|env| env.keys().into_iter().collect()).collect()
+    }
+
+    pub fn visible_variables(&mut self) -> Vec<(String, ast::Literal)> {
+        // User-defined variables which the current code can see, sorted by name, where inner ones shadow outer ones
+        let mut variables = HashMap::<String, ast::Literal>::new();
+        for (key, literal) in self.global_variables() {
+            variables.insert(key, literal);
+        }
+        for env in self.visible_envs() {
+            variables.extend(env.entries());
+        }
+        // Names starting with '#' are made by the optimizer
+        let mut variables = variables.into_iter().filter(|(key, _)| !key.starts_with('#')).collect::<Vec<(String, ast::Literal)>>();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+        return variables;
+    }
+
+    pub fn is_visible(&mut self, name: &str) -> bool {
+        // Includes native and prelude functions
+        self.globals.get_value(name).is_some() || self.visible_envs().iter().any(|env| env.get_value(name).is_some())
+    }
+
+    pub fn names(&mut self) -> Vec<String> {
+        // Every name visible to the current thread, including native and prelude functions, for completion
+        let mut names = self.globals.keys();
+        for env in self.stack().iter() {
+            names.extend(env.keys());
+        }
+        names.sort_unstable();
+        names.dedup();
+        return names;
+    }
+
+    pub fn builtin_names(&mut self) -> HashSet<String> {
+        // Native and prelude functions, which scripts should not redefine
+        let prelude_names = &self.prelude_names;
+        self.globals.entries().into_iter()
+            .filter(|(key, literal)| prelude_names.contains(key) || literal.function.as_ref().map_or(false, |function| function.is_native()))
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    pub fn capture_env(&mut self, depth: usize) -> Vec<Environment> {
+        // The innermost 'depth' environments, from the outermost inwards
+        // They are shared rather than copied, so the closure sees and makes the same changes as its enclosing scopes
+        let empty = Vec::<Environment>::new();
+        let stack = self.stacks.get(&thread::current().id()).unwrap_or(&empty);
+        let envs = std::iter::once(&self.globals).chain(stack.iter()).collect::<Vec<&Environment>>();
+        return envs[envs.len().saturating_sub(depth)..].iter().map(|env| (*env).clone()).collect();
+    }
+
+    fn resolved_index(&mut self, depth: Option<usize>) -> Option<usize> {
+        // Index of the environment that the resolver bound a variable to
+        match depth {
+            Some(d) => self.len().checked_sub(d + 1),
+            None => Some(0), // Global
+        }
+    }
+
+    pub fn get_variable(&mut self, token_key: &token::Token, depth: Option<usize>) -> ast::Literal {
+        if let Some(index) = self.resolved_index(depth) {
+            if let Ok(literal) = self.get_nth_env(index).get(token_key) {
+                return literal;
             }
         }
-        token_key.print_custom_error(&format!("'{}' is an undefined variable", token_key.lexeme));
+        token_key.print_custom_error(ErrorClass::UndefinedVariable, &format!("'{}' is an undefined variable", token_key.lexeme));
         panic!()
     }
 
-    pub fn assign_variable(&mut self, tok : &token::Token, literal_value : ast::Literal) {
-        let mut len = self.envs.len();
-        while len > 0 {
-            let env = self.get_nth_env(len - 1);
+    pub fn assign_variable(&mut self, tok : &token::Token, depth: Option<usize>, literal_value : ast::Literal) {
+        if let Some(index) = self.resolved_index(depth) {
+            let env = self.get_nth_env(index);
+            if env.is_constant(&tok.lexeme) {
+                tok.print_custom_error(ErrorClass::Runtime, &format!("'{}' is a constant and cannot be reassigned", tok.lexeme));
+                return;
+            }
+            if env.is_frozen(&tok.lexeme) {
+                tok.print_custom_error(ErrorClass::Runtime, &format!("'{}' is frozen and cannot be reassigned", tok.lexeme));
+                return;
+            }
             if env.contains_key(tok) {
-                env.define(tok.lexeme.to_owned(), literal_value);
+                env.define(tok.lexeme.to_string(), literal_value);
                 return;
             }
-            else {
-                len -= 1;
+        }
+        tok.print_custom_error(ErrorClass::UndefinedVariable, &format!("'{}' variable cannot be found in this scope", tok.lexeme));
+    }
+
+    pub fn modify_variable(&mut self, tok : &token::Token, depth: Option<usize>, modify: impl FnOnce(&mut ast::Literal)) {
+        // Such as 'a[0] = 1', which changes part of the value instead of replacing it
+        if let Some(index) = self.resolved_index(depth) {
+            let env = self.get_nth_env(index);
+            if env.is_constant(&tok.lexeme) {
+                tok.print_custom_error(ErrorClass::Runtime, &format!("'{}' is a constant and cannot be modified", tok.lexeme));
+                return;
+            }
+            if env.is_frozen(&tok.lexeme) {
+                tok.print_custom_error(ErrorClass::Runtime, &format!("'{}' is frozen and its elements cannot be modified, use copy() for a modifiable copy", tok.lexeme));
+                return;
+            }
+            if env.modify(&tok.lexeme, modify) {
+                return;
             }
         }
-        tok.print_custom_error(&format!("'{}' variable cannot be found in this scope", tok.lexeme));
+        tok.print_custom_error(ErrorClass::UndefinedVariable, &format!("'{}' variable cannot be found in this scope", tok.lexeme));
     }
+
+    pub fn take_variable(&mut self, tok : &token::Token, depth: Option<usize>, take_if: impl FnOnce(&ast::Literal) -> bool) -> Option<ast::Literal> {
+        // Moves the value out and leaves null in its place, for a value which is about to be reassigned
+        // None if 'take_if' is false or the variable cannot be reassigned, which is reported when it is assigned
+        let index = self.resolved_index(depth)?;
+        let env = self.get_nth_env(index);
+        if env.is_constant(&tok.lexeme) || env.is_frozen(&tok.lexeme) {
+            return None;
+        }
+        let mut taken = None;
+        env.modify(&tok.lexeme, |literal| {
+            if take_if(literal) {
+                taken = Some(std::mem::replace(literal, ast::Literal::null()));
+            }
+        });
+        taken
+    }
+}
+
+struct Scope {
+    values: HashMap<String, ast::Literal>,
+    constants: HashSet<String>, // Declared with 'const'
 }
 
 pub struct Environment{
-    pub values: HashMap<String, ast::Literal>,
+    // Shared by the stack and every closure which captured it, so cloning an Environment does not copy its variables
+    scope: Arc<Mutex<Scope>>,
 }
 impl Clone for Environment {
     fn clone(&self) -> Environment {
         Environment {
-            values: self.values.clone(),
+            scope: Arc::clone(&self.scope),
         }
     }
 }
 impl Environment {
     pub fn new() -> Environment {
         Environment {
-            values : HashMap::<String, ast::Literal>::new(),
-
+            scope: Arc::new(Mutex::new(Scope {
+                values : HashMap::<String, ast::Literal>::new(),
+                constants: HashSet::<String>::new(),
+            })),
         }
     }
 
+    fn lock(&self) -> MutexGuard<Scope> {
+        // An error raised on another thread may have poisoned the lock, the variables are still usable
+        self.scope.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     pub fn define(&mut self, key: String, value: ast::Literal) {
         // IMPORTANT
         // Only use define() to create new variables.
         // For assignment/redefinition, use EnvManager's assign_variable() instead.
-        self.values.insert(key, value);
+        self.lock().values.insert(key, value);
+    }
+
+    pub fn define_constant(&mut self, key: String, value: ast::Literal) {
+        let mut scope = self.lock();
+        scope.constants.insert(key.to_owned());
+        scope.values.insert(key, value);
+    }
+
+    pub fn is_constant(&self, key: &str) -> bool {
+        self.lock().constants.contains(key)
+    }
+    pub fn is_frozen(&self, key: &str) -> bool {
+        self.lock().values.get(key).map_or(false, |literal| literal.is_frozen)
+    }
+    pub fn modify(&self, key: &str, modify: impl FnOnce(&mut ast::Literal)) -> bool {
+        // False if the variable does not exist
+        match self.lock().values.get_mut(key) {
+            Some(literal) => {
+                modify(literal);
+                true
+            },
+            None => false
+        }
     }
 
     pub fn contains_key(&mut self, token_key: &token::Token) -> bool {
-        return self.values.contains_key(&token_key.lexeme);
+        return self.lock().values.contains_key(&*token_key.lexeme);
     }
 
     pub fn get(&mut self, token_key: &token::Token) -> Result<ast::Literal, &str> {
-        match self.values.get(&token_key.lexeme) {
+        match self.lock().values.get(&*token_key.lexeme) {
             Some(literal) => {
                 Ok(literal.clone())
             },
@@ -177,4 +681,17 @@ impl Environment {
             }
         }
     }
+
+    pub fn get_value(&self, key: &str) -> Option<ast::Literal> {
+        self.lock().values.get(key).cloned()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.lock().values.keys().cloned().collect()
+    }
+
+    pub fn entries(&self) -> Vec<(String, ast::Literal)> {
+        // Copy of every variable, so the lock is not held while they are used
+        self.lock().values.iter().map(|(key, literal)| (key.to_owned(), literal.clone())).collect()
+    }
 }
\ No newline at end of file
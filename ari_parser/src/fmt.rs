@@ -0,0 +1,389 @@
+use crate::ast;
+use crate::token;
+
+///////////////////////////////////////////
+// Formatter
+///////////////////////////////////////////
+// Prints the parsed statements back as source code for 'ari fmt':
+//      4 spaces of indentation, one statement per line, '} else {' on one line,
+//      single spaces around binary operators and after commas.
+// The scanner drops comments, so they are found separately and put back by line number.
+// Block comments are kept as they are, and one in the middle of a line is moved after the statement.
+// Blank lines between statements are kept, but never more than one in a row.
+
+struct Comment {
+    line_number: usize, // Line where the comment starts
+    text: String,
+    trailing: bool, // Follows code on the same line
+    block: bool, // '/* */', which may span several lines
+}
+
+impl Comment {
+    fn to_source(&self) -> String {
+        if self.block { format!("/*{}*/", self.text) } else { format!("//{}", self.text) }
+    }
+}
+
+pub struct Formatter {
+    source_lines: Vec<String>,
+    comments: Vec<Comment>, // Not yet written, in order of line number
+    output: String,
+}
+
+impl Formatter {
+    pub fn new(source: &str) -> Formatter {
+        Formatter {
+            source_lines: source.lines().map(|line| line.to_owned()).collect(),
+            comments: find_comments(source),
+            output: String::new(),
+        }
+    }
+
+    pub fn format(mut self, statements: &[Box<ast::Statement>]) -> String {
+        self.write_statements(statements, 0);
+        // Comments after the last statement
+        let remaining = std::mem::take(&mut self.comments);
+        for comment in remaining {
+            self.write_blank_line_before(comment.line_number);
+            self.output.push_str(&format!("{}\n", comment.to_source()));
+        }
+        self.output
+    }
+
+    fn write_statements(&mut self, statements: &[Box<ast::Statement>], depth: usize) {
+        let mut first = true;
+        for s in statements {
+            self.write_comments_before(s.line_number, depth, first);
+            if !first {
+                self.write_blank_line_before(s.line_number);
+            }
+            first = false;
+            self.output.push_str(&indent(depth));
+            self.write_statement(s, depth);
+            if let Some(comment) = self.take_trailing_comment(s.line_number) {
+                self.output.push_str(&format!(" {}", comment));
+            }
+            self.output.push('\n');
+        }
+    }
+
+    fn write_comments_before(&mut self, line_number: usize, depth: usize, first: bool) {
+        // Writes the comments which come before a statement, including trailing comments of compound statements
+        if line_number == 0 {
+            return;
+        }
+        let mut written = 0;
+        while let Some(comment) = self.comments.first() {
+            if comment.line_number > line_number || (comment.line_number == line_number && comment.trailing) {
+                break;
+            }
+            let comment = self.comments.remove(0);
+            if !first || written > 0 {
+                self.write_blank_line_before(comment.line_number);
+            }
+            self.output.push_str(&format!("{}{}\n", indent(depth), comment.to_source()));
+            written += 1;
+        }
+    }
+
+    fn take_trailing_comment(&mut self, line_number: usize) -> Option<String> {
+        match self.comments.first() {
+            Some(comment) if comment.trailing && comment.line_number == line_number => Some(self.comments.remove(0).to_source()),
+            _ => None
+        }
+    }
+
+    fn write_blank_line_before(&mut self, line_number: usize) {
+        // Keeps a blank line from the source, except at the start of a block
+        if line_number < 2 || self.output.is_empty() || self.output.ends_with("{\n") || self.output.ends_with("\n\n") {
+            return;
+        }
+        if let Some(line) = self.source_lines.get(line_number - 2) {
+            if line.trim().is_empty() {
+                self.output.push('\n');
+            }
+        }
+    }
+
+    fn write_statement(&mut self, statement: &ast::Statement, depth: usize) {
+        let export = if statement.is_exported { "export " } else { "" };
+        match statement.statement_type {
+            ast::StatementType::Expression => {
+                self.output.push_str(&format!("{};", format_expr(statement.expr.as_ref().unwrap())));
+            },
+            ast::StatementType::Print | ast::StatementType::Println | ast::StatementType::Bai => {
+                let keyword = match statement.statement_type {
+                    ast::StatementType::Print => "print",
+                    ast::StatementType::Println => "println",
+                    _ => "bai",
+                };
+                self.output.push_str(&format!("{} {};", keyword, format_expr(statement.expr.as_ref().unwrap())));
+            },
+            ast::StatementType::Return => {
+                match statement.expr.as_ref() {
+                    Some(expr) if expr.expr_type != ast::ExprType::None => self.output.push_str(&format!("return {};", format_expr(expr))),
+                    _ => self.output.push_str("return;"),
+                }
+            },
+            ast::StatementType::Break => self.output.push_str("break;"),
+            ast::StatementType::Continue => self.output.push_str("continue;"),
+            ast::StatementType::Let => {
+                self.output.push_str(&format!("{}{};", export, format_let(statement)));
+            },
+            ast::StatementType::Import => {
+                self.output.push_str(&format!("import \"{}\";", statement.token_name.literal));
+            },
+            ast::StatementType::Function => {
+                let arguments = format_parameters(statement);
+                self.output.push_str(&format!("{}fn {}({}) ", export, statement.token_name.lexeme, arguments));
+                self.write_statement(statement.then_branch.as_ref().unwrap(), depth);
+            },
+            ast::StatementType::Block if statement.is_for_loop => {
+                // Initialisation of a 'for' loop, followed by the loop itself
+                let init = statement.statements[0].as_ref();
+                let init_text = match init.statement_type {
+                    ast::StatementType::Let => format_let(init),
+                    _ => format_expr(init.expr.as_ref().unwrap()),
+                };
+                self.write_for_loop(&init_text, statement.statements[1].as_ref(), depth);
+            },
+            ast::StatementType::Block => {
+                if statement.statements.is_empty() {
+                    self.output.push_str("{}");
+                    return;
+                }
+                self.output.push_str("{\n");
+                self.write_statements(&statement.statements, depth + 1);
+                self.output.push_str(&format!("{}}}", indent(depth)));
+            },
+            ast::StatementType::If => {
+                self.output.push_str(&format!("if ({}) ", format_expr(statement.expr.as_ref().unwrap())));
+                let then_branch = statement.then_branch.as_ref().unwrap();
+                self.write_statement(then_branch, depth);
+                if let Some(else_branch) = statement.else_branch.as_ref() {
+                    if then_branch.statement_type == ast::StatementType::Block {
+                        self.output.push_str(" else ");
+                    }
+                    else {
+                        self.output.push_str(&format!("\n{}else ", indent(depth)));
+                    }
+                    self.write_statement(else_branch, depth);
+                }
+            },
+            ast::StatementType::While if statement.is_for_loop => {
+                self.write_for_loop("", statement, depth);
+            },
+            ast::StatementType::ForIn => {
+                self.output.push_str(&format!("for ({} in {}) ", statement.token_name.lexeme, format_expr(statement.expr.as_ref().unwrap())));
+                self.write_statement(statement.then_branch.as_ref().unwrap(), depth);
+            },
+            ast::StatementType::While => {
+                self.output.push_str(&format!("while ({}) ", format_expr(statement.expr.as_ref().unwrap())));
+                self.write_statement(statement.then_branch.as_ref().unwrap(), depth);
+            },
+        }
+    }
+
+    fn write_for_loop(&mut self, init_text: &str, while_statement: &ast::Statement, depth: usize) {
+        let condition = format_expr(while_statement.expr.as_ref().unwrap());
+        let body = while_statement.then_branch.as_ref().unwrap();
+        if body.statement_type == ast::StatementType::Block && body.is_for_loop {
+            // The body was wrapped in a block together with the increment
+            let increment = format_expr(body.statements[1].expr.as_ref().unwrap());
+            self.output.push_str(&format!("for ({}; {}; {}) ", init_text, condition, increment));
+            self.write_statement(&body.statements[0], depth);
+        }
+        else {
+            self.output.push_str(&format!("for ({}; {};) ", init_text, condition));
+            self.write_statement(body, depth);
+        }
+    }
+}
+
+pub fn format_statement(statement: &ast::Statement) -> String {
+    // Single statement without comments, with compound statements cut off after their first line
+    let mut formatter = Formatter::new("");
+    formatter.write_statement(statement, 0);
+    let first_line = formatter.output.lines().next().unwrap_or("");
+    first_line.trim_end_matches('{').trim_end().to_owned()
+}
+
+fn indent(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+fn format_let(statement: &ast::Statement) -> String {
+    let name = if statement.tokens.is_empty() { statement.token_name.lexeme.to_string() } else { format!("({})", statement.declared_names().join(", ")) };
+    match statement.expr.as_ref() {
+        Some(expr) if expr.expr_type != ast::ExprType::None => {
+            let keyword = if statement.is_const { "const" } else { "let" };
+            format!("{} {} = {}", keyword, name, format_expr(expr))
+        },
+        _ => format!("let {}", statement.token_name.lexeme),
+    }
+}
+
+fn format_parameters(statement: &ast::Statement) -> String {
+    let arguments: Vec<String> = statement.tokens.iter().enumerate().map(|(i, t)| {
+        if statement.is_variadic && i == statement.tokens.len() - 1 {
+            return format!("...{}", t.lexeme);
+        }
+        match statement.defaults.get(i) {
+            Some(default) if default.expr_type != ast::ExprType::None => format!("{} = {}", t.lexeme, format_expr(default)),
+            _ => t.lexeme.to_string(),
+        }
+    }).collect();
+    arguments.join(", ")
+}
+
+fn format_option_expr(expr: &Option<Box<ast::Expr>>) -> String {
+    match expr.as_ref() {
+        Some(e) => format_expr(e),
+        None => String::new(),
+    }
+}
+
+fn format_arguments(arguments: &[Box<ast::Expr>]) -> String {
+    arguments.iter().map(|argument| format_expr(argument)).collect::<Vec<String>>().join(", ")
+}
+
+pub fn format_expr(expr: &ast::Expr) -> String {
+    match expr.expr_type {
+        ast::ExprType::Binary if expr.operator.token_type == token::TokenType::DotDot || expr.operator.token_type == token::TokenType::DotDotEqual => {
+            format!("{}{}{}", format_option_expr(&expr.left), expr.operator.lexeme, format_option_expr(&expr.right))
+        },
+        ast::ExprType::Binary | ast::ExprType::Logical => {
+            format!("{} {} {}", format_option_expr(&expr.left), expr.operator.lexeme, format_option_expr(&expr.right))
+        },
+        ast::ExprType::Unary => format!("{}{}", expr.operator.lexeme, format_option_expr(&expr.right)),
+        ast::ExprType::Grouping => format!("({})", format_option_expr(&expr.right)),
+        ast::ExprType::Literal => {
+            match expr.literal.literal_type {
+                ast::LiteralType::String => format!("\"{}\"", expr.literal.value),
+                // Numbers are written as in the source, such as '0xFF' or '1_000'
+                ast::LiteralType::Number if !expr.operator.lexeme.is_empty() => expr.operator.lexeme.to_string(),
+                _ => expr.literal.value.to_owned(),
+            }
+        },
+        ast::ExprType::ArrayCreation => format!("[{}]", format_arguments(&expr.arguments)),
+        ast::ExprType::ArrayAccess => format!("{}[{}]", format_option_expr(&expr.left), format_option_expr(&expr.right)),
+        ast::ExprType::Slice => {
+            let mut bounds: Vec<String> = expr.arguments.iter().map(|bound| format_expr(bound)).collect();
+            if bounds.last().map_or(false, |step| step.is_empty()) {
+                bounds.pop(); // 'a[1:3]' rather than 'a[1:3:]'
+            }
+            format!("{}[{}]", format_option_expr(&expr.right), bounds.join(":"))
+        },
+        ast::ExprType::Variable => expr.operator.lexeme.to_string(),
+        ast::ExprType::Assign => format!("{} = {}", expr.operator.lexeme, format_option_expr(&expr.right)),
+        ast::ExprType::ArrayAssign => {
+            let indices: Vec<String> = expr.arguments.iter().map(|index| format!("[{}]", format_expr(index))).collect();
+            format!("{}{} = {}", expr.operator.lexeme, indices.join(""), format_option_expr(&expr.right))
+        },
+        ast::ExprType::MultipleAssign => {
+            let values = expr.right.as_ref().unwrap();
+            // 'a, b = b, a' is parsed into an Array made from the '=' token
+            let values_text = if values.expr_type == ast::ExprType::ArrayCreation && values.operator.token_type == token::TokenType::Equal {
+                format_arguments(&values.arguments)
+            }
+            else {
+                format_expr(values)
+            };
+            format!("{} = {}", format_arguments(&expr.arguments), values_text)
+        },
+        ast::ExprType::Call => format!("{}({})", format_option_expr(&expr.right), format_arguments(&expr.arguments)),
+        ast::ExprType::MethodCall => {
+            format!("{}.{}({})", format_expr(&expr.arguments[0]), format_option_expr(&expr.right), format_arguments(&expr.arguments[1..]))
+        },
+        ast::ExprType::Spread => format!("...{}", format_option_expr(&expr.right)),
+        ast::ExprType::NamedArgument => format!("{} = {}", expr.operator.lexeme, format_option_expr(&expr.right)),
+        ast::ExprType::None => String::new(),
+    }
+}
+
+fn find_comments(source: &str) -> Vec<Comment> {
+    // Line and block comments outside of strings. Block comments nest, like in the scanner
+    let mut comments = Vec::<Comment>::new();
+    let chars: Vec<char> = source.chars().collect();
+    let mut line_number = 1;
+    let mut code_on_line = false;
+    let mut in_string = false;
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        let next = chars.get(index + 1).copied();
+        if c == '\n' {
+            line_number += 1;
+            code_on_line = false;
+        }
+        else if in_string {
+            in_string = c != '"';
+        }
+        else if c == '/' && next == Some('/') {
+            let end = chars[index..].iter().position(|&c| c == '\n').map_or(chars.len(), |position| index + position);
+            let text: String = chars[index + 2..end].iter().collect();
+            comments.push(Comment { line_number, text: text.trim_end().to_owned(), trailing: code_on_line, block: false });
+            index = end;
+            continue;
+        }
+        else if c == '/' && next == Some('*') {
+            let (start, start_line) = (index, line_number);
+            let mut depth = 0;
+            while index < chars.len() {
+                if chars[index] == '/' && chars.get(index + 1) == Some(&'*') {
+                    depth += 1;
+                    index += 2;
+                }
+                else if chars[index] == '*' && chars.get(index + 1) == Some(&'/') {
+                    depth -= 1;
+                    index += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                else {
+                    if chars[index] == '\n' {
+                        line_number += 1;
+                    }
+                    index += 1;
+                }
+            }
+            let text: String = chars[start + 2..index.saturating_sub(2).max(start + 2)].iter().collect();
+            comments.push(Comment { line_number: start_line, text, trailing: code_on_line, block: true });
+            continue;
+        }
+        else if c == '"' {
+            in_string = true;
+            code_on_line = true;
+        }
+        else if !c.is_whitespace() {
+            code_on_line = true;
+        }
+        index += 1;
+    }
+    comments
+}
+
+#[cfg(test)]
+mod tests {
+    fn formatted(source: &str) -> String {
+        super::Formatter::new(source).format(&crate::parse(source, "", 1))
+    }
+
+    #[test]
+    fn indents_blocks_and_spaces_operators() {
+        let source = "fn add(a,b){return a+b;}\nif(add(1,2)>2){println \"big\";}else{println \"small\";}\n";
+        let expected = "fn add(a, b) {\n    return a + b;\n}\nif (add(1, 2) > 2) {\n    println \"big\";\n} else {\n    println \"small\";\n}\n";
+        assert_eq!(formatted(source), expected);
+    }
+
+    #[test]
+    fn keeps_comments_and_is_idempotent() {
+        let source = "// Totals\nlet total = 0; // Running sum\n\n\n\nfor (i in 0..3) { total = total + i; }\n";
+        let once = formatted(source);
+        assert!(once.contains("// Totals\n"), "{}", once);
+        assert!(once.contains("let total = 0; // Running sum\n"), "{}", once);
+        assert!(!once.contains("\n\n\n"), "{}", once);
+        assert_eq!(formatted(&once), once);
+    }
+}
@@ -3,7 +3,13 @@ use crate::ast;
 use crate::ast::Expr;
 use crate::environment::Environment;
 use crate::environment::ENV;
-//use rayon::prelude::*; // For array operations/fast parallelism
+use crate::profile;
+use crate::limits;
+use crate::scanner;
+use crate::parser;
+use crate::resolver;
+use ari_errors::ErrorClass;
+use rayon::prelude::*; // For array operations/fast parallelism
 
 #[derive(Debug)]
 #[derive(Clone, Copy)]
@@ -28,117 +34,351 @@ pub enum NativeType {
     Ceiling,
     Max,
     Min,
+    Round,
+    Clamp,
+
+    // Trigonometry and other math
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sqrt,
+    Exp,
+    Pi,
+    E,
     
+    // Types
+    TypeOf,
+    IsNumber,
+    IsInteger,
+    IsString,
+    IsBool,
+    IsNull,
+    IsArray,
+    IsFunction,
+
+    // Printing
+    PrintFull,
+    SetDisplayLimit,
+
+    // Code evaluation
+    Eval,
+    Vars,
+    IsDefined,
+    Help,
+
+    // Copying
+    Copy,
+    Freeze,
+
     // String/Number conversions
     ToString,
     ToNumber,
+    StringToBytes,
+    BytesToString,
 
     // String operations
     Split,
+    Join,
     ToLowercase,
     ToUpperCase,
+    Trim,
+    Replace,
+    Substring,
+
+    // Regular expressions
+    RegexMatch,
+    RegexFindAll,
+    RegexReplace,
 
     // Array operations
     Length, // Also works for string
     Insert, // Also works for string
     Remove, // Also works for string
+    Push,
+    Pop,
+    Concat,
+    IndexOf, // Also works for string
+    Contains, // Also works for string
+    Flatten,
+    Unique,
+    Zip,
+    Enumerate,
+    Take,
+    Drop,
+    Chunk,
+    Windows,
 
     Map,
     Filter,
     Reduce,
+    ParMap,
+    ParFilter,
+    ParReduce,
+    Find,
+    Any,
+    All,
+    CountIf,
 
     Range,
     Linspace,
     Repeat,
 
+    // Statistics
+    Sum,
+    Mean,
+    Median,
+    Variance,
+    StdDev,
+    Cumsum,
+    Cumprod,
+    Diff,
+
+    // Linear algebra
+    Dot,
+    Matmul,
+    Transpose,
+
     // Random generation
     RandomChoose,
     RandomNormal,
+    RandomUniform,
+    RandomInt,
+    Shuffle,
+    RandomSeed,
+
+    // Time
+    Sleep,
+    Clock,
+
+    // Threads
+    Spawn,
+    JoinThread,
+    Channel,
+    Send,
+    Receive,
+    SetTimeout,
+    SetInterval,
+    ClearTimer,
+
+    // Environment variables
+    EnvGet,
+    EnvSet,
+
+    // Shell commands
+    Exec,
+    ExecCapture,
 
     // File operations
     ReadFile,
     WriteFile,
+    AppendFile,
+    ReadBytes,
+    WriteBytes,
+    FileExists,
+    DeleteFile,
+    CopyFile,
+    ListDir,
+    MakeDir,
+
+    // Key-value store
+    StoreSet,
+    StoreGet,
+
+    // Archives
+    GzipCompress,
+    GzipDecompress,
+    ZipCreate,
+    ZipExtract,
+
+    // Path operations
+    PathJoin,
+    Basename,
+    Dirname,
+    Extension,
 
     // Web
     ServeStaticFolder,
+    Route,
+    Response,
+    ServeRoutes,
+    ServerStop,
+    ServerWait,
     WebGet,
+    WebGetBytes,
+    WebGetFull,
     WebPost,
+    WebPostForm,
+    WebPut,
+    WebPatch,
+    WebDelete,
+    WebSendRaw,
+    WebRequest,
+    DownloadFile,
+    UrlEncode,
+    UrlDecode,
+    BuildQuery,
+
+    // Plugins
+    LoadPlugin,
+    FfiCall,
+
+    // Registered by an embedding application, index into HOST_NATIVES
+    Host(usize),
 
     None, // Placeholder
 }
 
+thread_local! {
+    // Call left by 'return f(x);', which is made as soon as the function containing it returns
+    static TAIL_CALL: std::cell::RefCell<Option<(Function, Vec<Option<ast::Literal>>, token::Token)>> = std::cell::RefCell::new(None);
+}
+
+// Every created function gets a new id, which copies of it keep, so that '==' compares functions by identity
+static NEXT_FUNCTION_ID: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
 pub struct Function {
+    id: usize,
     function_type: FunctionType,
     arguments: Vec<token::Token>,
+    defaults: Vec<Box<ast::Expr>>, // Default value of each argument, ExprType::None if it must be given
+    variadic: bool, // The last argument collects the remaining arguments into an array
     user_defined: Option<Box<ast::Statement>>,
     native_type: NativeType,
-    pub closure_env: Option<Environment>,
-    pub variable_token: token::Token, // For updating the closure in the environment
+    pub closure_env: Vec<Environment>, // Enclosing local environments, shared with the scopes which declared the function
+    declaration_token: token::Token, // Name given in the 'fn' declaration, or the variable of a native
+    doc: String, // User-defined only, text of the '///' comments before the declaration
 }
 
 impl Clone for Function { // Enables Function to be copied
     fn clone(&self) -> Function {
         Function {
+            id: self.id,
             function_type: self.function_type,
             arguments: self.arguments.clone(),
+            defaults: self.defaults.clone(),
+            variadic: self.variadic,
             user_defined: self.user_defined.clone(),
             native_type: self.native_type,
             closure_env: self.closure_env.clone(),
-            variable_token: self.variable_token.clone(),
+            declaration_token: self.declaration_token.clone(),
+            doc: self.doc.clone(),
         }
     }
 }
 
 impl Function {
     pub fn new(function_type: FunctionType, arguments: Vec<token::Token>, user_defined: Option<Box<ast::Statement>>, native_type: NativeType,
-                closure_env: Option<Environment>, variable_token: token::Token) -> Function {
+                closure_env: Vec<Environment>, declaration_token: token::Token) -> Function {
         Function {
+            id: NEXT_FUNCTION_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
             function_type,
             arguments,
+            defaults: Vec::<Box<ast::Expr>>::new(),
+            variadic: false,
             user_defined,
             native_type,
             closure_env,
-            variable_token,
+            declaration_token,
+            doc: String::new(),
         }
     }
-    pub fn new_user(arguments: Vec<token::Token>, user_defined: Option<Box<ast::Statement>>, closure_env: Environment, variable_token: token::Token) -> Function {
-        Function::new(FunctionType::UserDefined, arguments, user_defined, NativeType::None, Some(closure_env), variable_token)
+    pub fn new_user(arguments: Vec<token::Token>, defaults: Vec<Box<ast::Expr>>, variadic: bool, user_defined: Option<Box<ast::Statement>>, closure_env: Vec<Environment>, declaration_token: token::Token, doc: &str) -> Function {
+        let mut function = Function::new(FunctionType::UserDefined, arguments, user_defined, NativeType::None, closure_env, declaration_token);
+        function.defaults = defaults;
+        function.variadic = variadic;
+        function.doc = doc.to_owned();
+        function
     }
     pub fn new_native(native_type: NativeType) -> Function {
         let number_of_args = Function::number_of_args(native_type);
-        Function::new(FunctionType::Native, Vec::<token::Token>::with_capacity(number_of_args), None, native_type, None, token::Token::none())
+        Function::new(FunctionType::Native, Vec::<token::Token>::with_capacity(number_of_args), None, native_type, Vec::<Environment>::new(), token::Token::none())
     }
     pub fn none() -> Function {
-        Function::new(FunctionType::None, Vec::<token::Token>::new(), None, NativeType::None, None, token::Token::none())
+        Function::new(FunctionType::None, Vec::<token::Token>::new(), None, NativeType::None, Vec::<Environment>::new(), token::Token::none())
+    }
+
+    pub fn is_same(&self, other: &Function) -> bool {
+        self.id == other.id
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.declaration_token.lexeme = token::intern(name);
+    }
+
+    pub fn name(&self) -> &str {
+        &self.declaration_token.lexeme
     }
 
     pub fn call(&self, arguments: Vec<ast::Literal>, tok: &token::Token) -> Option<ast::Literal> {
-        
+        self.call_bound(arguments.into_iter().map(Some).collect(), tok)
+    }
+
+    pub fn call_bound(&self, arguments: Vec<Option<ast::Literal>>, tok: &token::Token) -> Option<ast::Literal> {
+        // Arguments which are None were left out, and take their default value
+        let mut result = self.call_once(arguments, tok);
+        // Tail calls of the function, and those of the functions it tail-calls, are made one after another
+        while result.as_ref().map_or(false, |literal| literal.literal_type == ast::LiteralType::TailCall) {
+            let (function, arguments, tail_tok) = TAIL_CALL.with(|tail_call| tail_call.borrow_mut().take()).unwrap();
+            result = function.call_once(arguments, &tail_tok);
+        }
+        result
+    }
+
+    pub fn tail_call(self, arguments: Vec<Option<ast::Literal>>, tok: &token::Token) -> ast::Literal {
+        // Left for call_bound() to make, once the function which contains 'return f(x);' has returned
+        TAIL_CALL.with(|tail_call| *tail_call.borrow_mut() = Some((self, arguments, tok.clone())));
+        ast::Literal::new_value(ast::LiteralType::TailCall, String::new())
+    }
+
+    fn call_once(&self, arguments: Vec<Option<ast::Literal>>, tok: &token::Token) -> Option<ast::Literal> {
+        limits::check_time(tok);
+        let _guard = limits::enter_call(tok);
+        let result = if profile::is_enabled() {
+            let started = profile::enter(&self.declaration_token.lexeme);
+            let result = self.invoke(arguments, tok);
+            profile::exit(&self.declaration_token.lexeme, started, 1);
+            result
+        }
+        else {
+            self.invoke(arguments, tok)
+        };
+        if let Some(literal) = result.as_ref() {
+            limits::check_value(literal, tok);
+        }
+        result
+    }
+
+    fn invoke(&self, arguments: Vec<Option<ast::Literal>>, tok: &token::Token) -> Option<ast::Literal> {
         let result = match self.function_type {
             FunctionType::UserDefined => {
                 //println!("Invoke user! {}", self.arguments.len());
-                ENV.lock().unwrap().add_env(self.closure_env.as_ref().unwrap().clone());
-                
-                ENV.lock().unwrap().create_env();
-                let mut r = Some(self.call_user(arguments));
-                /*
-                if r.as_ref().unwrap().is_return {
-                    r = Some(ast::Literal::none());
-                }*/
-
-                ENV.lock().unwrap().destroy_env();
-
-                let cloned = Some(ENV.lock().unwrap().get_env().clone());
-                ENV.lock().unwrap().destroy_env();
+                // The captured environments are shared, so changes made by the call stay visible to its enclosing scopes
+                // and to other closures over the same variables
+                {
+                    let mut env_manager = ENV.lock().unwrap();
+                    env_manager.enter_frame();
+                    for env in &self.closure_env {
+                        env_manager.add_env(env.clone());
+                    }
+                    env_manager.create_env();
+                }
+                let r = Some(self.call_user(arguments));
 
-                let mut updated_function = self.clone();
-                updated_function.closure_env = cloned;
-                ENV.lock().unwrap().assign_variable(&self.variable_token, ast::Literal::new_function(updated_function));
+                let mut env_manager = ENV.lock().unwrap();
+                for _ in 0..self.closure_env.len() + 1 {
+                    env_manager.destroy_env();
+                }
+                env_manager.exit_frame();
                 r
             },
             FunctionType::Native => {
                 //println!("Invoke native! {}", self.arguments.len());
                 ENV.lock().unwrap().create_env();
+                let arguments = arguments.into_iter().map(|a| a.unwrap_or_else(ast::Literal::none)).collect();
                 let r = Some(self.call_native(arguments, tok));
                 ENV.lock().unwrap().destroy_env();
                 r
@@ -150,10 +390,19 @@ impl Function {
         return result;
     }
 
-    pub fn call_user(&self, arguments: Vec<ast::Literal>) -> ast::Literal {
-        for i in 0..arguments.len() {
-            // Insert arg name: arg value into new scope
-            ENV.lock().unwrap().get_env().define(self.arguments.get(i).unwrap().lexeme.to_string(), arguments.get(i).unwrap().clone());
+    pub fn call_user(&self, arguments: Vec<Option<ast::Literal>>) -> ast::Literal {
+        for i in 0..self.arguments.len() {
+            // Insert arg name: arg value into new scope. Missing args take their default, which can read the args before it
+            let value = if self.variadic && i == self.arguments.len() - 1 {
+                ast::Literal::new_array(arguments.get(i..).map_or(Vec::<ast::Literal>::new(), |rest| rest.iter().flatten().cloned().collect()))
+            }
+            else {
+                match arguments.get(i) {
+                    Some(Some(literal)) => literal.clone(),
+                    _ => self.defaults[i].evaluate_expr(),
+                }
+            };
+            ENV.lock().unwrap().get_env().define(self.arguments.get(i).unwrap().lexeme.to_string(), value);
         }
         return self.user_defined.as_ref().unwrap().evaluate_statement()
     }
@@ -184,39 +433,213 @@ impl Function {
             NativeType::Min => {
                 min(arguments, tok)
             },
+            NativeType::Round => {
+                round(arguments, tok)
+            },
+            NativeType::Clamp => {
+                clamp(arguments, tok)
+            },
+            NativeType::Sin => {
+                unary_math(arguments, tok, "sin", f64::sin)
+            },
+            NativeType::Cos => {
+                unary_math(arguments, tok, "cos", f64::cos)
+            },
+            NativeType::Tan => {
+                unary_math(arguments, tok, "tan", f64::tan)
+            },
+            NativeType::Asin => {
+                unary_math(arguments, tok, "asin", f64::asin)
+            },
+            NativeType::Acos => {
+                unary_math(arguments, tok, "acos", f64::acos)
+            },
+            NativeType::Atan => {
+                unary_math(arguments, tok, "atan", f64::atan)
+            },
+            NativeType::Atan2 => {
+                atan2(arguments, tok)
+            },
+            NativeType::Sqrt => {
+                unary_math(arguments, tok, "sqrt", f64::sqrt)
+            },
+            NativeType::Exp => {
+                unary_math(arguments, tok, "exp", f64::exp)
+            },
+            NativeType::Pi => {
+                ast::Literal::number(std::f64::consts::PI)
+            },
+            NativeType::E => {
+                ast::Literal::number(std::f64::consts::E)
+            },
+            NativeType::TypeOf => {
+                type_of(arguments)
+            },
+            NativeType::IsNumber => {
+                is_type(arguments, ast::LiteralType::Number)
+            },
+            NativeType::IsInteger => {
+                is_integer(arguments)
+            },
+            NativeType::IsString => {
+                is_type(arguments, ast::LiteralType::String)
+            },
+            NativeType::IsBool => {
+                is_type(arguments, ast::LiteralType::Bool)
+            },
+            NativeType::IsNull => {
+                is_type(arguments, ast::LiteralType::Null)
+            },
+            NativeType::IsArray => {
+                is_type(arguments, ast::LiteralType::Array)
+            },
+            NativeType::IsFunction => {
+                is_type(arguments, ast::LiteralType::Function)
+            },
+            NativeType::PrintFull => {
+                print_full(arguments)
+            },
+            NativeType::SetDisplayLimit => {
+                set_display_limit(arguments, tok)
+            },
+            NativeType::Eval => {
+                eval(arguments, tok)
+            },
+            NativeType::Vars => {
+                vars(arguments)
+            },
+            NativeType::IsDefined => {
+                is_defined(arguments, tok)
+            },
+            NativeType::Help => {
+                help(arguments, tok)
+            },
+            NativeType::Copy => {
+                copy(arguments)
+            },
+            NativeType::Freeze => {
+                freeze(arguments)
+            },
             NativeType::ToString => {
-                to_string(arguments, tok)
+                to_string(arguments)
             },
             NativeType::ToNumber => {
                 to_number(arguments, tok)
             },
+            NativeType::StringToBytes => {
+                string_to_bytes(arguments, tok)
+            },
+            NativeType::BytesToString => {
+                bytes_to_string(arguments, tok)
+            },
             NativeType::Split => {
                 split(arguments, tok)
             },
+            NativeType::Join => {
+                join(arguments, tok)
+            },
             NativeType::ToLowercase => {
                 to_lowercase(arguments, tok)
             },
             NativeType::ToUpperCase => {
                 to_uppercase(arguments, tok)
             },
+            NativeType::Trim => {
+                trim(arguments, tok)
+            },
+            NativeType::Replace => {
+                replace(arguments, tok)
+            },
+            NativeType::Substring => {
+                substring(arguments, tok)
+            },
+            NativeType::RegexMatch => {
+                regex_match(arguments, tok)
+            },
+            NativeType::RegexFindAll => {
+                regex_find_all(arguments, tok)
+            },
+            NativeType::RegexReplace => {
+                regex_replace(arguments, tok)
+            },
             NativeType::Length => {
                 length(arguments, tok)
             },
             NativeType::Insert => {
                 insert(arguments, tok)
             },
+            NativeType::Push => {
+                push(arguments, tok)
+            },
+            NativeType::Pop => {
+                pop(arguments, tok)
+            },
+            NativeType::Concat => {
+                concat(arguments, tok)
+            },
             NativeType::Remove => {
                 remove(arguments, tok)
             },
+            NativeType::IndexOf => {
+                index_of(arguments, tok)
+            },
+            NativeType::Contains => {
+                contains(arguments, tok)
+            },
+            NativeType::Flatten => {
+                flatten(arguments, tok)
+            },
+            NativeType::Unique => {
+                unique(arguments, tok)
+            },
+            NativeType::Zip => {
+                zip(arguments, tok)
+            },
+            NativeType::Enumerate => {
+                enumerate(arguments, tok)
+            },
+            NativeType::Take => {
+                take(arguments, tok)
+            },
+            NativeType::Drop => {
+                drop(arguments, tok)
+            },
+            NativeType::Chunk => {
+                chunk(arguments, tok)
+            },
+            NativeType::Windows => {
+                windows(arguments, tok)
+            },
             NativeType::Map => {
                 map(arguments, tok)
             },
             NativeType::Filter => {
                 filter(arguments, tok)
             },
+            NativeType::ParMap => {
+                par_map(arguments, tok)
+            },
+            NativeType::ParFilter => {
+                par_filter(arguments, tok)
+            },
+            NativeType::ParReduce => {
+                par_reduce(arguments, tok)
+            },
             NativeType::Reduce => {
                 reduce(arguments, tok)
             },
+            NativeType::Find => {
+                find(arguments, tok)
+            },
+            NativeType::Any => {
+                any(arguments, tok)
+            },
+            NativeType::All => {
+                all(arguments, tok)
+            },
+            NativeType::CountIf => {
+                count_if(arguments, tok)
+            },
             NativeType::Range => {
                 range(arguments, tok)
             },
@@ -226,32 +649,271 @@ impl Function {
             NativeType::Repeat => {
                 repeat(arguments, tok)
             },
+            NativeType::Sum => {
+                sum(arguments, tok)
+            },
+            NativeType::Mean => {
+                mean(arguments, tok)
+            },
+            NativeType::Median => {
+                median(arguments, tok)
+            },
+            NativeType::Variance => {
+                variance(arguments, tok)
+            },
+            NativeType::StdDev => {
+                std_dev(arguments, tok)
+            },
+            NativeType::Cumsum => {
+                cumsum(arguments, tok)
+            },
+            NativeType::Cumprod => {
+                cumprod(arguments, tok)
+            },
+            NativeType::Diff => {
+                diff(arguments, tok)
+            },
+            NativeType::Dot => {
+                dot(arguments, tok)
+            },
+            NativeType::Matmul => {
+                matmul(arguments, tok)
+            },
+            NativeType::Transpose => {
+                transpose(arguments, tok)
+            },
             NativeType::RandomChoose => {
                 random_choose(arguments, tok)
             },
             NativeType::RandomNormal => {
                 random_normal(arguments, tok)
             },
+            NativeType::RandomUniform => {
+                random_uniform(arguments, tok)
+            },
+            NativeType::RandomInt => {
+                random_int(arguments, tok)
+            },
+            NativeType::Shuffle => {
+                shuffle(arguments, tok)
+            },
+            NativeType::RandomSeed => {
+                random_seed(arguments, tok)
+            },
+            NativeType::Sleep => {
+                sleep(arguments, tok)
+            },
+            NativeType::Clock => {
+                clock(arguments, tok)
+            },
+            NativeType::Spawn => {
+                spawn(arguments, tok)
+            },
+            NativeType::JoinThread => {
+                join_thread(arguments, tok)
+            },
+            NativeType::Channel => {
+                channel(arguments, tok)
+            },
+            NativeType::Send => {
+                send(arguments, tok)
+            },
+            NativeType::Receive => {
+                receive(arguments, tok)
+            },
+            NativeType::SetTimeout => {
+                set_timeout(arguments, tok)
+            },
+            NativeType::SetInterval => {
+                set_interval(arguments, tok)
+            },
+            NativeType::ClearTimer => {
+                clear_timer(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::EnvGet => {
+                env_get(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::EnvSet => {
+                env_set(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::Exec => {
+                exec(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::ExecCapture => {
+                exec_capture(arguments, tok)
+            },
             NativeType::ReadFile => {
                 read_file(arguments, tok)
             },
             NativeType::WriteFile => {
                 write_file(arguments, tok)
             },
+            #[cfg(feature = "os")]
+            NativeType::ReadBytes => {
+                read_bytes(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::WriteBytes => {
+                write_bytes(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::AppendFile => {
+                append_file(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::FileExists => {
+                file_exists(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::DeleteFile => {
+                delete_file(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::CopyFile => {
+                copy_file(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::ListDir => {
+                list_dir(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::StoreSet => {
+                store_set(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::StoreGet => {
+                store_get(arguments, tok)
+            },
+            NativeType::GzipCompress => {
+                gzip_compress(arguments, tok)
+            },
+            NativeType::GzipDecompress => {
+                gzip_decompress(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::ZipCreate => {
+                zip_create(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::ZipExtract => {
+                zip_extract(arguments, tok)
+            },
+            #[cfg(feature = "os")]
+            NativeType::MakeDir => {
+                make_dir(arguments, tok)
+            },
+            NativeType::PathJoin => {
+                path_join(arguments, tok)
+            },
+            NativeType::Basename => {
+                basename(arguments, tok)
+            },
+            NativeType::Dirname => {
+                dirname(arguments, tok)
+            },
+            NativeType::Extension => {
+                extension(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::Route => {
+                route(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::Response => {
+                response(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::ServeRoutes => {
+                serve_routes(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::ServerStop => {
+                server_stop(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::ServerWait => {
+                server_wait(arguments, tok)
+            },
+            #[cfg(feature = "network")]
             NativeType::ServeStaticFolder => {
                 serve_static_folder(arguments, tok)
             },
+            #[cfg(feature = "network")]
             NativeType::WebGet => {
                 web_get(arguments, tok)
             },
+            #[cfg(feature = "network")]
+            NativeType::WebGetBytes => {
+                web_get_bytes(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::WebGetFull => {
+                web_get_full(arguments, tok)
+            },
+            #[cfg(feature = "network")]
             NativeType::WebPost => {
                 web_post(arguments, tok)
             },
+            #[cfg(feature = "network")]
+            NativeType::WebPostForm => {
+                web_post_form(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::WebPut => {
+                web_put(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::WebPatch => {
+                web_patch(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::WebDelete => {
+                web_delete(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::WebSendRaw => {
+                web_send_raw(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::WebRequest => {
+                web_request(arguments, tok)
+            },
+            #[cfg(feature = "network")]
+            NativeType::DownloadFile => {
+                download_file(arguments, tok)
+            },
+            NativeType::UrlEncode => {
+                url_encode(arguments, tok)
+            },
+            NativeType::UrlDecode => {
+                url_decode(arguments, tok)
+            },
+            NativeType::BuildQuery => {
+                build_query(arguments, tok)
+            },
+            #[cfg(feature = "plugins")]
+            NativeType::LoadPlugin => {
+                load_plugin(arguments, tok)
+            },
+            #[cfg(feature = "plugins")]
+            NativeType::FfiCall => {
+                ffi_call(arguments, tok)
+            },
+            NativeType::Host(index) => {
+                call_host_native(index, arguments, tok)
+            },
             _ => {
                 panic!("call_native() has not accounted for {:?}", self.native_type);
             }
         }
     }
+    pub fn is_native(&self) -> bool {
+        self.function_type == FunctionType::Native
+    }
     pub fn arg_length(&self) -> usize {
         if self.function_type == FunctionType::UserDefined {
             self.arguments.len()
@@ -260,53 +922,640 @@ impl Function {
             Function::number_of_args(self.native_type)
         }
     }
-    pub fn number_of_args(native_type: NativeType) -> usize {
+    pub fn min_arg_length(&self) -> usize {
+        if self.function_type != FunctionType::UserDefined {
+            return Function::min_number_of_args(self.native_type);
+        }
+        // Arguments with a default value can be left out
+        let optional = self.defaults.iter().filter(|d| d.expr_type != ast::ExprType::None).count();
+        let variadic = if self.variadic { 1 } else { 0 };
+        self.arg_length() - optional - variadic
+    }
+    pub fn is_variadic(&self) -> bool {
+        self.variadic
+    }
+    fn has_default(&self, index: usize) -> bool {
+        if self.function_type != FunctionType::UserDefined {
+            return Function::native_default(self.native_type, index).is_some();
+        }
+        self.defaults.get(index).map_or(false, |d| d.expr_type != ast::ExprType::None)
+    }
+    pub fn argument_names(&self) -> Vec<String> {
+        // Names which can be given at a call site, the variadic argument cannot be named
+        if self.function_type == FunctionType::UserDefined {
+            let named = if self.variadic { self.arguments.len() - 1 } else { self.arguments.len() };
+            self.arguments[..named].iter().map(|a| a.lexeme.to_string()).collect()
+        }
+        else {
+            Function::native_argument_names(self.native_type).iter().map(|n| n.to_string()).collect()
+        }
+    }
+    pub fn help(&self) -> String {
+        // Signature followed by the description, such as 'map(source, function)'
+        let (parameters, description) = match self.function_type {
+            FunctionType::UserDefined => {
+                let parameters: Vec<String> = self.arguments.iter().enumerate().map(|(i, a)| {
+                    if self.variadic && i == self.arguments.len() - 1 {
+                        format!("...{}", a.lexeme)
+                    }
+                    else if self.has_default(i) {
+                        format!("{} = {}", a.lexeme, crate::fmt::format_expr(&self.defaults[i]))
+                    }
+                    else {
+                        a.lexeme.to_string()
+                    }
+                }).collect();
+                let description = if self.doc.is_empty() { "No description, add one with '///' comments before 'fn'".to_owned() } else { self.doc.to_owned() };
+                (parameters.join(", "), description)
+            },
+            _ => match self.native_type {
+                NativeType::Host(index) => {
+                    let arity = host_native_arity(index);
+                    (vec!["_"; arity].join(", "), format!("Native function registered by the host application, takes {} arguments", arity))
+                },
+                native_type => {
+                    let (parameters, description) = Function::native_help(native_type);
+                    (parameters.to_owned(), description.to_owned())
+                }
+            }
+        };
+        let description: Vec<String> = description.lines().map(|line| format!("    {}", line)).collect();
+        format!("{}({})\n{}", self.name(), parameters, description.join("\n"))
+    }
+    pub fn bind_arguments(&self, positional: Vec<ast::Literal>, named: Vec<(token::Token, ast::Literal)>, tok: &token::Token) -> Vec<Option<ast::Literal>> {
+        // Places the named arguments after the positional ones, in the order they were declared
+        let names = self.argument_names();
+        let mut bound: Vec<Option<ast::Literal>> = positional.into_iter().map(Some).collect();
+        if bound.len() < self.arg_length() {
+            bound.resize(self.arg_length(), None);
+        }
+        for (name, literal) in named {
+            match names.iter().position(|n| **n == *name.lexeme) {
+                Some(index) if bound[index].is_some() => {
+                    name.print_custom_error(ErrorClass::WrongArgumentCount, &format!("Argument '{}' was already given by position", name.lexeme));
+                },
+                Some(index) => {
+                    bound[index] = Some(literal);
+                },
+                None if names.is_empty() => {
+                    name.print_custom_error(ErrorClass::WrongArgumentCount, &format!("{}() does not accept named arguments", self.declaration_token.lexeme));
+                },
+                None => {
+                    name.print_custom_error(ErrorClass::WrongArgumentCount, &format!("{}() has no argument named '{}', expected one of: {}", self.declaration_token.lexeme, name.lexeme, names.join(", ")));
+                }
+            }
+        }
+        for (index, name) in names.iter().enumerate() {
+            if bound[index].is_none() && !self.has_default(index) {
+                tok.print_custom_error(ErrorClass::WrongArgumentCount, &format!("{}() is missing argument '{}'", self.declaration_token.lexeme, name));
+            }
+            if bound[index].is_none() && self.is_native() {
+                // Natives receive every argument, user functions evaluate their own defaults
+                bound[index] = Function::native_default(self.native_type, index);
+            }
+        }
+        bound
+    }
+    pub fn moves_first_argument(&self, arguments: &[ast::Literal]) -> bool {
+        // Whether 'arr = f(arr, ...)' can move 'arr' into the call instead of sharing it, so the array is not copied
+        // Only for valid arguments, since an error would leave the variable without its value
+        let is_array = |index: usize| arguments.get(index).map_or(false, |literal| literal.literal_type == ast::LiteralType::Array);
+        match (self.function_type, self.native_type) {
+            (FunctionType::Native, NativeType::Push) | (FunctionType::Native, NativeType::Pop) => is_array(0),
+            (FunctionType::Native, NativeType::Concat) => is_array(0) && is_array(1),
+            _ => false,
+        }
+    }
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min_arg_length() && (self.variadic || count <= self.arg_length())
+    }
+    pub fn native_argument_names(native_type: NativeType) -> &'static [&'static str] {
+        // For named arguments, only natives with several arguments are named
         match native_type {
             // Number operations
-            NativeType::Power =>    2,
-            NativeType::Log =>      2,
-            NativeType::Modulo =>   2,
-            NativeType::Absolute => 1,
-            NativeType::Floor =>    1,
-            NativeType::Ceiling =>  1,
-            NativeType::Max =>      2,
-            NativeType::Min =>      2,
-            //String/Number conversions
-            NativeType::ToString => 1,
-            NativeType::ToNumber => 1,
+            NativeType::Power =>    &["base", "power"],
+            NativeType::Log =>      &["base", "value"],
+            NativeType::Modulo =>   &["value", "modulus"],
+            NativeType::Max =>      &["left", "right"],
+            NativeType::Min =>      &["left", "right"],
+            NativeType::Round =>    &["value", "digits"],
+            NativeType::Clamp =>    &["value", "min", "max"],
+            // Trigonometry and other math
+            NativeType::Atan2 =>    &["y", "x"],
             //String operations
-            NativeType::Split =>        2,
-            NativeType::ToLowercase =>  1,
-            NativeType::ToUpperCase =>  1,
+            NativeType::Split =>        &["source", "delimiter"],
+            NativeType::Join =>         &["source", "separator"],
+            NativeType::Replace =>      &["source", "from", "to"],
+            NativeType::Substring =>    &["source", "start", "end"],
+            //Regular expressions
+            NativeType::RegexMatch =>   &["source", "pattern"],
+            NativeType::RegexFindAll => &["source", "pattern"],
+            NativeType::RegexReplace => &["source", "pattern", "replacement"],
             //Array operations
-            NativeType::Length =>       1,
-            NativeType::Insert =>       3,
-            NativeType::Remove =>       2,
+            NativeType::Insert =>       &["source", "index", "value"],
+            NativeType::Remove =>       &["source", "index"],
+            NativeType::Push =>         &["source", "value"],
+            NativeType::Concat =>       &["left", "right"],
+            NativeType::IndexOf =>      &["source", "value"],
+            NativeType::Contains =>     &["source", "value"],
+            NativeType::Zip =>          &["left", "right"],
+            NativeType::Take =>         &["source", "count"],
+            NativeType::Drop =>         &["source", "count"],
+            NativeType::Chunk =>        &["source", "size"],
+            NativeType::Windows =>      &["source", "size"],
 
-            NativeType::Map =>          2,
-            NativeType::Filter =>       2,
-            NativeType::Reduce =>       3,
+            NativeType::Map =>          &["source", "function"],
+            NativeType::Filter =>       &["source", "function"],
+            NativeType::Reduce =>       &["source", "initial", "function"],
+            NativeType::ParMap =>       &["source", "function"],
+            NativeType::ParFilter =>    &["source", "function"],
+            NativeType::ParReduce =>    &["source", "initial", "function"],
+            NativeType::Find =>         &["source", "function"],
+            NativeType::Any =>          &["source", "function"],
+            NativeType::All =>          &["source", "function"],
+            NativeType::CountIf =>      &["source", "function"],
 
-            NativeType::Range =>        3,
-            NativeType::Linspace =>     3,
-            NativeType::Repeat =>       2,
+            NativeType::Range =>        &["start", "end", "step"],
+            NativeType::Linspace =>     &["start", "end", "n"],
+            NativeType::Vars =>         &["types"],
+            NativeType::Repeat =>       &["value", "n"],
+
+            // Linear algebra
+            NativeType::Dot =>          &["left", "right"],
+            NativeType::Matmul =>       &["left", "right"],
 
             // Random generation
-            NativeType::RandomChoose => 2,
-            NativeType::RandomNormal => 3,
+            NativeType::RandomChoose => &["source", "n"],
+            NativeType::RandomNormal => &["mean", "std_dev", "n"],
+            NativeType::RandomUniform => &["low", "high", "n"],
+            NativeType::RandomInt =>    &["low", "high", "n"],
+
+            // Threads
+            NativeType::Send =>         &["channel", "value"],
+            NativeType::SetTimeout =>   &["function", "milliseconds"],
+            NativeType::SetInterval =>  &["function", "milliseconds"],
+
+            // Environment variables
+            NativeType::EnvSet =>       &["name", "value"],
 
             // File operations
-            NativeType::ReadFile =>     1,
-            NativeType::WriteFile =>    2,
-            
-             // Web
-             NativeType::ServeStaticFolder =>   3,
-             NativeType::WebGet =>              1,
-             NativeType::WebPost =>             2,
+            NativeType::WriteFile =>    &["filepath", "data"],
+            NativeType::AppendFile =>   &["filepath", "data"],
+            NativeType::WriteBytes =>   &["filepath", "data"],
+            NativeType::CopyFile =>     &["source", "destination"],
 
-            _ => {
-                panic!("new_native() has not accounted for {:?}", native_type);
-            }
+            // Key-value store
+            NativeType::StoreSet =>     &["path", "key", "value"],
+            NativeType::StoreGet =>     &["path", "key"],
+
+            // Archives
+            NativeType::ZipCreate =>        &["path", "files"],
+            NativeType::ZipExtract =>       &["path", "destination"],
+
+            // Path operations
+            NativeType::PathJoin =>     &["base", "child"],
+
+            // Web
+            NativeType::ServeStaticFolder =>   &["folderpath", "address", "port"],
+            NativeType::Route =>               &["method", "path", "handler"],
+            NativeType::Response =>            &["status", "body"],
+            NativeType::ServeRoutes =>         &["routes", "address", "port"],
+            NativeType::WebPost =>             &["url", "params"],
+            NativeType::WebPostForm =>         &["url", "params"],
+            NativeType::WebPut =>              &["url", "params"],
+            NativeType::WebPatch =>            &["url", "params"],
+            NativeType::WebSendRaw =>          &["method", "url", "body", "content_type"],
+            NativeType::WebRequest =>          &["method", "url", "body", "headers", "timeout", "auth"],
+            NativeType::DownloadFile =>        &["url", "filepath"],
+
+            NativeType::FfiCall =>             &["library_path", "symbol", "arg_types", "args", "return_type"],
+
+            _ => &[]
+        }
+    }
+    pub fn min_number_of_args(native_type: NativeType) -> usize {
+        // Natives whose last arguments are optional, the rest need all of them
+        match native_type {
+            NativeType::Range =>        1, // range(end), range(start, end)
+            NativeType::Linspace =>     2, // linspace(start, end)
+            NativeType::Vars =>         0, // vars()
+            _ => Function::number_of_args(native_type)
+        }
+    }
+    fn native_default(native_type: NativeType, index: usize) -> Option<ast::Literal> {
+        // Values of the optional arguments when given by name, such as 'range(end = 5)'
+        match (native_type, index) {
+            (NativeType::Range, 0) =>       Some(ast::Literal::number(0)),
+            (NativeType::Range, 2) =>       Some(ast::Literal::number(1)),
+            (NativeType::Linspace, 2) =>    Some(ast::Literal::number(LINSPACE_ELEMENTS)),
+            (NativeType::Vars, 0) =>        Some(ast::Literal::bool(false)),
+            _ => None
+        }
+    }
+    fn native_help(native_type: NativeType) -> (&'static str, &'static str) {
+        // Parameters and description shown by help()
+        match native_type {
+            // Number operations
+            NativeType::Power =>    ("base, power", "Returns base raised to the power"),
+            NativeType::Log =>      ("base, value", "Returns the logarithm of value in the given base"),
+            NativeType::Modulo =>   ("value, modulus", "Returns the remainder of dividing value by modulus"),
+            NativeType::Absolute => ("value", "Returns the absolute value"),
+            NativeType::Floor =>    ("value", "Rounds down to an integer"),
+            NativeType::Ceiling =>  ("value", "Rounds up to an integer"),
+            NativeType::Max =>      ("left, right", "Returns the larger number"),
+            NativeType::Min =>      ("left, right", "Returns the smaller number"),
+            NativeType::Round =>    ("value, digits", "Rounds to the given number of decimal places, negative digits round to tens, hundreds and so on"),
+            NativeType::Clamp =>    ("value, min, max", "Limits value to the range from min to max"),
+
+            // Trigonometry and other math
+            NativeType::Sin =>      ("radians", "Returns the sine"),
+            NativeType::Cos =>      ("radians", "Returns the cosine"),
+            NativeType::Tan =>      ("radians", "Returns the tangent"),
+            NativeType::Asin =>     ("value", "Returns the arcsine in radians"),
+            NativeType::Acos =>     ("value", "Returns the arccosine in radians"),
+            NativeType::Atan =>     ("value", "Returns the arctangent in radians"),
+            NativeType::Atan2 =>    ("y, x", "Returns the angle of the point (x, y) in radians, from -pi to pi"),
+            NativeType::Sqrt =>     ("value", "Returns the square root"),
+            NativeType::Exp =>      ("value", "Returns e raised to the value"),
+            NativeType::Pi =>       ("", "Returns the constant pi"),
+            NativeType::E =>        ("", "Returns Euler's number e"),
+
+            // Types
+            NativeType::TypeOf =>       ("value", "Returns the type as a string: Number, String, Bool, Null, Array or Function"),
+            NativeType::IsNumber =>     ("value", "Returns true if the value is a number"),
+            NativeType::IsInteger =>    ("value", "Returns true if the value is an integer number, such as 2 but not 2.0"),
+            NativeType::IsString =>     ("value", "Returns true if the value is a string"),
+            NativeType::IsBool =>       ("value", "Returns true if the value is true or false"),
+            NativeType::IsNull =>       ("value", "Returns true if the value is null"),
+            NativeType::IsArray =>      ("value", "Returns true if the value is an array"),
+            NativeType::IsFunction =>   ("value", "Returns true if the value is a function"),
+
+            // Printing
+            NativeType::PrintFull =>        ("value", "Like 'println', but shows every element of arrays"),
+            NativeType::SetDisplayLimit =>  ("limit", "Sets the number of elements 'print' shows of each array, 0 for all of them"),
+
+            // Code evaluation
+            NativeType::Eval =>         ("code", "Runs a string of code in the current scope and returns the value of its last statement"),
+            NativeType::Vars =>         ("types = false", "Returns the names of the variables visible in the current scope, or [name, type] pairs if types is true"),
+            NativeType::IsDefined =>    ("name", "Returns true if a variable or function with the name is visible in the current scope"),
+            NativeType::Help =>         ("function", "Returns the signature and description of a function, from '///' comments for user functions"),
+
+            // Copying
+            NativeType::Copy =>         ("value", "Returns a deep copy of the value, which can be modified even if the value is frozen"),
+            NativeType::Freeze =>       ("value", "Returns the value made immutable, so the variable holding it cannot be reassigned or index-assigned"),
+
+            // String/Number conversions
+            NativeType::ToString =>         ("value", "Converts any value to a string"),
+            NativeType::ToNumber =>         ("text", "Converts a string to a number"),
+            NativeType::StringToBytes =>    ("text", "Returns the UTF-8 bytes of the string"),
+            NativeType::BytesToString =>    ("bytes", "Decodes UTF-8 bytes into a string, replacing invalid sequences with U+FFFD"),
+
+            // String operations
+            NativeType::Split =>        ("source, delimiter", "Splits the string into an array of strings"),
+            NativeType::Join =>         ("source, separator", "Joins an array of strings with the separator between them"),
+            NativeType::ToLowercase =>  ("text", "Returns the string in lowercase"),
+            NativeType::ToUpperCase =>  ("text", "Returns the string in uppercase"),
+            NativeType::Trim =>         ("text", "Removes whitespace from both ends of the string"),
+            NativeType::Replace =>      ("source, from, to", "Replaces every occurrence of from with to"),
+            NativeType::Substring =>    ("source, start, end", "Returns the characters from start up to, but not including, end"),
+
+            // Regular expressions
+            NativeType::RegexMatch =>   ("source, pattern", "Returns true if the pattern matches anywhere in the source"),
+            NativeType::RegexFindAll => ("source, pattern", "Returns an array of every match"),
+            NativeType::RegexReplace => ("source, pattern, replacement", "Replaces every match, where $1, $2, ${name} and so on refer to capture groups"),
+
+            // Array operations
+            NativeType::Length =>       ("source", "Returns the number of elements of an array or characters of a string"),
+            NativeType::Insert =>       ("source, index, value", "Returns the array or string with the value inserted at the index"),
+            NativeType::Remove =>       ("source, index", "Returns the array or string without the element at the index"),
+            NativeType::Push =>         ("source, value", "Returns the array with the value appended at the end"),
+            NativeType::Pop =>          ("source", "Returns [array without its last element, last element], such as 'arr, last = pop(arr)'"),
+            NativeType::Concat =>       ("left, right", "Returns the elements of the left array followed by the elements of the right array"),
+            NativeType::IndexOf =>      ("source, value", "Returns the index of the first match in an array or string, or -1 if not found"),
+            NativeType::Contains =>     ("source, value", "Returns true if an array or string contains the value"),
+            NativeType::Flatten =>      ("source", "Flattens nested arrays by one level"),
+            NativeType::Unique =>       ("source", "Removes duplicates, keeping the first occurrence of each value"),
+            NativeType::Zip =>          ("left, right", "Returns [left, right] pairs, as long as the shorter array"),
+            NativeType::Enumerate =>    ("source", "Returns [index, value] pairs"),
+            NativeType::Take =>         ("source, count", "Returns the first count elements"),
+            NativeType::Drop =>         ("source, count", "Returns the array without its first count elements"),
+            NativeType::Chunk =>        ("source, size", "Splits the array into arrays of the given size, the last one may be shorter"),
+            NativeType::Windows =>      ("source, size", "Returns every overlapping window of the given size"),
+
+            NativeType::Map =>          ("source, function", "Returns the results of calling the function on every element"),
+            NativeType::Filter =>       ("source, function", "Returns the elements for which the function returns a truthy value"),
+            NativeType::Reduce =>       ("source, initial, function", "Combines the elements from left to right with function(accumulated, element), starting from initial"),
+            NativeType::ParMap =>       ("source, function", "Like map(), but calls the function on several threads"),
+            NativeType::ParFilter =>    ("source, function", "Like filter(), but calls the function on several threads"),
+            NativeType::ParReduce =>    ("source, initial, function", "Like reduce(), but on several threads, so the function must be associative"),
+            NativeType::Find =>         ("source, function", "Returns the first element for which the function returns a truthy value, or null"),
+            NativeType::Any =>          ("source, function", "Returns true if the function returns a truthy value for at least one element"),
+            NativeType::All =>          ("source, function", "Returns true if the function returns a truthy value for every element"),
+            NativeType::CountIf =>      ("source, function", "Returns the number of elements for which the function returns a truthy value"),
+
+            NativeType::Range =>        ("start = 0, end, step = 1", "Returns the numbers from start up to end, where range(end) starts from 0"),
+            NativeType::Linspace =>     ("start, end, n = 50", "Returns n evenly spaced numbers from start to end"),
+            NativeType::Repeat =>       ("value, n", "Returns an array of n copies of the value"),
+
+            // Statistics
+            NativeType::Sum =>          ("source", "Returns the sum of the numbers"),
+            NativeType::Mean =>         ("source", "Returns the average of the numbers"),
+            NativeType::Median =>       ("source", "Returns the middle value, or the average of the two middle values"),
+            NativeType::Variance =>     ("source", "Returns the population variance"),
+            NativeType::StdDev =>       ("source", "Returns the population standard deviation"),
+            NativeType::Cumsum =>       ("source", "Returns the running sum"),
+            NativeType::Cumprod =>      ("source", "Returns the running product"),
+            NativeType::Diff =>         ("source", "Returns the differences between neighbouring elements"),
+
+            // Linear algebra
+            NativeType::Dot =>          ("left, right", "Returns the dot product of two arrays of numbers"),
+            NativeType::Matmul =>       ("left, right", "Multiplies two matrices, given as arrays of rows"),
+            NativeType::Transpose =>    ("source", "Swaps the rows and columns of a matrix"),
+
+            // Random generation
+            NativeType::RandomChoose => ("source, n", "Returns n randomly chosen elements"),
+            NativeType::RandomNormal => ("mean, std_dev, n", "Returns n numbers from a normal distribution"),
+            NativeType::RandomUniform => ("low, high, n", "Returns n numbers from low (inclusive) to high (exclusive)"),
+            NativeType::RandomInt =>    ("low, high, n", "Returns n integers from low to high, both inclusive"),
+            NativeType::Shuffle =>      ("source", "Returns a shuffled copy of the array"),
+            NativeType::RandomSeed =>   ("seed", "Reseeds the generator used by every random function"),
+
+            // Time
+            NativeType::Sleep =>        ("milliseconds", "Pauses the current thread"),
+            NativeType::Clock =>        ("", "Returns milliseconds elapsed since the first call to clock()"),
+
+            // Threads
+            NativeType::Spawn =>        ("function", "Runs a function with no arguments on a new thread, and returns its handle"),
+            NativeType::JoinThread =>   ("thread", "Waits for the thread to finish and returns the function's result"),
+            NativeType::Channel =>      ("", "Returns the handle of a new channel"),
+            NativeType::Send =>         ("channel, value", "Sends a value through the channel"),
+            NativeType::Receive =>      ("channel", "Waits until a value is sent through the channel and returns it"),
+            NativeType::SetTimeout =>   ("function, milliseconds", "Calls the function once after the delay, and returns the timer handle"),
+            NativeType::SetInterval =>  ("function, milliseconds", "Calls the function every delay, and returns the timer handle"),
+            NativeType::ClearTimer =>   ("timer", "Stops the timer, and returns true if it was still active"),
+
+            // Environment variables
+            NativeType::EnvGet =>       ("name", "Returns the value of an environment variable, or null if it is not set"),
+            NativeType::EnvSet =>       ("name, value", "Sets an environment variable"),
+
+            // Shell commands
+            NativeType::Exec =>         ("command", "Runs the command in the system shell and returns its exit code"),
+            NativeType::ExecCapture =>  ("command", "Runs the command in the system shell and returns [stdout, stderr, exit code]"),
+
+            // File operations
+            NativeType::ReadFile =>     ("filepath", "Returns the contents of the file, or null if it cannot be read"),
+            NativeType::WriteFile =>    ("filepath, data", "Writes the string to the file, and returns 1 if success or 0 if fail"),
+            NativeType::AppendFile =>   ("filepath, data", "Appends the string to the file, and returns 1 if success or 0 if fail"),
+            NativeType::ReadBytes =>    ("filepath", "Returns the bytes of the file, or null if it cannot be read"),
+            NativeType::WriteBytes =>   ("filepath, data", "Writes the bytes to the file, and returns 1 if success or 0 if fail"),
+            NativeType::FileExists =>   ("filepath", "Returns true if a file or folder exists at the path"),
+            NativeType::DeleteFile =>   ("filepath", "Deletes the file, and returns 1 if success or 0 if fail"),
+            NativeType::CopyFile =>     ("source, destination", "Copies the file, and returns 1 if success or 0 if fail"),
+            NativeType::ListDir =>      ("folderpath", "Returns the sorted names of the files and folders in the folder, or null if fail"),
+            NativeType::MakeDir =>      ("folderpath", "Creates the folder and any missing parents, and returns 1 if success or 0 if fail"),
+
+            // Key-value store
+            NativeType::StoreSet =>     ("path, key, value", "Saves the value under the key in a JSON file, and returns 1 if success or 0 if fail"),
+            NativeType::StoreGet =>     ("path, key", "Returns the value saved under the key, or null if it does not exist"),
+
+            // Archives
+            NativeType::GzipCompress =>     ("data", "Returns the gzip compressed bytes of a string or byte array"),
+            NativeType::GzipDecompress =>   ("bytes", "Returns the decompressed string, or null if the bytes are not valid"),
+            NativeType::ZipCreate =>        ("path, files", "Creates a zip archive of the files and folders, and returns 1 if success or 0 if fail"),
+            NativeType::ZipExtract =>       ("path, destination", "Extracts a zip archive into the folder, and returns 1 if success or 0 if fail"),
+
+            // Path operations
+            NativeType::PathJoin =>     ("base, child", "Joins two paths with the separator of the current platform"),
+            NativeType::Basename =>     ("path", "Returns the final component of the path, such as 'file.txt' in 'folder/file.txt'"),
+            NativeType::Dirname =>      ("path", "Returns the path without its final component, such as 'folder' in 'folder/file.txt'"),
+            NativeType::Extension =>    ("path", "Returns the extension without the dot, such as 'txt' in 'folder/file.txt'"),
+
+            // Web
+            NativeType::ServeStaticFolder =>   ("folderpath, address, port", "Serves the files of the folder, and returns the server handle or null if fail"),
+            NativeType::Route =>               ("method, path, handler", "Returns a route for serve_routes(), such as route(\"GET\", \"/hello/<name>\", handler)"),
+            NativeType::Response =>            ("status, body", "Returns a response for a route handler"),
+            NativeType::ServeRoutes =>         ("routes, address, port", "Serves an array of routes, and returns the server handle or null if fail"),
+            NativeType::ServerStop =>          ("server", "Stops the server, and returns false if it had already been stopped"),
+            NativeType::ServerWait =>          ("server", "Waits until the server is stopped"),
+            NativeType::WebGet =>              ("url", "Sends a GET request and returns the body, or null if fail"),
+            NativeType::WebGetBytes =>         ("url", "Sends a GET request and returns the body as bytes, or null if fail"),
+            NativeType::WebGetFull =>          ("url", "Sends a GET request and returns [status, headers, body], or null if the request could not be made"),
+            NativeType::WebPost =>             ("url, params", "Sends a POST request with the parameters as JSON and returns the body, or null if fail"),
+            NativeType::WebPostForm =>         ("url, params", "Sends a POST request with the parameters form-encoded and returns the body, or null if fail"),
+            NativeType::WebPut =>              ("url, params", "Sends a PUT request with the parameters as JSON and returns the body, or null if fail"),
+            NativeType::WebPatch =>            ("url, params", "Sends a PATCH request with the parameters as JSON and returns the body, or null if fail"),
+            NativeType::WebDelete =>           ("url", "Sends a DELETE request and returns the body, or null if fail"),
+            NativeType::WebSendRaw =>          ("method, url, body, content_type", "Sends a request with a raw body and returns the response body, or null if fail"),
+            NativeType::WebRequest =>          ("method, url, body, headers, timeout, auth", "Sends a request with any method, headers, timeout and authentication"),
+            NativeType::DownloadFile =>        ("url, filepath", "Saves the response body to the file, and returns the number of bytes written or null if fail"),
+            NativeType::UrlEncode =>           ("text", "Percent-encodes the string for use in a URL"),
+            NativeType::UrlDecode =>           ("text", "Decodes a percent-encoded string"),
+            NativeType::BuildQuery =>          ("parameters", "Builds a query string from [name, value, ...] pairs, such as \"q=ari%20lang&page=2\""),
+
+            // Plugins
+            NativeType::LoadPlugin =>   ("path", "Loads native functions from a plugin library, and returns 1 if success or 0 if fail"),
+            NativeType::FfiCall =>      ("library_path, symbol, arg_types, args, return_type", "Calls a C function from a shared library, such as ffi_call(\"libm.so.6\", \"cos\", [\"double\"], [0], \"double\")"),
+
+            _ => ("", "No description")
+        }
+    }
+    pub fn number_of_args(native_type: NativeType) -> usize {
+        match native_type {
+            // Number operations
+            NativeType::Power =>    2,
+            NativeType::Log =>      2,
+            NativeType::Modulo =>   2,
+            NativeType::Absolute => 1,
+            NativeType::Floor =>    1,
+            NativeType::Ceiling =>  1,
+            NativeType::Max =>      2,
+            NativeType::Min =>      2,
+            NativeType::Round =>    2,
+            NativeType::Clamp =>    3,
+            // Trigonometry and other math
+            NativeType::Sin =>      1,
+            NativeType::Cos =>      1,
+            NativeType::Tan =>      1,
+            NativeType::Asin =>     1,
+            NativeType::Acos =>     1,
+            NativeType::Atan =>     1,
+            NativeType::Atan2 =>    2,
+            NativeType::Sqrt =>     1,
+            NativeType::Exp =>      1,
+            NativeType::Pi =>       0,
+            NativeType::E =>        0,
+            // Types
+            NativeType::TypeOf =>   1,
+            NativeType::IsNumber => 1,
+            NativeType::IsInteger => 1,
+            NativeType::IsString => 1,
+            NativeType::IsBool =>   1,
+            NativeType::IsNull =>   1,
+            NativeType::IsArray =>  1,
+            NativeType::IsFunction => 1,
+            // Printing
+            NativeType::PrintFull =>        1,
+            NativeType::SetDisplayLimit =>  1,
+            // Code evaluation
+            NativeType::Eval =>     1,
+            NativeType::Vars =>     1,
+            NativeType::IsDefined => 1,
+            NativeType::Help =>     1,
+            NativeType::Copy =>     1,
+            NativeType::Freeze =>   1,
+            //String/Number conversions
+            NativeType::ToString => 1,
+            NativeType::ToNumber => 1,
+            NativeType::StringToBytes => 1,
+            NativeType::BytesToString => 1,
+            //String operations
+            NativeType::Split =>        2,
+            NativeType::Join =>         2,
+            NativeType::ToLowercase =>  1,
+            NativeType::ToUpperCase =>  1,
+            NativeType::Trim =>         1,
+            NativeType::Replace =>      3,
+            NativeType::Substring =>    3,
+            //Regular expressions
+            NativeType::RegexMatch =>   2,
+            NativeType::RegexFindAll => 2,
+            NativeType::RegexReplace => 3,
+            //Array operations
+            NativeType::Length =>       1,
+            NativeType::Insert =>       3,
+            NativeType::Remove =>       2,
+            NativeType::Push =>         2,
+            NativeType::Pop =>          1,
+            NativeType::Concat =>       2,
+            NativeType::IndexOf =>      2,
+            NativeType::Contains =>     2,
+            NativeType::Flatten =>      1,
+            NativeType::Unique =>       1,
+            NativeType::Zip =>          2,
+            NativeType::Enumerate =>    1,
+            NativeType::Take =>         2,
+            NativeType::Drop =>         2,
+            NativeType::Chunk =>        2,
+            NativeType::Windows =>      2,
+
+            NativeType::Map =>          2,
+            NativeType::Filter =>       2,
+            NativeType::Reduce =>       3,
+            NativeType::ParMap =>       2,
+            NativeType::ParFilter =>    2,
+            NativeType::ParReduce =>    3,
+            NativeType::Find =>         2,
+            NativeType::Any =>          2,
+            NativeType::All =>          2,
+            NativeType::CountIf =>      2,
+
+            NativeType::Range =>        3,
+            NativeType::Linspace =>     3,
+            NativeType::Repeat =>       2,
+
+            // Statistics
+            NativeType::Sum =>          1,
+            NativeType::Mean =>         1,
+            NativeType::Median =>       1,
+            NativeType::Variance =>     1,
+            NativeType::StdDev =>       1,
+            NativeType::Cumsum =>       1,
+            NativeType::Cumprod =>      1,
+            NativeType::Diff =>         1,
+
+            // Linear algebra
+            NativeType::Dot =>          2,
+            NativeType::Matmul =>       2,
+            NativeType::Transpose =>    1,
+
+            // Random generation
+            NativeType::RandomChoose => 2,
+            NativeType::RandomNormal => 3,
+            NativeType::RandomUniform => 3,
+            NativeType::RandomInt =>    3,
+            NativeType::Shuffle =>      1,
+            NativeType::RandomSeed =>   1,
+
+            // Time
+            NativeType::Sleep =>        1,
+            NativeType::Clock =>        0,
+
+            // Threads
+            NativeType::Spawn =>        1,
+            NativeType::JoinThread =>   1,
+            NativeType::Channel =>      0,
+            NativeType::Send =>         2,
+            NativeType::Receive =>      1,
+            NativeType::SetTimeout =>   2,
+            NativeType::SetInterval =>  2,
+            NativeType::ClearTimer =>   1,
+
+            // Environment variables
+            NativeType::EnvGet =>       1,
+            NativeType::EnvSet =>       2,
+
+            // Shell commands
+            NativeType::Exec =>         1,
+            NativeType::ExecCapture =>  1,
+
+            // File operations
+            NativeType::ReadFile =>     1,
+            NativeType::WriteFile =>    2,
+            NativeType::AppendFile =>   2,
+            NativeType::ReadBytes =>    1,
+            NativeType::WriteBytes =>   2,
+            NativeType::FileExists =>   1,
+            NativeType::DeleteFile =>   1,
+            NativeType::CopyFile =>     2,
+            NativeType::ListDir =>      1,
+            NativeType::MakeDir =>      1,
+
+            // Key-value store
+            NativeType::StoreSet =>     3,
+            NativeType::StoreGet =>     2,
+
+            // Archives
+            NativeType::GzipCompress =>     1,
+            NativeType::GzipDecompress =>   1,
+            NativeType::ZipCreate =>        2,
+            NativeType::ZipExtract =>       2,
+
+            // Path operations
+            NativeType::PathJoin =>     2,
+            NativeType::Basename =>     1,
+            NativeType::Dirname =>      1,
+            NativeType::Extension =>    1,
+            
+             // Web
+             NativeType::ServeStaticFolder =>   3,
+             NativeType::Route =>               3,
+             NativeType::Response =>            2,
+             NativeType::ServeRoutes =>         3,
+             NativeType::ServerStop =>          1,
+             NativeType::ServerWait =>          1,
+             NativeType::WebGet =>              1,
+             NativeType::WebGetBytes =>         1,
+             NativeType::WebGetFull =>          1,
+             NativeType::WebPost =>             2,
+             NativeType::WebPostForm =>         2,
+             NativeType::WebPut =>              2,
+             NativeType::WebPatch =>            2,
+             NativeType::WebDelete =>           1,
+             NativeType::WebSendRaw =>          4,
+             NativeType::WebRequest =>          6,
+             NativeType::DownloadFile =>        2,
+             NativeType::UrlEncode =>           1,
+             NativeType::UrlDecode =>           1,
+             NativeType::BuildQuery =>          1,
+
+             NativeType::LoadPlugin =>          1,
+             NativeType::FfiCall =>             5,
+
+             NativeType::Host(index) =>         host_native_arity(index),
+
+            _ => {
+                panic!("new_native() has not accounted for {:?}", native_type);
+            }
         }
     }
 }
@@ -319,13 +1568,21 @@ fn power(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let base = arguments.get(0).unwrap();
     let power = arguments.get(1).unwrap();
     if base.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("power() expects 1st argument (base) of type Number, but received {:?} instead", base.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("power() expects 1st argument (base) of type Number, but received {:?} instead", base.literal_type));
     }
     else if power.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("power() expects 2nd argument (power) of type Number, but received {:?} instead", power.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("power() expects 2nd argument (power) of type Number, but received {:?} instead", power.literal_type));
     }
     else {
-        return ast::Literal::number(Expr::string_to_float(&base).powf(Expr::string_to_float(&power)).to_string());
+        // An integer raised to a non-negative integer stays exact, unless it overflows
+        if let (ast::Number::Integer(b), ast::Number::Integer(p)) = (base.number, power.number) {
+            if (0..=u32::MAX as i64).contains(&p) {
+                if let Some(result) = b.checked_pow(p as u32) {
+                    return ast::Literal::number(result);
+                }
+            }
+        }
+        return ast::Literal::number(Expr::to_float(&base).powf(Expr::to_float(&power)));
     }
     ast::Literal::none()
 }
@@ -333,75 +1590,75 @@ fn log(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let base = arguments.get(0).unwrap();
     let value = arguments.get(1).unwrap();
     if base.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("log() expects 1st argument (base) of type Number, but received {:?} instead", base.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("log() expects 1st argument (base) of type Number, but received {:?} instead", base.literal_type));
     }
     else if value.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("log() expects 2nd argument (value) of type Number, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("log() expects 2nd argument (value) of type Number, but received {:?} instead", value.literal_type));
     }
     else {
-        let result = Expr::string_to_float(&value).log(Expr::string_to_float(&base));
+        let result = Expr::to_float(&value).log(Expr::to_float(&base));
         if result.is_infinite() || result.is_nan() {
-            tok.print_custom_error(&format!("log() resulted in an invalid value"));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("log() resulted in an invalid value"));
         }
-        return ast::Literal::number(result.to_string());
+        return ast::Literal::number(result);
     }
     ast::Literal::none()
 }
 fn modulo(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let value = arguments.get(0).unwrap();
-    let modulee = arguments.get(1).unwrap();
+    let modulus = arguments.get(1).unwrap();
     if value.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("modulo() expects 1st argument (value) of type Number, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("modulo() expects 1st argument (value) of type Number, but received {:?} instead", value.literal_type));
     }
-    else if modulee.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("modulo() expects 2nd argument (modulee) of type Number, but received {:?} instead", modulee.literal_type));
+    else if modulus.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("modulo() expects 2nd argument (modulus) of type Number, but received {:?} instead", modulus.literal_type));
     }
     else {
-        let value_float = Expr::string_to_float(&value);
+        let value_float = Expr::to_float(&value);
         if value_float.fract() != 0.0 {
-            tok.print_custom_error(&format!("modulo() expects 1st argument (value) to be an integer, but received {} instead", value_float));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("modulo() expects 1st argument (value) to be an integer, but received {} instead", value_float));
+        }
+        let modulus_float = Expr::to_float(&modulus);
+        if modulus_float.fract() != 0.0 {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("modulo() expects 2nd argument (modulus) to be an integer, but received {} instead", modulus_float));
         }
-        let modulee_float = Expr::string_to_float(&modulee);
-        if modulee_float.fract() != 0.0 {
-            tok.print_custom_error(&format!("modulo() expects 2nd argument (modulee) to be an integer, but received {} instead", modulee_float));
+        if (1.0 / modulus_float).is_infinite() {
+            tok.print_custom_error(ErrorClass::DivisionByZero, &format!("modulo() expects 2nd argument (modulus) to be non-zero"));
         }
-        if (1.0 / modulee_float).is_infinite() {
-            tok.print_custom_error(&format!("modulo() expects 2nd argument (modulee) to be non-zero"));
+        // The same as '%' once both are known to be integers
+        if let Ok(result) = Expr::remainder(&value, &modulus) {
+            return result;
         }
-        let value_integer = value_float as i32;
-        let modulee_integer = modulee_float as i32;
-        let result = value_integer % modulee_integer;
-        return ast::Literal::number(result.to_string());
     }
     ast::Literal::none()
 }
 fn absolute(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let value = arguments.get(0).unwrap();
     if value.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("absolute() expects one argument of type Number, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("absolute() expects one argument of type Number, but received {:?} instead", value.literal_type));
     }
     else {
-        return ast::Literal::number(Expr::string_to_float(&value).abs().to_string());
+        return Expr::arithmetic(&value, &value, |v, _| v.checked_abs(), |v, _| v.abs());
     }
     ast::Literal::none()
 }
 fn floor(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let value = arguments.get(0).unwrap();
     if value.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("floor() expects one argument of type Number, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("floor() expects one argument of type Number, but received {:?} instead", value.literal_type));
     }
     else {
-        return ast::Literal::number(Expr::string_to_float(&value).floor().to_string());
+        return whole_number(Expr::to_float(&value).floor());
     }
     ast::Literal::none()
 }
 fn ceiling(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let value = arguments.get(0).unwrap();
     if value.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("ceiling() expects one argument of type Number, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("ceiling() expects one argument of type Number, but received {:?} instead", value.literal_type));
     }
     else {
-        return ast::Literal::number(Expr::string_to_float(&value).ceil().to_string());
+        return whole_number(Expr::to_float(&value).ceil());
     }
     ast::Literal::none()
 }
@@ -409,21 +1666,20 @@ fn max(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let left = arguments.get(0).unwrap();
     let right = arguments.get(1).unwrap();
     if left.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("max() expects 1st argument (left) of type Number, but received {:?} instead", left.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("max() expects 1st argument (left) of type Number, but received {:?} instead", left.literal_type));
     }
     else if right.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("max() expects 2nd argument (right) of type Number, but received {:?} instead", right.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("max() expects 2nd argument (right) of type Number, but received {:?} instead", right.literal_type));
     }
     else {
-        let left_float = Expr::string_to_float(&left);
-        let right_float = Expr::string_to_float(&right);
-        let result = if left_float > right_float {
-            left_float
+        // The larger argument itself, so an integer stays an integer
+        let result = if Expr::to_float(&left) > Expr::to_float(&right) {
+            left
         }
         else {
-            right_float
+            right
         };
-        return ast::Literal::number(result.to_string());
+        return result.clone();
     }
     ast::Literal::none()
 }
@@ -431,63 +1687,287 @@ fn min(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let left = arguments.get(0).unwrap();
     let right = arguments.get(1).unwrap();
     if left.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("min() expects 1st argument (left) of type Number, but received {:?} instead", left.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("min() expects 1st argument (left) of type Number, but received {:?} instead", left.literal_type));
     }
     else if right.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("min() expects 2nd argument (right) of type Number, but received {:?} instead", right.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("min() expects 2nd argument (right) of type Number, but received {:?} instead", right.literal_type));
     }
     else {
-        let left_float = Expr::string_to_float(&left);
-        let right_float = Expr::string_to_float(&right);
-        let result = if left_float < right_float {
-            left_float
+        let result = if Expr::to_float(&left) < Expr::to_float(&right) {
+            left
         }
         else {
-            right_float
+            right
         };
-        return ast::Literal::number(result.to_string());
+        return result.clone();
+    }
+    ast::Literal::none()
+}
+fn round(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Rounds to the given number of decimal places, negative digits round to tens, hundreds and so on
+    let value = arguments.get(0).unwrap();
+    let digits = arguments.get(1).unwrap();
+    if value.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("round() expects 1st argument (value) of type Number, but received {:?} instead", value.literal_type));
+    }
+    else if digits.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("round() expects 2nd argument (digits) of type Number, but received {:?} instead", digits.literal_type));
+    }
+    else {
+        let digits_float = Expr::to_float(&digits);
+        if digits_float.fract() != 0.0 {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("round() expects 2nd argument (digits) to be an integer, but received {} instead", digits_float));
+        }
+        let factor = 10f64.powi(digits_float as i32);
+        let result = (Expr::to_float(&value) * factor).round() / factor;
+        return ast::Literal::number(result);
+    }
+    ast::Literal::none()
+}
+fn clamp(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let value = arguments.get(0).unwrap();
+    let min = arguments.get(1).unwrap();
+    let max = arguments.get(2).unwrap();
+    if value.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("clamp() expects 1st argument (value) of type Number, but received {:?} instead", value.literal_type));
+    }
+    else if min.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("clamp() expects 2nd argument (min) of type Number, but received {:?} instead", min.literal_type));
+    }
+    else if max.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("clamp() expects 3rd argument (max) of type Number, but received {:?} instead", max.literal_type));
+    }
+    else {
+        let min_float = Expr::to_float(&min);
+        let max_float = Expr::to_float(&max);
+        if min_float > max_float {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("clamp() expects min ({}) to be less than or equal to max ({})", min_float, max_float));
+        }
+        let value_float = Expr::to_float(&value);
+        let result = if value_float < min_float { min } else if value_float > max_float { max } else { value };
+        return result.clone();
     }
     ast::Literal::none()
 }
-fn to_string(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+
+fn whole_number(value: f64) -> ast::Literal {
+    // Result of floor() and ceiling(), an integer unless it is too large for one
+    match ast::Number::Float(value).to_integer() {
+        Some(integer) => ast::Literal::number(integer),
+        None => ast::Literal::number(value)
+    }
+}
+
+// Trigonometry and other math
+fn unary_math(arguments: Vec<ast::Literal>, tok: &token::Token, function_name: &str, operation: fn(f64) -> f64) -> ast::Literal {
+    // Angles are in radians
     let value = arguments.get(0).unwrap();
     if value.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("to_string() expects one argument of type Number, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects one argument of type Number, but received {:?} instead", function_name, value.literal_type));
+    }
+    else {
+        let result = operation(Expr::to_float(&value));
+        if result.is_infinite() || result.is_nan() {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() resulted in an invalid value", function_name));
+        }
+        return ast::Literal::number(result);
+    }
+    ast::Literal::none()
+}
+fn atan2(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Angle of the point (x, y) in radians
+    let y = arguments.get(0).unwrap();
+    let x = arguments.get(1).unwrap();
+    if y.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("atan2() expects 1st argument (y) of type Number, but received {:?} instead", y.literal_type));
+    }
+    else if x.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("atan2() expects 2nd argument (x) of type Number, but received {:?} instead", x.literal_type));
     }
     else {
-        return ast::Literal::string(value.value.clone());
+        return ast::Literal::number(Expr::to_float(&y).atan2(Expr::to_float(&x)));
+    }
+    ast::Literal::none()
+}
+
+// Types
+fn script_type(literal: &ast::Literal) -> ast::LiteralType {
+    // A function which returned nothing gives None, which scripts see as null
+    match literal.literal_type {
+        ast::LiteralType::None => ast::LiteralType::Null,
+        literal_type => literal_type,
     }
+}
+fn type_of(arguments: Vec<ast::Literal>) -> ast::Literal {
+    // Returns string Literal, such as "Number" or "Array"
+    let value = arguments.get(0).unwrap();
+    ast::Literal::string(format!("{:?}", script_type(value)))
+}
+fn is_type(arguments: Vec<ast::Literal>, literal_type: ast::LiteralType) -> ast::Literal {
+    let value = arguments.get(0).unwrap();
+    ast::Literal::bool(script_type(value) == literal_type)
+}
+fn is_integer(arguments: Vec<ast::Literal>) -> ast::Literal {
+    let value = arguments.get(0).unwrap();
+    ast::Literal::bool(value.literal_type == ast::LiteralType::Number && matches!(value.number, ast::Number::Integer(_)))
+}
+
+// Printing
+fn print_full(arguments: Vec<ast::Literal>) -> ast::Literal {
+    // Like 'println', but shows every element of arrays
+    let value = arguments.get(0).unwrap();
+    ari_errors::print_output(&format!("{}\n", value.display(0)));
     ast::Literal::none()
 }
+fn set_display_limit(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Number of elements 'print' shows of each array, 0 for all of them
+    let limit = literal_to_index(arguments.get(0).unwrap(), "display limit", "set_display_limit", tok);
+    ast::set_display_limit(limit);
+    ast::Literal::none()
+}
+
+// Code evaluation
+fn eval(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Runs the code in the caller's environment, and returns the value of its last statement
+    let code = arguments.get(0).unwrap();
+    if code.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("eval() expects one argument (code) of type String, but received {:?} instead", code.literal_type));
+    }
+    // 'eval("1 + 2")' does not need the semicolon
+    let mut source = code.value.trim_end().to_string();
+    if !source.ends_with(';') && !source.ends_with('}') {
+        source.push(';');
+    }
+    let mut scanner_struct = scanner::Scanner::new(&source, "", 1);
+    let tokens = scanner_struct.scan_tokens();
+    let mut parser_struct = parser::Parser::new(tokens);
+    let mut statements = parser_struct.parse();
+
+    // Leave the environment created for this native, so that variables are read and defined in the caller's ones
+    ENV.lock().unwrap().destroy_env();
+    let scopes = ENV.lock().unwrap().local_names();
+    let mut resolver_struct = resolver::Resolver::with_scopes(scopes);
+    resolver_struct.resolve(&mut statements);
+    let mut result = ast::Literal::none();
+    for s in statements {
+        result = s.evaluate_statement();
+    }
+    ENV.lock().unwrap().create_env();
+    result
+}
+
+fn vars(arguments: Vec<ast::Literal>) -> ast::Literal {
+    // Returns array of the names of user-defined variables which can be seen here,
+    // or of [name, type] pairs when given true
+    let with_types = arguments.get(0).map_or(false, |types| Expr::is_truthy(types));
+    let variables = ENV.lock().unwrap().visible_variables();
+    let result_array = variables.into_iter().map(|(name, literal)| {
+        if with_types {
+            ast::Literal::new_array(vec![ast::Literal::string(name), ast::Literal::string(format!("{:?}", script_type(&literal)))])
+        }
+        else {
+            ast::Literal::string(name)
+        }
+    }).collect();
+    ast::Literal::new_array(result_array)
+}
+fn is_defined(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let name = arguments.get(0).unwrap();
+    if name.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("is_defined() expects one argument (name) of type String, but received {:?} instead", name.literal_type));
+    }
+    ast::Literal::bool(ENV.lock().unwrap().is_visible(&name.value))
+}
+
+fn help(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns string Literal of the signature and description
+    let function = arguments.get(0).unwrap();
+    if function.literal_type != ast::LiteralType::Function {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("help() expects one argument (function) of type Function, but received {:?} instead", function.literal_type));
+    }
+    ast::Literal::string(function.function.as_ref().unwrap().help())
+}
+
+// Copying
+fn copy(arguments: Vec<ast::Literal>) -> ast::Literal {
+    // Nested arrays are copied too, and the copy is not frozen
+    let mut result = arguments.get(0).unwrap().clone();
+    result.set_frozen(false);
+    result
+}
+fn freeze(arguments: Vec<ast::Literal>) -> ast::Literal {
+    let mut result = arguments.get(0).unwrap().clone();
+    result.set_frozen(true);
+    result
+}
+
+// String/Number conversions
+fn to_string(arguments: Vec<ast::Literal>) -> ast::Literal {
+    // Any value, arrays are converted element by element
+    let value = arguments.get(0).unwrap();
+    ast::Literal::string(value.to_text())
+}
 fn to_number(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let value = arguments.get(0).unwrap();
     if value.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("to_number() expects one argument of type String, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("to_number() expects one argument of type String, but received {:?} instead", value.literal_type));
     }
     else {
-        let result = match value.value.parse::<f32>() {
-            Ok(v) => {
-                v
-            },
-            Err(_) => {
-                tok.print_custom_error(&format!("to_number() failed to extract a Number from {}", value.value));
-                panic!();
+        // Integers stay exact beyond the precision of a float
+        match ast::Number::parse(&value.value) {
+            Some(number) => return ast::Literal::number(number),
+            None => {
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("to_number() failed to extract a Number from {}", value.value));
             }
-        };
-        return ast::Literal::number(result.to_string());
+        }
     }
     ast::Literal::none()
 }
 
+// Byte arrays are arrays of Numbers from 0 to 255
+fn bytes_to_literal(bytes: &[u8]) -> ast::Literal {
+    ast::Literal::new_array(bytes.iter().map(|b| ast::Literal::number(*b)).collect())
+}
+fn literal_to_bytes(literal: &ast::Literal, function_name: &str, tok: &token::Token) -> Vec<u8> {
+    if literal.literal_type != ast::LiteralType::Array {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects a byte array of type Array, but received {:?} instead", function_name, literal.literal_type));
+    }
+    let mut bytes = Vec::<u8>::with_capacity(literal.array_values.len());
+    for (index, value) in literal.array_values.iter().enumerate() {
+        if value.literal_type != ast::LiteralType::Number {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects a byte array of Numbers, but index {} is of type {:?}", function_name, index, value.literal_type));
+        }
+        let value_float = Expr::to_float(&value);
+        if value_float.fract() != 0.0 || value_float < 0.0 || value_float > 255.0 {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects a byte array of integers from 0 to 255, but index {} is {}", function_name, index, value_float));
+        }
+        bytes.push(value_float as u8);
+    }
+    bytes
+}
+fn string_to_bytes(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns the UTF-8 bytes of the string
+    let value = arguments.get(0).unwrap();
+    if value.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("string_to_bytes() expects one argument of type String, but received {:?} instead", value.literal_type));
+    }
+    bytes_to_literal(value.value.as_bytes())
+}
+fn bytes_to_string(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Invalid UTF-8 sequences are replaced with U+FFFD
+    let bytes = literal_to_bytes(arguments.get(0).unwrap(), "bytes_to_string", tok);
+    ast::Literal::string(String::from_utf8_lossy(&bytes).to_string())
+}
+
 // String operations
 fn split(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let source = arguments.get(0).unwrap();
     let delimiter = arguments.get(1).unwrap();
     if source.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("split() expects 1st argument (source) of type String, but received {:?} instead", source.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("split() expects 1st argument (source) of type String, but received {:?} instead", source.literal_type));
     }
     else if delimiter.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("split() expects 2nd argument (delimiter) of type String, but received {:?} instead", delimiter.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("split() expects 2nd argument (delimiter) of type String, but received {:?} instead", delimiter.literal_type));
     }
     else {
         let result_array = source.value.split(&delimiter.value).map(|value| ast::Literal::string(value.to_string().clone())).collect();
@@ -495,10 +1975,28 @@ fn split(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     }
     ast::Literal::none()
 }
+fn join(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let source = arguments.get(0).unwrap();
+    let separator = arguments.get(1).unwrap();
+    if source.literal_type != ast::LiteralType::Array {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("join() expects 1st argument (source) of type Array, but received {:?} instead", source.literal_type));
+    }
+    else if separator.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("join() expects 2nd argument (separator) of type String, but received {:?} instead", separator.literal_type));
+    }
+    else {
+        if let Some(value) = source.array_values.iter().find(|value| value.literal_type != ast::LiteralType::String) {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("join() expects 1st argument (source) to have String elements, but received {:?} elements instead", value.literal_type));
+        }
+        let result = source.array_values.iter().map(|value| value.value.as_str()).collect::<Vec<&str>>().join(&separator.value);
+        return ast::Literal::string(result);
+    }
+    ast::Literal::none()
+}
 fn to_lowercase(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let value = arguments.get(0).unwrap();
     if value.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("to_lowercase() expects one argument of type String, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("to_lowercase() expects one argument of type String, but received {:?} instead", value.literal_type));
     }
     else {
         return ast::Literal::string(value.value.to_lowercase().clone());
@@ -508,28 +2006,127 @@ fn to_lowercase(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Litera
 fn to_uppercase(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let value = arguments.get(0).unwrap();
     if value.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("to_uppercase() expects one argument of type String, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("to_uppercase() expects one argument of type String, but received {:?} instead", value.literal_type));
     }
     else {
         return ast::Literal::string(value.value.to_uppercase().clone());
     }
     ast::Literal::none()
 }
+fn trim(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let value = arguments.get(0).unwrap();
+    if value.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("trim() expects one argument of type String, but received {:?} instead", value.literal_type));
+    }
+    else {
+        return ast::Literal::string(value.value.trim().to_string());
+    }
+    ast::Literal::none()
+}
+fn replace(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let source = arguments.get(0).unwrap();
+    let from = arguments.get(1).unwrap();
+    let to = arguments.get(2).unwrap();
+    if source.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("replace() expects 1st argument (source) of type String, but received {:?} instead", source.literal_type));
+    }
+    else if from.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("replace() expects 2nd argument (from) of type String, but received {:?} instead", from.literal_type));
+    }
+    else if to.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("replace() expects 3rd argument (to) of type String, but received {:?} instead", to.literal_type));
+    }
+    else {
+        if from.value.is_empty() {
+            tok.print_custom_error(ErrorClass::InvalidArgument, "replace() expects 2nd argument (from) to be a non-empty String");
+        }
+        return ast::Literal::string(source.value.replace(&from.value, &to.value));
+    }
+    ast::Literal::none()
+}
+fn substring(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Characters from start up to, but not including, end
+    let source = arguments.get(0).unwrap();
+    let start = arguments.get(1).unwrap();
+    let end = arguments.get(2).unwrap();
+    if source.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("substring() expects 1st argument (source) of type String, but received {:?} instead", source.literal_type));
+    }
+    else {
+        let start_integer = literal_to_index(start, "start", "substring", tok);
+        let end_integer = literal_to_index(end, "end", "substring", tok);
+        let char_count = source.value.chars().count();
+        if end_integer > char_count {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("substring() cannot end at {} because it is beyond the string's bounds.", end_integer));
+        }
+        if start_integer > end_integer {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("substring() expects start ({}) to be less than or equal to end ({})", start_integer, end_integer));
+        }
+        let result = source.value.chars().skip(start_integer).take(end_integer - start_integer).collect::<String>();
+        return ast::Literal::string(result);
+    }
+    ast::Literal::none()
+}
+
+// Regular expressions
+use regex::Regex;
+
+fn build_regex(source: &ast::Literal, pattern: &ast::Literal, function_name: &str, tok: &token::Token) -> Regex {
+    if source.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 1st argument (source) of type String, but received {:?} instead", function_name, source.literal_type));
+    }
+    if pattern.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 2nd argument (pattern) of type String, but received {:?} instead", function_name, pattern.literal_type));
+    }
+    match Regex::new(&pattern.value) {
+        Ok(regex) => regex,
+        Err(e) => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() received an invalid pattern: {}", function_name, e));
+            panic!();
+        }
+    }
+}
+fn regex_match(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns true if the pattern matches anywhere in the source
+    let source = arguments.get(0).unwrap();
+    let pattern = arguments.get(1).unwrap();
+    let regex = build_regex(source, pattern, "regex_match", tok);
+    ast::Literal::bool(regex.is_match(&source.value))
+}
+fn regex_find_all(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array of String Literals, one for each match
+    let source = arguments.get(0).unwrap();
+    let pattern = arguments.get(1).unwrap();
+    let regex = build_regex(source, pattern, "regex_find_all", tok);
+    let result_array = regex.find_iter(&source.value).map(|m| ast::Literal::string(m.as_str().to_string())).collect();
+    ast::Literal::new_array(result_array)
+}
+fn regex_replace(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Replaces every match. Capture groups can be referred to in the replacement with $1, $2, ${name} and so on
+    let source = arguments.get(0).unwrap();
+    let pattern = arguments.get(1).unwrap();
+    let replacement = arguments.get(2).unwrap();
+    let regex = build_regex(source, pattern, "regex_replace", tok);
+    if replacement.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("regex_replace() expects 3rd argument (replacement) of type String, but received {:?} instead", replacement.literal_type));
+    }
+    ast::Literal::string(regex.replace_all(&source.value, replacement.value.as_str()).to_string())
+}
 
 // Array operations
 fn length(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let value = arguments.get(0).unwrap();
     if value.literal_type != ast::LiteralType::Array && value.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("length() expects one argument of type Array or String, but received {:?} instead", value.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("length() expects one argument of type Array or String, but received {:?} instead", value.literal_type));
     }
     else {
         if value.literal_type == ast::LiteralType::Array {
             // Length of array
-            return ast::Literal::number(value.array_values.len().to_string());
+            return ast::Literal::number(value.array_values.len());
         }
         else {
-            // Length of string
-            return ast::Literal::number(value.value.len().to_string());
+            // Length of string in characters, not bytes
+            return ast::Literal::number(value.value.chars().count());
         }
     }
     ast::Literal::none()
@@ -540,44 +2137,39 @@ fn insert(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let new_value = arguments.get(2).unwrap();
     
     if source.literal_type != ast::LiteralType::Array && source.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("insert() expects 1st argument (source) of type Array or String, but received {:?} instead", source.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("insert() expects 1st argument (source) of type Array or String, but received {:?} instead", source.literal_type));
     }
     else if index.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("insert() expects 2nd argument (index) of type Number, but received {:?} instead", index.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("insert() expects 2nd argument (index) of type Number, but received {:?} instead", index.literal_type));
     }
     else {
         // Do some index checks
         if index.literal_type != ast::LiteralType::Number {
-            tok.print_custom_error(&format!("{:?} is not a valid array index type for insert(). Only positive integers are allowed", index.literal_type));
+            tok.print_custom_error(ErrorClass::InvalidIndex, &format!("{:?} is not a valid array index type for insert(). Only positive integers are allowed", index.literal_type));
         }
-        let index_float = Expr::string_to_float(&index);
+        let index_float = Expr::to_float(&index);
         if index_float.fract() != 0.0 {
-            tok.print_custom_error(&format!("{} is a float and is not a valid array index for insert(). Only positive integers are allowed", index_float));
+            tok.print_custom_error(ErrorClass::InvalidIndex, &format!("{} is a float and is not a valid array index for insert(). Only positive integers are allowed", index_float));
         }
-        let index_integer = index_float as i32;
+        let index_integer = index_float as i64;
         if index_integer < 0 {
-            tok.print_custom_error(&format!("{} is negative and is not a valid array index for insert(). Only positive integers are allowed", index_float));
+            tok.print_custom_error(ErrorClass::InvalidIndex, &format!("{} is negative and is not a valid array index for insert(). Only positive integers are allowed", index_float));
         }
         let index_integer = index_integer as usize;
         if source.literal_type == ast::LiteralType::Array {
             // Array insert
             if new_value.literal_type != ast::LiteralType::Array {
-                tok.print_custom_error(&format!("insert() expects new value of type Array, but received {:?} instead", new_value.literal_type));
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("insert() expects new value of type Array, but received {:?} instead", new_value.literal_type));
             }
-            let mut source_array = source.array_values.clone();
+            let mut source_array = source.array_values.to_vec();
             if source_array.len() == 0 {
                 if index_integer == 0 {
-                    source_array.push(new_value.clone());
+                    return ast::Literal::new_array(new_value.array_values.to_vec());
                 }
-                tok.print_custom_error(&format!("insert() cannot insert at {} because the array is empty.", index_integer));
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("insert() cannot insert at {} because the array is empty.", index_integer));
             }
             if index_integer > source_array.len() {
-                tok.print_custom_error(&format!("insert() cannot insert at {} because it is beyond the array's bounds.", index_integer));
-            }
-            let original_type = source_array.get(0).unwrap().literal_type;
-            let new_type = new_value.array_values.get(0).unwrap().literal_type;
-            if new_type != original_type {
-                tok.print_custom_error(&format!("insert() expects 3rd argument (value) of type {:?}, but received {:?} instead", original_type, new_type));
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("insert() cannot insert at {} because it is beyond the array's bounds.", index_integer));
             }
             source_array.splice(index_integer..index_integer, new_value.array_values.iter().cloned());
             return ast::Literal::new_array(source_array);
@@ -586,10 +2178,10 @@ fn insert(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
             // String insert
             let mut source_string = source.value.clone();
             if index_integer > source_string.len() {
-                tok.print_custom_error(&format!("insert() cannot insert at {} because it is beyond the string's bounds.", index_integer));
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("insert() cannot insert at {} because it is beyond the string's bounds.", index_integer));
             }
             if new_value.literal_type != ast::LiteralType::String {
-                tok.print_custom_error(&format!("insert() expects 3rd argument (value) of type String, but received {:?} instead", new_value.literal_type));
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("insert() expects 3rd argument (value) of type String, but received {:?} instead", new_value.literal_type));
             }
             source_string.insert_str(index_integer, &new_value.value);
             return ast::Literal::string(source_string);
@@ -602,33 +2194,33 @@ fn remove(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let index = arguments.get(1).unwrap();
     
     if source.literal_type != ast::LiteralType::Array && source.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("remove() expects 1st argument (source) of type Array or String, but received {:?} instead", source.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("remove() expects 1st argument (source) of type Array or String, but received {:?} instead", source.literal_type));
     }
     else if index.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("remove() expects 2nd argument (index) of type Number, but received {:?} instead", index.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("remove() expects 2nd argument (index) of type Number, but received {:?} instead", index.literal_type));
     }
     else {
         // Do some index checks
         if index.literal_type != ast::LiteralType::Number {
-            tok.print_custom_error(&format!("{:?} is not a valid array index type for remove(). Only positive integers are allowed", index.literal_type));
+            tok.print_custom_error(ErrorClass::InvalidIndex, &format!("{:?} is not a valid array index type for remove(). Only positive integers are allowed", index.literal_type));
         }
-        let index_float = Expr::string_to_float(&index);
+        let index_float = Expr::to_float(&index);
         if index_float.fract() != 0.0 {
-            tok.print_custom_error(&format!("{} is a float and is not a valid array index for remove(). Only positive integers are allowed", index_float));
+            tok.print_custom_error(ErrorClass::InvalidIndex, &format!("{} is a float and is not a valid array index for remove(). Only positive integers are allowed", index_float));
         }
-        let index_integer = index_float as i32;
+        let index_integer = index_float as i64;
         if index_integer < 0 {
-            tok.print_custom_error(&format!("{} is negative and is not a valid array index for remove(). Only positive integers are allowed", index_float));
+            tok.print_custom_error(ErrorClass::InvalidIndex, &format!("{} is negative and is not a valid array index for remove(). Only positive integers are allowed", index_float));
         }
         let index_integer = index_integer as usize;
         if source.literal_type == ast::LiteralType::Array {
             // Array remove
-            let mut source_array = source.array_values.clone();
+            let mut source_array = source.array_values.to_vec();
             if source_array.len() == 0 {
-                tok.print_custom_error(&format!("remove() cannot remove at {} because the array is empty.", index_integer));
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("remove() cannot remove at {} because the array is empty.", index_integer));
             }
             if index_integer >= source_array.len() {
-                tok.print_custom_error(&format!("remove() cannot remove at {} because it is beyond the array's bounds.", index_integer));
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("remove() cannot remove at {} because it is beyond the array's bounds.", index_integer));
             }
             source_array.remove(index_integer);
             return ast::Literal::new_array(source_array);
@@ -637,7 +2229,7 @@ fn remove(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
             // String remove
             let mut source_string = source.value.clone();
             if index_integer >= source_string.len() {
-                tok.print_custom_error(&format!("remove() cannot remove at {} because it is beyond the string's bounds.", index_integer));
+                tok.print_custom_error(ErrorClass::InvalidArgument, &format!("remove() cannot remove at {} because it is beyond the string's bounds.", index_integer));
             }
             source_string.remove(index_integer);
             return ast::Literal::string(source_string);
@@ -645,24 +2237,160 @@ fn remove(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     }
     ast::Literal::none()
 }
-
-fn map(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+fn index_of(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns index of the first match, or -1 if not found
     let source = arguments.get(0).unwrap();
-    let map_function = arguments.get(1).unwrap();
-    
-    if source.literal_type != ast::LiteralType::Array {
-        tok.print_custom_error(&format!("map() expects 1st argument (source) of type Array, but received {:?} instead", source.literal_type));
+    let value = arguments.get(1).unwrap();
+    match find_index(source, value, "index_of", tok) {
+        Some(index) => ast::Literal::number(index),
+        None => ast::Literal::number(-1)
     }
-    else if map_function.literal_type != ast::LiteralType::Function {
-        tok.print_custom_error(&format!("map() expects 2nd argument (function) of type Function, but received {:?} instead", map_function.literal_type));
+}
+fn contains(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let source = arguments.get(0).unwrap();
+    let value = arguments.get(1).unwrap();
+    ast::Literal::bool(find_index(source, value, "contains", tok).is_some())
+}
+// push(), pop() and concat() change the array given as their first argument, which is only copied if it is shared.
+// In 'arr = push(arr, x)', the variable is moved into the call so that it is not shared, see Function::moves_first_argument()
+fn push(mut arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns the array with the value appended at the end
+    let new_value = arguments.pop().unwrap();
+    let mut source = arguments.pop().unwrap();
+    check_array_argument(&source, "1st argument (source)", "push", tok);
+    source.array_mut().push(new_value);
+    source
+}
+fn pop(mut arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns [array without its last element, last element], where the element is null if the array is empty
+    let mut source = arguments.pop().unwrap();
+    check_array_argument(&source, "one argument", "pop", tok);
+    let last = source.array_mut().pop().unwrap_or_else(ast::Literal::null);
+    ast::Literal::new_array(vec![source, last])
+}
+fn concat(mut arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns the elements of the left array followed by the elements of the right array
+    let right = arguments.pop().unwrap();
+    let mut left = arguments.pop().unwrap();
+    check_array_argument(&left, "1st argument (left)", "concat", tok);
+    check_array_argument(&right, "2nd argument (right)", "concat", tok);
+    left.array_mut().extend(right.into_array());
+    left
+}
+fn check_array_argument(literal: &ast::Literal, description: &str, function_name: &str, tok: &token::Token) {
+    if literal.literal_type != ast::LiteralType::Array {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects {} of type Array, but received {:?} instead", function_name, description, literal.literal_type));
     }
-    else {
-        let function = map_function.function.as_ref().unwrap();
-        if function.arg_length() != 1 {
-            tok.print_custom_error(&format!("map() expects a function with 1 argument, but received one with {} arguments instead", function.arg_length()));
+}
+fn flatten(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Flattens nested arrays by one level, other elements are kept as they are
+    let source = arguments.get(0).unwrap();
+    check_array_argument(source, "one argument", "flatten", tok);
+    let mut result_array = Vec::<ast::Literal>::new();
+    for value in source.array_values.iter() {
+        if value.literal_type == ast::LiteralType::Array {
+            result_array.extend(value.array_values.iter().cloned());
         }
-        // Array map
-        let source_array = &source.array_values;
+        else {
+            result_array.push(value.clone());
+        }
+    }
+    ast::Literal::new_array(result_array)
+}
+fn unique(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Removes duplicates, keeping the first occurrence of each value
+    let source = arguments.get(0).unwrap();
+    check_array_argument(source, "one argument", "unique", tok);
+    let mut seen = std::collections::HashSet::<String>::new();
+    let result_array = source.array_values.iter()
+                        .filter(|value| seen.insert(literal_key(value)))
+                        .cloned()
+                        .collect();
+    ast::Literal::new_array(result_array)
+}
+fn zip(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array of [left, right] pairs, as long as the shorter array
+    let left = arguments.get(0).unwrap();
+    let right = arguments.get(1).unwrap();
+    check_array_argument(left, "1st argument (left)", "zip", tok);
+    check_array_argument(right, "2nd argument (right)", "zip", tok);
+    let result_array = left.array_values.iter()
+                        .zip(right.array_values.iter())
+                        .map(|(a, b)| ast::Literal::new_array(vec![a.clone(), b.clone()]))
+                        .collect();
+    ast::Literal::new_array(result_array)
+}
+fn enumerate(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array of [index, value] pairs
+    let source = arguments.get(0).unwrap();
+    check_array_argument(source, "one argument", "enumerate", tok);
+    let result_array = source.array_values.iter()
+                        .enumerate()
+                        .map(|(index, value)| ast::Literal::new_array(vec![ast::Literal::number(index), value.clone()]))
+                        .collect();
+    ast::Literal::new_array(result_array)
+}
+fn take(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns the first n elements, or the whole array if it is shorter
+    let source = arguments.get(0).unwrap();
+    check_array_argument(source, "1st argument (source)", "take", tok);
+    let n = literal_to_index(arguments.get(1).unwrap(), "count", "take", tok);
+    ast::Literal::new_array(source.array_values.iter().take(n).cloned().collect())
+}
+fn drop(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns the array without its first n elements
+    let source = arguments.get(0).unwrap();
+    check_array_argument(source, "1st argument (source)", "drop", tok);
+    let n = literal_to_index(arguments.get(1).unwrap(), "count", "drop", tok);
+    ast::Literal::new_array(source.array_values.iter().skip(n).cloned().collect())
+}
+fn partition_size(arguments: &[ast::Literal], function_name: &str, tok: &token::Token) -> usize {
+    let size = literal_to_index(arguments.get(1).unwrap(), "size", function_name, tok);
+    if size == 0 {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("0 is not a valid size for {}(). Size must be at least 1", function_name));
+    }
+    size
+}
+fn chunk(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Splits the array into arrays of the given size, the last one may be shorter
+    let source = arguments.get(0).unwrap();
+    check_array_argument(source, "1st argument (source)", "chunk", tok);
+    let size = partition_size(&arguments, "chunk", tok);
+    let result_array = source.array_values
+                        .chunks(size)
+                        .map(|c| ast::Literal::new_array(c.to_vec()))
+                        .collect();
+    ast::Literal::new_array(result_array)
+}
+fn windows(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns every overlapping window of the given size, empty if the array is shorter than the size
+    let source = arguments.get(0).unwrap();
+    check_array_argument(source, "1st argument (source)", "windows", tok);
+    let size = partition_size(&arguments, "windows", tok);
+    let result_array = source.array_values
+                        .windows(size)
+                        .map(|w| ast::Literal::new_array(w.to_vec()))
+                        .collect();
+    ast::Literal::new_array(result_array)
+}
+
+fn map(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let source = arguments.get(0).unwrap();
+    let map_function = arguments.get(1).unwrap();
+    
+    if source.literal_type != ast::LiteralType::Array {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("map() expects 1st argument (source) of type Array, but received {:?} instead", source.literal_type));
+    }
+    else if map_function.literal_type != ast::LiteralType::Function {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("map() expects 2nd argument (function) of type Function, but received {:?} instead", map_function.literal_type));
+    }
+    else {
+        let function = map_function.function.as_ref().unwrap();
+        if !function.accepts(1) {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("map() expects a function with 1 argument, but received one with {} arguments instead", function.arg_length()));
+        }
+        // Array map
+        let source_array = &source.array_values;
         let result_array = source_array.iter()
                                         .map(
                                             |a|
@@ -672,7 +2400,7 @@ fn map(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
                                                         literal
                                                     },
                                                     None => {
-                                                        tok.print_custom_error(&format!("map() cannot invoke Function of type 'None'"));
+                                                        tok.print_custom_error(ErrorClass::NotCallable, &format!("map() cannot invoke Function of type 'None'"));
                                                         ast::Literal::none()
                                                     }
                                                 }
@@ -686,6 +2414,7 @@ fn map(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
 }
 ///////////////////
 // Helper function
+#[cfg(any(feature = "os", feature = "plugins"))]
 fn string_to_bool(string : &str) -> bool {
     match string {
         "true" => {
@@ -702,66 +2431,95 @@ fn string_to_bool(string : &str) -> bool {
         }
     }
 }
+fn literal_to_index(literal: &ast::Literal, name: &str, function_name: &str, tok: &token::Token) -> usize {
+    // Do some integer checks
+    if literal.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{:?} is not a valid {} for {}(). Only positive integers are allowed", literal.literal_type, name, function_name));
+    }
+    let index_integer = match Expr::to_integer(&literal) {
+        Some(integer) => integer,
+        None => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is a float and is not a valid {} for {}(). Only positive integers are allowed", literal.value, name, function_name));
+            panic!();
+        }
+    };
+    if index_integer < 0 {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is negative and is not a valid {} for {}(). Only positive integers are allowed", index_integer, name, function_name));
+    }
+    index_integer as usize
+}
+fn literal_equals(left: &ast::Literal, right: &ast::Literal) -> bool {
+    if left.literal_type != right.literal_type {
+        return false;
+    }
+    match left.literal_type {
+        ast::LiteralType::Number => {
+            Expr::numbers_equal(&left, &right)
+        },
+        ast::LiteralType::Array => {
+            left.array_values.len() == right.array_values.len() &&
+            left.array_values.iter().zip(right.array_values.iter()).all(|(a, b)| literal_equals(a, b))
+        },
+        _ => {
+            left.value == right.value
+        }
+    }
+}
+fn literal_key(literal: &ast::Literal) -> String {
+    // Key which is the same for literals considered equal by literal_equals()
+    match literal.literal_type {
+        ast::LiteralType::Number => {
+            match Expr::to_integer(&literal) {
+                Some(integer) => format!("Number:{}", integer),
+                None => format!("Number:{}", Expr::to_float(&literal))
+            }
+        },
+        ast::LiteralType::Array => {
+            let keys = literal.array_values.iter().map(literal_key).collect::<Vec<String>>();
+            format!("Array:[{}]", keys.join(","))
+        },
+        _ => {
+            format!("{:?}:{}", literal.literal_type, literal.value)
+        }
+    }
+}
+fn find_index(source: &ast::Literal, value: &ast::Literal, function_name: &str, tok: &token::Token) -> Option<usize> {
+    // Searches an Array for an equal element, or a String for a substring
+    if source.literal_type == ast::LiteralType::Array {
+        return source.array_values.iter().position(|element| literal_equals(element, value));
+    }
+    else if source.literal_type == ast::LiteralType::String {
+        if value.literal_type != ast::LiteralType::String {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 2nd argument (value) of type String when searching a String, but received {:?} instead", function_name, value.literal_type));
+        }
+        // Convert byte index to character index
+        return source.value.find(&value.value).map(|byte_index| source.value[..byte_index].chars().count());
+    }
+    tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 1st argument (source) of type Array or String, but received {:?} instead", function_name, source.literal_type));
+    None
+}
 ///////////////////////////////
 // Continued...Array Operations
 
 fn filter(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
-    // Returns an array of bool Literals
+    // Returns an array of the elements for which the function returns a truthy value
     let source = arguments.get(0).unwrap();
     let filter_function = arguments.get(1).unwrap();
     
     if source.literal_type != ast::LiteralType::Array {
-        tok.print_custom_error(&format!("filter() expects 1st argument (source) of type Array, but received {:?} instead", source.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("filter() expects 1st argument (source) of type Array, but received {:?} instead", source.literal_type));
     }
     else if filter_function.literal_type != ast::LiteralType::Function {
-        tok.print_custom_error(&format!("filter() expects 2nd argument (function) of type Function, but received {:?} instead", filter_function.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("filter() expects 2nd argument (function) of type Function, but received {:?} instead", filter_function.literal_type));
     }
     else {
         let function = filter_function.function.as_ref().unwrap();
-        if function.arg_length() != 1 {
-            tok.print_custom_error(&format!("filter() expects a function with 1 argument, but received one with {} arguments instead", function.arg_length()));
-        }
-
-        let source_array = &source.array_values;
-
-        // Check if function returns boolean Literal
-        if source_array.len() > 0 {
-            let return_type = match function.call(vec![source_array.get(0).unwrap().clone()], &tok) {
-                Some(literal) => {
-                    literal.literal_type
-                },
-                None => {
-                    tok.print_custom_error(&format!("filter() cannot invoke Function of type 'None'"));
-                    panic!();
-                }
-            };
-            if return_type != ast::LiteralType::Bool && return_type != ast::LiteralType::Null && return_type != ast::LiteralType::None {
-                tok.print_custom_error(&format!("filter() expects 2nd argument (function) to return Bool, but received {:?} instead", return_type));
-            }
+        if !function.accepts(1) {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("filter() expects a function with 1 argument, but received one with {} arguments instead", function.arg_length()));
         }
 
-        // Array filter
-        let result_array = source_array.iter().cloned()
-                                        .filter(
-                                            |a|
-                                            {
-                                                match function.call(vec![a.clone()], &tok) {
-                                                    Some(literal) => {
-                                                        if literal.literal_type == ast::LiteralType::None {
-                                                            false
-                                                        }
-                                                        else {
-                                                            string_to_bool(&literal.value)
-                                                        }
-                                                    },
-                                                    None => {
-                                                        tok.print_custom_error(&format!("filter() cannot invoke Function of type 'None'"));
-                                                        panic!();
-                                                    }
-                                                }
-                                            }
-                                        )
-                                        .collect();
+        // The function's results are truthy or falsy, like conditions
+        let result_array = source.array_values.iter().filter(|a| call_predicate(function, a, "filter", tok)).cloned().collect();
         return ast::Literal::new_array(result_array);
     }
     ast::Literal::none()
@@ -773,76 +2531,146 @@ fn reduce(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let filter_function = arguments.get(2).unwrap();
     
     if source.literal_type != ast::LiteralType::Array {
-        tok.print_custom_error(&format!("reduce() expects 1st argument (source) of type Array, but received {:?} instead", source.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("reduce() expects 1st argument (source) of type Array, but received {:?} instead", source.literal_type));
     }
     else if filter_function.literal_type != ast::LiteralType::Function {
-        tok.print_custom_error(&format!("reduce() expects 3rd argument (function) of type Function, but received {:?} instead", filter_function.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("reduce() expects 3rd argument (function) of type Function, but received {:?} instead", filter_function.literal_type));
     }
     else {
         let function = filter_function.function.as_ref().unwrap();
-        if function.arg_length() != 2 {
-            tok.print_custom_error(&format!("reduce() expects a function with 2 arguments, but received one with {} arguments instead", function.arg_length()));
+        if !function.accepts(2) {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("reduce() expects a function with 2 arguments, but received one with {} arguments instead", function.arg_length()));
         }
 
-        let source_array = &source.array_values;
+        // The initial value and the function's results can be of any type, such as a count of strings
+        let result_literal = source.array_values.iter()
+                                        .fold(initial_literal.clone(), |a, b| call_or_error(function, vec![a, b.clone()], "reduce", tok));
+        return result_literal;
+    }
+    ast::Literal::none()
+}
 
-        // Check if function returns Literal
-        if source_array.len() > 0 {
-            let first_element = source_array.get(0).unwrap().clone();
-            if initial_literal.literal_type != first_element.literal_type {
-                tok.print_custom_error(&format!("2nd argument (initial_value) of reduce() is of type {:?}, but array values are of type {:?}", initial_literal.literal_type, first_element.literal_type));
-            }
-            let return_type = match function.call(vec![initial_literal.clone(), first_element], &tok) {
-                Some(literal) => {
-                    literal.literal_type
-                },
-                None => {
-                    tok.print_custom_error(&format!("reduce() cannot invoke Function of type 'None'"));
-                    panic!();
-                }
-            };
-            if return_type != initial_literal.literal_type {
-                tok.print_custom_error(&format!("reduce() expects 3rd argument (function) to return {:?}, but received {:?} instead", initial_literal.literal_type, return_type));
-            }
+// Parallel variants run the callback on rayon's threads, each of which evaluates on its own environment stack
+fn call_or_error(function: &Function, arguments: Vec<ast::Literal>, function_name: &str, tok: &token::Token) -> ast::Literal {
+    match function.call(arguments, &tok) {
+        Some(literal) => literal,
+        None => {
+            tok.print_custom_error(ErrorClass::NotCallable, &format!("{}() cannot invoke Function of type 'None'", function_name));
+            panic!();
         }
+    }
+}
+fn par_map(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let (source_array, function) = predicate_arguments(&arguments, "par_map", tok);
+    let result_array = source_array.par_iter()
+                        .map(|a| call_or_error(function, vec![a.clone()], "par_map", tok))
+                        .collect();
+    ast::Literal::new_array(result_array)
+}
+fn par_filter(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let (source_array, function) = predicate_arguments(&arguments, "par_filter", tok);
+    let result_array = source_array.par_iter()
+                        .filter(|a| call_predicate(function, a, "par_filter", tok))
+                        .cloned()
+                        .collect();
+    ast::Literal::new_array(result_array)
+}
+fn par_reduce(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Elements are combined in any grouping, so the function must be associative, e.g. addition or max
+    // The initial value is combined once, with the reduced elements
+    let source = arguments.get(0).unwrap();
+    let initial_literal = arguments.get(1).unwrap();
+    let reduce_function = arguments.get(2).unwrap();
+    check_array_argument(source, "1st argument (source)", "par_reduce", tok);
+    if reduce_function.literal_type != ast::LiteralType::Function {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("par_reduce() expects 3rd argument (function) of type Function, but received {:?} instead", reduce_function.literal_type));
+    }
+    let function = reduce_function.function.as_ref().unwrap();
+    if !function.accepts(2) {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("par_reduce() expects a function with 2 arguments, but received one with {} arguments instead", function.arg_length()));
+    }
+    let reduced = source.array_values.par_iter()
+                        .cloned()
+                        .reduce_with(|a, b| call_or_error(function, vec![a, b], "par_reduce", tok));
+    match reduced {
+        Some(literal) => call_or_error(function, vec![initial_literal.clone(), literal], "par_reduce", tok),
+        None => initial_literal.clone()
+    }
+}
 
-        // Array reduce
-        let result_literal : ast::Literal = source_array.iter()
-                                        .fold(
-                                            initial_literal.clone(),
-                                            |a, b|
-                                            {
-                                                match function.call(vec![a.clone(), b.clone()], &tok) {
-                                                    Some(literal) => {
-                                                        literal
-                                                    },
-                                                    None => {
-                                                        tok.print_custom_error(&format!("reduce() cannot invoke Function of type 'None'"));
-                                                        panic!();
-                                                    }
-                                                }
-                                            }
-                                        );
-        return result_literal;
+fn predicate_arguments<'a>(arguments: &'a [ast::Literal], function_name: &str, tok: &token::Token) -> (&'a Vec<ast::Literal>, &'a Function) {
+    // Checks (array, function with 1 argument) arguments shared by find(), any(), all(), count_if(), par_map() and par_filter()
+    let source = arguments.get(0).unwrap();
+    let predicate = arguments.get(1).unwrap();
+    if source.literal_type != ast::LiteralType::Array {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 1st argument (source) of type Array, but received {:?} instead", function_name, source.literal_type));
     }
-    ast::Literal::none()
+    if predicate.literal_type != ast::LiteralType::Function {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 2nd argument (function) of type Function, but received {:?} instead", function_name, predicate.literal_type));
+    }
+    let function = predicate.function.as_ref().unwrap();
+    if !function.accepts(1) {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects a function with 1 argument, but received one with {} arguments instead", function_name, function.arg_length()));
+    }
+    (&source.array_values, function)
+}
+fn call_predicate(function: &Function, value: &ast::Literal, function_name: &str, tok: &token::Token) -> bool {
+    match function.call(vec![value.clone()], &tok) {
+        // A function which returns nothing counts as false
+        Some(literal) => literal.literal_type != ast::LiteralType::None && ast::Expr::is_truthy(&literal),
+        None => {
+            tok.print_custom_error(ErrorClass::NotCallable, &format!("{}() cannot invoke Function of type 'None'", function_name));
+            panic!();
+        }
+    }
+}
+fn find(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns the first element for which the function returns true, null Literal if none
+    let (source_array, function) = predicate_arguments(&arguments, "find", tok);
+    match source_array.iter().find(|a| call_predicate(function, a, "find", tok)) {
+        Some(literal) => literal.clone(),
+        None => ast::Literal::null()
+    }
+}
+fn any(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns true if the function returns true for at least one element
+    let (source_array, function) = predicate_arguments(&arguments, "any", tok);
+    ast::Literal::bool(source_array.iter().any(|a| call_predicate(function, a, "any", tok)))
+}
+fn all(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns true if the function returns true for every element, including an empty array
+    let (source_array, function) = predicate_arguments(&arguments, "all", tok);
+    ast::Literal::bool(source_array.iter().all(|a| call_predicate(function, a, "all", tok)))
+}
+fn count_if(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns number of elements for which the function returns true
+    let (source_array, function) = predicate_arguments(&arguments, "count_if", tok);
+    let count = source_array.iter().filter(|a| call_predicate(function, a, "count_if", tok)).count();
+    ast::Literal::number(count)
 }
 
-fn range(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+fn range(mut arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     // Returns array of number Literals
+    // range(end) starts from 0, and the step is 1 unless given
+    if arguments.len() == 1 {
+        arguments.insert(0, Function::native_default(NativeType::Range, 0).unwrap());
+    }
+    if arguments.len() == 2 {
+        arguments.push(Function::native_default(NativeType::Range, 2).unwrap());
+    }
     let start = arguments.get(0).unwrap();
     let end = arguments.get(1).unwrap();
     let step = arguments.get(2).unwrap();
     if start.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("range() expects 1st argument (start) of type Number, but received {:?} instead", start.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("range() expects 1st argument (start) of type Number, but received {:?} instead", start.literal_type));
     }
     if end.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("range() expects 2nd argument (end) of type Number, but received {:?} instead", end.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("range() expects 2nd argument (end) of type Number, but received {:?} instead", end.literal_type));
     }
     else {
-        let mut start_float = Expr::string_to_float(&start);
-        let end_float = Expr::string_to_float(&end);
-        let step_float = Expr::string_to_float(&step);
+        let mut start_float = Expr::to_float(&start);
+        let end_float = Expr::to_float(&end);
+        let step_float = Expr::to_float(&step);
 
         if start_float == end_float {
             return ast::Literal::new_array(vec![start.clone()]);
@@ -851,22 +2679,24 @@ fn range(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
         // Do some range checks
         let increasing = start_float < end_float;
         if step.literal_type != ast::LiteralType::Number {
-            tok.print_custom_error(&format!("{:?} is not a valid step for range()", step.literal_type));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{:?} is not a valid step for range()", step.literal_type));
         }
         if (1.0/step_float).is_infinite() {
-            tok.print_custom_error(&format!("range() expects a non-zero step from {} to {}", start_float, end_float));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("range() expects a non-zero step from {} to {}", start_float, end_float));
         }
         if increasing && step_float < 0.0 {
             // Increasing, but negative step
-            tok.print_custom_error(&format!("range() expects a positive step from {} to {}, but received a {} step instead", start_float, end_float, step_float));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("range() expects a positive step from {} to {}, but received a {} step instead", start_float, end_float, step_float));
         }
         else if !increasing && step_float > 0.0 {
             // Decreasing, but positive step
-            tok.print_custom_error(&format!("range() expects a negative step from {} to {}, but received a {} step instead", start_float, end_float, step_float));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("range() expects a negative step from {} to {}, but received a {} step instead", start_float, end_float, step_float));
         }
+        // Counts in integers when the start and step are integers
+        let integers = matches!((start.number, step.number), (ast::Number::Integer(_), ast::Number::Integer(_)));
         let mut result_array = Vec::<ast::Literal>::new();
         loop {
-            result_array.push(ast::Literal::number(start_float.to_string()));
+            result_array.push(if integers { ast::Literal::number(start_float as i64) } else { ast::Literal::number(start_float) });
             if increasing {
                 start_float += step_float;
                 if start_float > end_float {
@@ -884,29 +2714,34 @@ fn range(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     }
     ast::Literal::none()
 }
-fn linspace(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+const LINSPACE_ELEMENTS: usize = 50; // Number of elements when linspace() is not given one
+
+fn linspace(mut arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     // Returns array of number Literals
+    if arguments.len() == 2 {
+        arguments.push(Function::native_default(NativeType::Linspace, 2).unwrap());
+    }
     let start = arguments.get(0).unwrap();
     let end = arguments.get(1).unwrap();
     let num_of_elements = arguments.get(2).unwrap();
     if start.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("linspace() expects 1st argument (start) of type Number, but received {:?} instead", start.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("linspace() expects 1st argument (start) of type Number, but received {:?} instead", start.literal_type));
     }
     if end.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("linspace() expects 2nd argument (end) of type Number, but received {:?} instead", end.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("linspace() expects 2nd argument (end) of type Number, but received {:?} instead", end.literal_type));
     }
     else {
         // Do some integer checks
         if num_of_elements.literal_type != ast::LiteralType::Number {
-            tok.print_custom_error(&format!("{:?} is not a valid value for linspace(). Only positive integers are allowed", num_of_elements.literal_type));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{:?} is not a valid value for linspace(). Only positive integers are allowed", num_of_elements.literal_type));
         }
-        let num_float = Expr::string_to_float(&num_of_elements);
+        let num_float = Expr::to_float(&num_of_elements);
         if num_float.fract() != 0.0 {
-            tok.print_custom_error(&format!("{} is a float and is not a valid value for linspace(). Only positive integers are allowed", num_float));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is a float and is not a valid value for linspace(). Only positive integers are allowed", num_float));
         }
         let num_integer = num_float as i32;
         if num_integer < 0 {
-            tok.print_custom_error(&format!("{} is negative and is not a valid value for linspace(). Only positive integers are allowed", num_integer));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is negative and is not a valid value for linspace(). Only positive integers are allowed", num_integer));
         }
         // Return early if only 0,1, or 2 elements
         let mut num_integer = num_integer as usize;
@@ -919,17 +2754,17 @@ fn linspace(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
             };
         }
 
-        let mut start_float = Expr::string_to_float(&start);
-        let end_float = Expr::string_to_float(&end);
+        let mut start_float = Expr::to_float(&start);
+        let end_float = Expr::to_float(&end);
 
         if start_float == end_float {
             return ast::Literal::new_array((0..num_integer).map(|_| start.clone()).collect::<Vec<ast::Literal>>());
         }
-        let step_float = (end_float - start_float).abs() / ((num_integer - 1) as f32);
+        let step_float = (end_float - start_float).abs() / ((num_integer - 1) as f64);
         let increasing = start_float < end_float;
         let mut result_array = Vec::<ast::Literal>::new();
         while num_integer > 0 {
-            result_array.push(ast::Literal::number(start_float.to_string()));
+            result_array.push(ast::Literal::number(start_float));
             if increasing {
                 start_float += step_float;
             }
@@ -948,51 +2783,210 @@ fn repeat(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     let num_of_elements = arguments.get(1).unwrap();
     // Do some integer checks
     if num_of_elements.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("{:?} is not a valid repeat value for repeat(). Only positive integers are allowed", num_of_elements.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{:?} is not a valid repeat value for repeat(). Only positive integers are allowed", num_of_elements.literal_type));
     }
-    let num_float = Expr::string_to_float(&num_of_elements);
+    let num_float = Expr::to_float(&num_of_elements);
     if num_float.fract() != 0.0 {
-        tok.print_custom_error(&format!("{} is a float and is not a valid repeat value for repeat(). Only positive integers are allowed", num_float));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is a float and is not a valid repeat value for repeat(). Only positive integers are allowed", num_float));
     }
     let num_integer = num_float as i32;
     if num_integer < 0 {
-        tok.print_custom_error(&format!("{} is negative and is not a valid repeat value for repeat(). Only positive integers are allowed", num_integer));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is negative and is not a valid repeat value for repeat(). Only positive integers are allowed", num_integer));
     }
     let num_integer = num_integer as usize;
     let result_array = (0..num_integer).map(|_| literal_copy.clone()).collect::<Vec<ast::Literal>>();
     return ast::Literal::new_array(result_array);
 }
 
+// Statistics
+fn literal_to_numbers(literal: &ast::Literal, function_name: &str, tok: &token::Token, allow_empty: bool) -> Vec<f64> {
+    // Converts an array of number Literals using rayon's iteration
+    if literal.literal_type != ast::LiteralType::Array {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects one argument of type Array, but received {:?} instead", function_name, literal.literal_type));
+    }
+    if literal.array_values.len() == 0 {
+        if !allow_empty {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects a non-empty Array", function_name));
+        }
+        return Vec::<f64>::new();
+    }
+    if let Some(value) = literal.array_values.iter().find(|value| value.literal_type != ast::LiteralType::Number) {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects an Array of Numbers, but received an Array with {:?} elements instead", function_name, value.literal_type));
+    }
+    literal.array_values.par_iter().map(|value| Expr::to_float(&value)).collect()
+}
+fn mean_of(numbers: &[f64]) -> f64 {
+    numbers.par_iter().sum::<f64>() / numbers.len() as f64
+}
+fn variance_of(numbers: &[f64]) -> f64 {
+    // Population variance
+    let mean = mean_of(numbers);
+    numbers.par_iter().map(|value| (value - mean) * (value - mean)).sum::<f64>() / numbers.len() as f64
+}
+fn sum(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Sum of an empty array is 0, and the sum of integers is an exact integer
+    let array = arguments.get(0).unwrap();
+    literal_to_numbers(array, "sum", tok, true);
+    array.array_values.iter().fold(ast::Literal::number(0), |total, value| Expr::arithmetic(&total, value, i64::checked_add, |l, r| l + r))
+}
+fn mean(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let numbers = literal_to_numbers(arguments.get(0).unwrap(), "mean", tok, false);
+    ast::Literal::number(mean_of(&numbers))
+}
+fn median(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Average of the two middle values for arrays of even length
+    let mut numbers = literal_to_numbers(arguments.get(0).unwrap(), "median", tok, false);
+    numbers.par_sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let middle = numbers.len() / 2;
+    let result = if numbers.len() % 2 == 0 {
+        (numbers[middle - 1] + numbers[middle]) / 2.0
+    }
+    else {
+        numbers[middle]
+    };
+    ast::Literal::number(result)
+}
+fn variance(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let numbers = literal_to_numbers(arguments.get(0).unwrap(), "variance", tok, false);
+    ast::Literal::number(variance_of(&numbers))
+}
+fn std_dev(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Population standard deviation
+    let numbers = literal_to_numbers(arguments.get(0).unwrap(), "std_dev", tok, false);
+    ast::Literal::number(variance_of(&numbers).sqrt())
+}
+fn cumsum(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Running sum, same length as the source
+    let numbers = literal_to_numbers(arguments.get(0).unwrap(), "cumsum", tok, true);
+    let running = numbers.iter().scan(0.0, |total, value| { *total += value; Some(*total) }).collect();
+    numbers_to_literal(running)
+}
+fn cumprod(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Running product, same length as the source
+    let numbers = literal_to_numbers(arguments.get(0).unwrap(), "cumprod", tok, true);
+    let running = numbers.iter().scan(1.0, |total, value| { *total *= value; Some(*total) }).collect();
+    numbers_to_literal(running)
+}
+fn diff(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // First differences, one shorter than the source
+    let numbers = literal_to_numbers(arguments.get(0).unwrap(), "diff", tok, true);
+    let differences = numbers.par_windows(2).map(|pair| pair[1] - pair[0]).collect();
+    numbers_to_literal(differences)
+}
+
+// Linear algebra
+fn numbers_to_literal(numbers: Vec<f64>) -> ast::Literal {
+    ast::Literal::new_array(numbers.into_iter().map(|value| ast::Literal::number(value)).collect())
+}
+fn literal_to_rows(literal: &ast::Literal, description: &str, function_name: &str, tok: &token::Token) -> Vec<Vec<ast::Literal>> {
+    // A matrix is a non-empty Array of Arrays which all have the same length
+    check_array_argument(literal, description, function_name, tok);
+    if literal.array_values.len() == 0 {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects {} to be a non-empty Array of Arrays", function_name, description));
+    }
+    let row_length = literal.array_values.get(0).unwrap().array_values.len();
+    for (index, row) in literal.array_values.iter().enumerate() {
+        if row.literal_type != ast::LiteralType::Array {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects {} to be an Array of Arrays, but row {} is of type {:?}", function_name, description, index, row.literal_type));
+        }
+        if row.array_values.len() != row_length {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects every row of {} to have length {}, but row {} has length {}", function_name, description, row_length, index, row.array_values.len()));
+        }
+    }
+    literal.array_values.iter().map(|row| row.array_values.to_vec()).collect()
+}
+fn literal_to_matrix(literal: &ast::Literal, description: &str, function_name: &str, tok: &token::Token) -> Vec<Vec<f64>> {
+    literal_to_rows(literal, description, function_name, tok);
+    literal.array_values.iter().map(|row| literal_to_numbers(row, function_name, tok, true)).collect()
+}
+fn dot(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let left = arguments.get(0).unwrap();
+    let right = arguments.get(1).unwrap();
+    check_array_argument(left, "1st argument (left)", "dot", tok);
+    check_array_argument(right, "2nd argument (right)", "dot", tok);
+    let left_numbers = literal_to_numbers(left, "dot", tok, true);
+    let right_numbers = literal_to_numbers(right, "dot", tok, true);
+    if left_numbers.len() != right_numbers.len() {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("dot() expects Arrays of the same length, but received lengths {} and {} instead", left_numbers.len(), right_numbers.len()));
+    }
+    let product = left_numbers.par_iter().zip(right_numbers.par_iter()).map(|(a, b)| a * b).sum::<f64>();
+    ast::Literal::number(product)
+}
+fn matmul(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // (n x m) matrix multiplied by (m x p) matrix gives (n x p) matrix. Rows are computed in parallel
+    let left = literal_to_matrix(arguments.get(0).unwrap(), "1st argument (left)", "matmul", tok);
+    let right = literal_to_matrix(arguments.get(1).unwrap(), "2nd argument (right)", "matmul", tok);
+    let inner = left.get(0).unwrap().len();
+    if inner != right.len() {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("matmul() cannot multiply a {}x{} matrix by a {}x{} matrix", left.len(), inner, right.len(), right.get(0).unwrap().len()));
+    }
+    let columns = right.get(0).unwrap().len();
+    let result_rows: Vec<ast::Literal> = left.par_iter()
+                        .map(|row| {
+                            let result_row = (0..columns).map(|j| {
+                                row.iter().enumerate().map(|(k, value)| value * right[k][j]).sum::<f64>()
+                            }).collect();
+                            numbers_to_literal(result_row)
+                        })
+                        .collect();
+    ast::Literal::new_array(result_rows)
+}
+fn transpose(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Works for matrices of any element type
+    let rows = literal_to_rows(arguments.get(0).unwrap(), "one argument", "transpose", tok);
+    let columns = rows.get(0).unwrap().len();
+    let result_rows = (0..columns)
+                        .map(|j| ast::Literal::new_array(rows.iter().map(|row| row[j].clone()).collect()))
+                        .collect();
+    ast::Literal::new_array(result_rows)
+}
+
 // Random generation
 use rand_distr::{Distribution, Uniform, Normal};
-use rand::thread_rng;
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use std::sync::Mutex;
+
+lazy_static! {
+    // Shared generator so that random_seed() makes every random function reproducible
+    static ref RNG: Mutex<StdRng> = Mutex::new(new_rng());
+}
+
+#[cfg(feature = "os")]
+fn new_rng() -> StdRng {
+    StdRng::from_entropy()
+}
+#[cfg(not(feature = "os"))]
+fn new_rng() -> StdRng {
+    // Without the operating system there is no source of entropy, so the sequence is the same until random_seed() is called
+    StdRng::seed_from_u64(0)
+}
 
 fn random_choose(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
     // Choose a random element of array returns array of number Literals
     let source = arguments.get(0).unwrap();
     let num_of_elements = arguments.get(1).unwrap();
     if source.literal_type != ast::LiteralType::Array {
-        tok.print_custom_error(&format!("random_choose() expects 1st argument of type Array, but received {:?} instead", source.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_choose() expects 1st argument of type Array, but received {:?} instead", source.literal_type));
     }
     else {
         // Do some integer checks
         if num_of_elements.literal_type != ast::LiteralType::Number {
-            tok.print_custom_error(&format!("{:?} is not a valid value for random_choose(). Only positive integers are allowed", num_of_elements.literal_type));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{:?} is not a valid value for random_choose(). Only positive integers are allowed", num_of_elements.literal_type));
         }
-        let num_float = Expr::string_to_float(&num_of_elements);
+        let num_float = Expr::to_float(&num_of_elements);
         if num_float.fract() != 0.0 {
-            tok.print_custom_error(&format!("{} is a float and is not a valid value for random_choose(). Only positive integers are allowed", num_float));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is a float and is not a valid value for random_choose(). Only positive integers are allowed", num_float));
         }
         let num_integer = num_float as i32;
         if num_integer < 0 {
-            tok.print_custom_error(&format!("{} is negative and is not a valid value for random_choose(). Only positive integers are allowed", num_integer));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is negative and is not a valid value for random_choose(). Only positive integers are allowed", num_integer));
         }
         let num_integer = num_integer as usize;
         let source_array = &source.array_values;
         // Generate random array
-        let mut rng = thread_rng();
+        let mut rng = RNG.lock().unwrap();
         let uniform = Uniform::from(0..source_array.len());
-        let result_array = (0..num_integer).map(|_| source_array[uniform.sample(&mut rng)].clone()).collect::<Vec<ast::Literal>>();
+        let result_array = (0..num_integer).map(|_| source_array[uniform.sample(&mut *rng)].clone()).collect::<Vec<ast::Literal>>();
         return ast::Literal::new_array(result_array);
     }
     ast::Literal::none()
@@ -1003,178 +2997,1677 @@ fn random_normal(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Liter
     let std_dev = arguments.get(1).unwrap();
     let num_of_elements = arguments.get(2).unwrap();
     if mean.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("random_normal() expects 1st argument of type Number, but received {:?} instead", mean.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_normal() expects 1st argument of type Number, but received {:?} instead", mean.literal_type));
     }
     if std_dev.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("random_normal() expects 2nd argument of type Number, but received {:?} instead", std_dev.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_normal() expects 2nd argument of type Number, but received {:?} instead", std_dev.literal_type));
     }
     else {
         // Do some integer checks
         if num_of_elements.literal_type != ast::LiteralType::Number {
-            tok.print_custom_error(&format!("{:?} is not a valid value for random_uniform(). Only positive integers are allowed", num_of_elements.literal_type));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{:?} is not a valid value for random_uniform(). Only positive integers are allowed", num_of_elements.literal_type));
         }
-        let num_float = Expr::string_to_float(&num_of_elements);
+        let num_float = Expr::to_float(&num_of_elements);
         if num_float.fract() != 0.0 {
-            tok.print_custom_error(&format!("{} is a float and is not a valid value for random_uniform(). Only positive integers are allowed", num_float));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is a float and is not a valid value for random_uniform(). Only positive integers are allowed", num_float));
         }
         let num_integer = num_float as i32;
         if num_integer < 0 {
-            tok.print_custom_error(&format!("{} is negative and is not a valid value for random_uniform(). Only positive integers are allowed", num_integer));
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is negative and is not a valid value for random_uniform(). Only positive integers are allowed", num_integer));
         }
         let num_integer = num_integer as usize;
-        let mean_float = Expr::string_to_float(&mean);
-        let std_float = Expr::string_to_float(&std_dev);
+        let mean_float = Expr::to_float(&mean);
+        let std_float = Expr::to_float(&std_dev);
     
         // Generate random array
-        let mut rng = thread_rng();
+        let mut rng = RNG.lock().unwrap();
         let normal = Normal::new(mean_float, std_float).unwrap();
-        let result_array = (0..num_integer).map(|_| ast::Literal::number(normal.sample(&mut rng).to_string())).collect::<Vec<ast::Literal>>();
+        let result_array = (0..num_integer).map(|_| ast::Literal::number(normal.sample(&mut *rng))).collect::<Vec<ast::Literal>>();
         return ast::Literal::new_array(result_array);
     }
     ast::Literal::none()
 }
 
-// File operations
-use std::fs;
-
-fn read_file(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
-    // Returns string Literal if success, null Literal if fail
-    let filepath = arguments.get(0).unwrap();
-    if filepath.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("read_file() expects one argument of type String, but received {:?} instead", filepath.literal_type));
+fn random_uniform(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array of number Literals from low (inclusive) to high (exclusive)
+    let low = arguments.get(0).unwrap();
+    let high = arguments.get(1).unwrap();
+    if low.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_uniform() expects 1st argument (low) of type Number, but received {:?} instead", low.literal_type));
     }
-    let result = match fs::read_to_string(filepath.value.clone()) {
-        Ok(content) => ast::Literal::string(content),
-        Err(_) => {
-            //tok.print_custom_error(&format!("read_file() failed to read file: {}", filepath.value));
-            //panic!();
-            ast::Literal::null()
-        }
-    };
-    return result;
-}
-
-fn write_file(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
-    // Returns 1 if success, 0 if fail
-    let filepath = arguments.get(0).unwrap();
-    let data = arguments.get(1).unwrap();
-    if filepath.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("write_file() expects 1st argument (filepath) of type String, but received {:?} instead", filepath.literal_type));
+    if high.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_uniform() expects 2nd argument (high) of type Number, but received {:?} instead", high.literal_type));
     }
-    if filepath.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("write_file() expects 2nd argument (data) of type String, but received {:?} instead", data.literal_type));
+    let num_integer = literal_to_index(arguments.get(2).unwrap(), "number of elements", "random_uniform", tok);
+    let low_float = Expr::to_float(&low);
+    let high_float = Expr::to_float(&high);
+    if low_float >= high_float {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_uniform() expects low ({}) to be less than high ({})", low_float, high_float));
     }
-    let result = match fs::write(filepath.value.clone(), &data.value) {
-        Ok(_) => {
-            1
-        },
-        Err(_) => {
-            //tok.print_custom_error(&format!("write_file() failed to write to file: {}", filepath.value));
-            //panic!();
-            0
+    let mut rng = RNG.lock().unwrap();
+    let uniform = Uniform::new(low_float, high_float);
+    let result_array = (0..num_integer).map(|_| ast::Literal::number(uniform.sample(&mut *rng))).collect::<Vec<ast::Literal>>();
+    ast::Literal::new_array(result_array)
+}
+fn random_int(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array of integer number Literals from low to high, both inclusive like range()
+    let low = arguments.get(0).unwrap();
+    let high = arguments.get(1).unwrap();
+    for (literal, description) in vec![(low, "1st argument (low)"), (high, "2nd argument (high)")] {
+        if literal.literal_type != ast::LiteralType::Number {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_int() expects {} of type Number, but received {:?} instead", description, literal.literal_type));
         }
-    };
-    ast::Literal::number(result.to_string())
+        if Expr::to_float(&literal).fract() != 0.0 {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_int() expects {} to be an integer, but received {} instead", description, literal.value));
+        }
+    }
+    let num_integer = literal_to_index(arguments.get(2).unwrap(), "number of elements", "random_int", tok);
+    let low_integer = Expr::to_float(&low) as i64;
+    let high_integer = Expr::to_float(&high) as i64;
+    if low_integer > high_integer {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_int() expects low ({}) to be less than or equal to high ({})", low_integer, high_integer));
+    }
+    let mut rng = RNG.lock().unwrap();
+    let uniform = Uniform::from(low_integer..=high_integer);
+    let result_array = (0..num_integer).map(|_| ast::Literal::number(uniform.sample(&mut *rng))).collect::<Vec<ast::Literal>>();
+    ast::Literal::new_array(result_array)
 }
-
-// Web
-fn serve_static_folder(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
-    use rocket::config::{Config, Environment};
-    use rocket_contrib::serve::StaticFiles;
-    // Returns string Literal if success, null Literal if fail
-    let folderpath = arguments.get(0).unwrap();
-    let address = arguments.get(1).unwrap();
-    let port = arguments.get(2).unwrap();
-    if folderpath.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("serve_static_folder() expects 1st argument (folder_path) of type String, but received {:?} instead", folderpath.literal_type));
+fn shuffle(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns a shuffled copy of the array
+    let source = arguments.get(0).unwrap();
+    if source.literal_type != ast::LiteralType::Array {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("shuffle() expects one argument of type Array, but received {:?} instead", source.literal_type));
     }
-    if address.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("serve_static_folder() expects 2nd argument (address) of type String, but received {:?} instead", address.literal_type));
+    let mut result_array = source.array_values.to_vec();
+    result_array.shuffle(&mut *RNG.lock().unwrap());
+    ast::Literal::new_array(result_array)
+}
+fn random_seed(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Reseeds the generator used by every random function
+    let seed = arguments.get(0).unwrap();
+    if seed.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_seed() expects one argument (seed) of type Number, but received {:?} instead", seed.literal_type));
     }
-    if port.literal_type != ast::LiteralType::Number {
-        tok.print_custom_error(&format!("serve_static_folder() expects 3rd argument (port) of type Number, but received {:?} instead", port.literal_type));
+    let seed_float = Expr::to_float(&seed);
+    if seed_float.fract() != 0.0 {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("random_seed() expects an integer seed, but received {} instead", seed_float));
     }
-    // Do some integer checks
-    let port_float = Expr::string_to_float(&port);
-    if port_float.fract() != 0.0 {
-        tok.print_custom_error(&format!("{} is a float and is not a valid port for serve_static_folder(). Only positive integers are allowed", port_float));
+    *RNG.lock().unwrap() = StdRng::seed_from_u64(seed_float as i64 as u64);
+    ast::Literal::none()
+}
+
+// Time
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    static ref CLOCK_START: Instant = Instant::now();
+}
+
+fn sleep(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let milliseconds = arguments.get(0).unwrap();
+    if milliseconds.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("sleep() expects one argument (milliseconds) of type Number, but received {:?} instead", milliseconds.literal_type));
     }
-    let port_integer = port_float as i32;
-    if port_integer < 0 {
-        tok.print_custom_error(&format!("{} is negative and is not a valid port for serve_static_folder(). Only positive integers are allowed", port_float));
+    let milliseconds_float = Expr::to_float(&milliseconds);
+    if milliseconds_float < 0.0 {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("sleep() expects a non-negative duration, but received {} instead", milliseconds_float));
     }
-    let port_integer = port_integer as u16;
-    let config = match Config::build(Environment::Staging)
-                .address(&address.value)
-                .port(port_integer)
-                .finalize() {
-                    Ok(result) => result,
-                    Err(_) => {
-                        tok.print_custom_error(&format!("Either address or port of serve_static_folder() is invalid"));
-                        panic!();
-                    }
-                };
-                        
-    let error = rocket::custom(config).mount("/", StaticFiles::from(&folderpath.value)).launch();
-    println!("Launch failed! Error: {}", error);
+    std::thread::sleep(Duration::from_secs_f64(milliseconds_float / 1000.0));
     ast::Literal::none()
 }
+fn clock(_arguments: Vec<ast::Literal>, _tok: &token::Token) -> ast::Literal {
+    // Returns milliseconds elapsed on a monotonic clock, measured from the first call to clock()
+    let elapsed = CLOCK_START.elapsed().as_secs_f64() * 1000.0;
+    ast::Literal::number(elapsed)
+}
 
-fn web_get(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
-    // Returns string Literal if success, null Literal if fail
-    let url = arguments.get(0).unwrap();
-    if url.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("web_get() expects one argument (url) of type String, but received {:?} instead", url.literal_type));
+// Threads
+lazy_static! {
+    // Handle of a thread is its index, None once it has been joined
+    static ref THREADS: Mutex<Vec<Option<std::thread::JoinHandle<ast::Literal>>>> = Mutex::new(Vec::new());
+}
+
+fn spawn(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Runs a function with no arguments on a new thread
+    // Returns number Literal (thread handle)
+    let source = arguments.get(0).unwrap();
+    if source.literal_type != ast::LiteralType::Function {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("spawn() expects one argument (function) of type Function, but received {:?} instead", source.literal_type));
     }
-    let result = match reqwest::blocking::get(&url.value) {
-        Ok(content) => ast::Literal::string(content.text().unwrap()),
-        Err(_) => {
-            //tok.print_custom_error(&format!("web_get() failed to GET url: {}", url.value));
-            //panic!();
-            ast::Literal::null()
+    let function = source.function.as_ref().unwrap().clone();
+    if !function.accepts(0) {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("spawn() expects a function with 0 arguments, but received one with {} arguments instead", function.arg_length()));
+    }
+    let thread_tok = tok.clone();
+    let handle = limits::spawn_with_stack(limits::THREAD_STACK_SIZE, move || {
+        let result = function.call(Vec::new(), &thread_tok).unwrap_or_else(ast::Literal::none);
+        ENV.lock().unwrap().remove_stack();
+        result
+    });
+    let mut threads = THREADS.lock().unwrap();
+    threads.push(Some(handle));
+    ast::Literal::number(threads.len() - 1)
+}
+
+fn join_thread(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Blocks until the thread finishes and returns the function's result
+    let index = literal_to_index(arguments.get(0).unwrap(), "thread handle", "join_thread", tok);
+    let handle = match THREADS.lock().unwrap().get_mut(index) {
+        Some(handle) => handle.take(),
+        None => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is not a valid thread handle for join_thread()", index));
+            panic!();
         }
     };
-    return result;
+    match handle {
+        Some(handle) => handle.join().unwrap_or_else(|_| ast::Literal::null()),
+        None => {
+            tok.print_custom_error(ErrorClass::Runtime, &format!("Thread {} has already been joined", index));
+            panic!();
+        }
+    }
 }
 
-use std::collections::HashMap;
+use std::sync::mpsc;
 
-fn web_post(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
-    // Returns string Literal if success, null Literal if fail
-    let url = arguments.get(0).unwrap();
-    let params = arguments.get(1).unwrap();
+struct Channel {
+    sender: Mutex<mpsc::Sender<ast::Literal>>,
+    receiver: Mutex<mpsc::Receiver<ast::Literal>>, // Any thread may receive, one at a time
+}
 
+lazy_static! {
+    // Handle of a channel is its index
+    static ref CHANNELS: Mutex<Vec<std::sync::Arc<Channel>>> = Mutex::new(Vec::new());
+}
+
+fn literal_to_channel(handle: &ast::Literal, function_name: &str, tok: &token::Token) -> std::sync::Arc<Channel> {
+    let index = literal_to_index(handle, "channel handle", function_name, tok);
+    match CHANNELS.lock().unwrap().get(index) {
+        Some(channel) => channel.clone(),
+        None => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is not a valid channel handle for {}()", index, function_name));
+            panic!();
+        }
+    }
+}
+
+fn channel(_arguments: Vec<ast::Literal>, _tok: &token::Token) -> ast::Literal {
+    // Returns number Literal (channel handle)
+    let (sender, receiver) = mpsc::channel();
+    let mut channels = CHANNELS.lock().unwrap();
+    channels.push(std::sync::Arc::new(Channel {
+        sender: Mutex::new(sender),
+        receiver: Mutex::new(receiver),
+    }));
+    ast::Literal::number(channels.len() - 1)
+}
+
+fn send(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let channel = literal_to_channel(arguments.get(0).unwrap(), "send", tok);
+    let value = arguments.get(1).unwrap().clone();
+    // The receiver lives as long as the channel, so sending cannot fail
+    let _ = channel.sender.lock().unwrap().send(value);
+    ast::Literal::none()
+}
+
+fn receive(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Blocks until a value is sent
+    let channel = literal_to_channel(arguments.get(0).unwrap(), "receive", tok);
+    let value = channel.receiver.lock().unwrap().recv();
+    value.unwrap_or_else(|_| ast::Literal::null())
+}
+
+// Timers
+use std::sync::atomic::{AtomicBool, Ordering};
+
+struct Timer {
+    cancelled: std::sync::Arc<AtomicBool>,
+    finished: std::sync::Arc<AtomicBool>,
+}
+
+lazy_static! {
+    // Handle of a timer is its index
+    static ref TIMERS: Mutex<Vec<Timer>> = Mutex::new(Vec::new());
+}
+
+fn start_timer(arguments: Vec<ast::Literal>, repeat: bool, function_name: &str, tok: &token::Token) -> ast::Literal {
+    // Calls the function with no arguments on its own thread after the delay, and again every delay if repeat is true
+    // Returns number Literal (timer handle)
+    let source = arguments.get(0).unwrap();
+    if source.literal_type != ast::LiteralType::Function {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 1st argument (function) of type Function, but received {:?} instead", function_name, source.literal_type));
+    }
+    let function = source.function.as_ref().unwrap().clone();
+    if !function.accepts(0) {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects a function with 0 arguments, but received one with {} arguments instead", function_name, function.arg_length()));
+    }
+    let milliseconds = literal_to_index(arguments.get(1).unwrap(), "delay in milliseconds", function_name, tok);
+    let cancelled = std::sync::Arc::new(AtomicBool::new(false));
+    let finished = std::sync::Arc::new(AtomicBool::new(false));
+    let (thread_cancelled, thread_finished) = (cancelled.clone(), finished.clone());
+    let thread_tok = tok.clone();
+    limits::spawn_with_stack(limits::THREAD_STACK_SIZE, move || {
+        loop {
+            std::thread::sleep(Duration::from_millis(milliseconds as u64));
+            if thread_cancelled.load(Ordering::SeqCst) {
+                break;
+            }
+            function.call(Vec::new(), &thread_tok);
+            if !repeat {
+                break;
+            }
+        }
+        ENV.lock().unwrap().remove_stack();
+        thread_finished.store(true, Ordering::SeqCst);
+    });
+    let mut timers = TIMERS.lock().unwrap();
+    timers.push(Timer { cancelled, finished });
+    ast::Literal::number(timers.len() - 1)
+}
+
+fn set_timeout(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    start_timer(arguments, false, "set_timeout", tok)
+}
+
+fn set_interval(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    start_timer(arguments, true, "set_interval", tok)
+}
+
+fn timer_active(timer: &Timer) -> bool {
+    !timer.cancelled.load(Ordering::SeqCst) && !timer.finished.load(Ordering::SeqCst)
+}
+
+fn clear_timer(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns true if the timer was still active
+    let index = literal_to_index(arguments.get(0).unwrap(), "timer handle", "clear_timer", tok);
+    let timers = TIMERS.lock().unwrap();
+    match timers.get(index) {
+        Some(timer) => {
+            let active = timer_active(timer);
+            timer.cancelled.store(true, Ordering::SeqCst);
+            ast::Literal::bool(active)
+        },
+        None => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is not a valid timer handle for clear_timer()", index));
+            panic!();
+        }
+    }
+}
+
+pub fn wait_for_timers() {
+    // Intervals keep the script running until they are cleared
+    while TIMERS.lock().unwrap().iter().any(timer_active) {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+// Environment variables
+#[cfg(feature = "os")]
+fn env_get(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns string Literal if set, null Literal if not set
+    let name = arguments.get(0).unwrap();
+    if name.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("env_get() expects one argument (name) of type String, but received {:?} instead", name.literal_type));
+    }
+    match std::env::var(&name.value) {
+        Ok(value) => ast::Literal::string(value),
+        Err(_) => ast::Literal::null()
+    }
+}
+#[cfg(feature = "os")]
+fn env_set(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Sets the variable for this process and any process started by it
+    let name = arguments.get(0).unwrap();
+    let value = arguments.get(1).unwrap();
+    if name.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("env_set() expects 1st argument (name) of type String, but received {:?} instead", name.literal_type));
+    }
+    if value.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("env_set() expects 2nd argument (value) of type String, but received {:?} instead", value.literal_type));
+    }
+    if name.value.is_empty() || name.value.contains('=') || name.value.contains('\0') {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("'{}' is not a valid environment variable name for env_set()", name.value));
+    }
+    if value.value.contains('\0') {
+        tok.print_custom_error(ErrorClass::InvalidArgument, "env_set() cannot set a value containing a null character");
+    }
+    std::env::set_var(&name.value, &value.value);
+    ast::Literal::none()
+}
+
+// Shell commands
+#[cfg(feature = "os")]
+use std::process::Command;
+
+#[cfg(feature = "os")]
+fn shell_command(command: &ast::Literal, function_name: &str, tok: &token::Token) -> Command {
+    if command.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects one argument (command) of type String, but received {:?} instead", function_name, command.literal_type));
+    }
+    let mut shell = if cfg!(target_os = "windows") {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    }
+    else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(&command.value);
+    shell
+}
+#[cfg(feature = "os")]
+fn exec(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Runs the command in the system shell with the same stdin/stdout/stderr
+    // Returns exit code as number Literal if success, null Literal if the command could not be started
+    let command = arguments.get(0).unwrap();
+    match shell_command(command, "exec", tok).status() {
+        Ok(status) => ast::Literal::number(status.code().unwrap_or(-1)),
+        Err(_) => ast::Literal::null()
+    }
+}
+#[cfg(feature = "os")]
+fn exec_capture(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array of Literals [stdout, stderr, exit code] with a number as the exit code if success, null Literal if the command could not be started
+    let command = arguments.get(0).unwrap();
+    match shell_command(command, "exec_capture", tok).output() {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let code = output.status.code().unwrap_or(-1);
+            ast::Literal::new_array(vec![ast::Literal::string(stdout), ast::Literal::string(stderr), ast::Literal::number(code)])
+        },
+        Err(_) => ast::Literal::null()
+    }
+}
+
+// File operations
+#[cfg(feature = "os")]
+use std::fs;
+
+fn read_file(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns string Literal if success, null Literal if fail
+    let filepath = arguments.get(0).unwrap();
+    if filepath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("read_file() expects one argument of type String, but received {:?} instead", filepath.literal_type));
+    }
+    let result = match ari_errors::read_file(&filepath.value) {
+        Ok(content) => ast::Literal::string(content),
+        Err(_) => {
+            //tok.print_custom_error(ErrorClass::InvalidArgument, &format!("read_file() failed to read file: {}", filepath.value));
+            //panic!();
+            ast::Literal::null()
+        }
+    };
+    return result;
+}
+
+fn write_file(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns 1 if success, 0 if fail
+    let filepath = arguments.get(0).unwrap();
+    let data = arguments.get(1).unwrap();
+    if filepath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("write_file() expects 1st argument (filepath) of type String, but received {:?} instead", filepath.literal_type));
+    }
+    if filepath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("write_file() expects 2nd argument (data) of type String, but received {:?} instead", data.literal_type));
+    }
+    let result = match ari_errors::write_file(&filepath.value, &data.value) {
+        Ok(_) => {
+            1
+        },
+        Err(_) => {
+            //tok.print_custom_error(ErrorClass::InvalidArgument, &format!("write_file() failed to write to file: {}", filepath.value));
+            //panic!();
+            0
+        }
+    };
+    ast::Literal::number(result)
+}
+
+#[cfg(feature = "os")]
+fn read_bytes(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns byte array Literal if success, null Literal if fail
+    let filepath = arguments.get(0).unwrap();
+    if filepath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("read_bytes() expects one argument of type String, but received {:?} instead", filepath.literal_type));
+    }
+    match fs::read(&filepath.value) {
+        Ok(content) => bytes_to_literal(&content),
+        Err(_) => ast::Literal::null()
+    }
+}
+
+#[cfg(feature = "os")]
+fn write_bytes(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns 1 if success, 0 if fail
+    let filepath = arguments.get(0).unwrap();
+    if filepath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("write_bytes() expects 1st argument (filepath) of type String, but received {:?} instead", filepath.literal_type));
+    }
+    let bytes = literal_to_bytes(arguments.get(1).unwrap(), "write_bytes", tok);
+    let result = match fs::write(&filepath.value, bytes) {
+        Ok(_) => 1,
+        Err(_) => 0
+    };
+    ast::Literal::number(result)
+}
+
+#[cfg(feature = "os")]
+fn append_file(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns 1 if success, 0 if fail
+    use std::io::Write;
+    let filepath = arguments.get(0).unwrap();
+    let data = arguments.get(1).unwrap();
+    if filepath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("append_file() expects 1st argument (filepath) of type String, but received {:?} instead", filepath.literal_type));
+    }
+    if data.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("append_file() expects 2nd argument (data) of type String, but received {:?} instead", data.literal_type));
+    }
+    let result = match fs::OpenOptions::new().create(true).append(true).open(&filepath.value) {
+        Ok(mut file) => {
+            match file.write_all(data.value.as_bytes()) {
+                Ok(_) => 1,
+                Err(_) => 0
+            }
+        },
+        Err(_) => 0
+    };
+    ast::Literal::number(result)
+}
+
+#[cfg(feature = "os")]
+fn file_exists(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns true for both files and folders
+    let filepath = arguments.get(0).unwrap();
+    if filepath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("file_exists() expects one argument of type String, but received {:?} instead", filepath.literal_type));
+    }
+    ast::Literal::bool(std::path::Path::new(&filepath.value).exists())
+}
+
+#[cfg(feature = "os")]
+fn delete_file(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns 1 if success, 0 if fail
+    let filepath = arguments.get(0).unwrap();
+    if filepath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("delete_file() expects one argument of type String, but received {:?} instead", filepath.literal_type));
+    }
+    let result = match fs::remove_file(&filepath.value) {
+        Ok(_) => 1,
+        Err(_) => 0
+    };
+    ast::Literal::number(result)
+}
+
+#[cfg(feature = "os")]
+fn copy_file(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns 1 if success, 0 if fail
+    let source = arguments.get(0).unwrap();
+    let destination = arguments.get(1).unwrap();
+    if source.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("copy_file() expects 1st argument (source) of type String, but received {:?} instead", source.literal_type));
+    }
+    if destination.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("copy_file() expects 2nd argument (destination) of type String, but received {:?} instead", destination.literal_type));
+    }
+    let result = match fs::copy(&source.value, &destination.value) {
+        Ok(_) => 1,
+        Err(_) => 0
+    };
+    ast::Literal::number(result)
+}
+
+#[cfg(feature = "os")]
+fn list_dir(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array of string Literals (file and folder names, sorted) if success, null Literal if fail
+    let folderpath = arguments.get(0).unwrap();
+    if folderpath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("list_dir() expects one argument of type String, but received {:?} instead", folderpath.literal_type));
+    }
+    let entries = match fs::read_dir(&folderpath.value) {
+        Ok(entries) => entries,
+        Err(_) => {
+            return ast::Literal::null();
+        }
+    };
+    let mut names = entries.filter_map(|entry| entry.ok())
+                            .map(|entry| entry.file_name().to_string_lossy().to_string())
+                            .collect::<Vec<String>>();
+    names.sort();
+    ast::Literal::new_array(names.into_iter().map(ast::Literal::string).collect())
+}
+
+#[cfg(feature = "os")]
+fn make_dir(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Creates any missing parent folders too
+    // Returns 1 if success, 0 if fail
+    let folderpath = arguments.get(0).unwrap();
+    if folderpath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("make_dir() expects one argument of type String, but received {:?} instead", folderpath.literal_type));
+    }
+    let result = match fs::create_dir_all(&folderpath.value) {
+        Ok(_) => 1,
+        Err(_) => 0
+    };
+    ast::Literal::number(result)
+}
+
+// Key-value store
+// A store is a JSON object file mapping keys to values
+#[cfg(any(feature = "os", feature = "plugins"))]
+fn literal_to_json(literal: &ast::Literal, tok: &token::Token) -> serde_json::Value {
+    match literal.literal_type {
+        ast::LiteralType::Number => {
+            match literal.number {
+                ast::Number::Integer(integer) => serde_json::Value::from(integer),
+                ast::Number::Float(float) => match serde_json::Number::from_f64(float) {
+                    Some(number) => serde_json::Value::Number(number),
+                    None => serde_json::Value::Null
+                }
+            }
+        },
+        ast::LiteralType::String => serde_json::Value::String(literal.value.clone()),
+        ast::LiteralType::Bool => serde_json::Value::Bool(string_to_bool(&literal.value)),
+        ast::LiteralType::Null => serde_json::Value::Null,
+        ast::LiteralType::Array => serde_json::Value::Array(literal.array_values.iter().map(|value| literal_to_json(value, tok)).collect()),
+        _ => {
+            tok.print_custom_error(ErrorClass::Runtime, &format!("{:?} cannot be converted to JSON", literal.literal_type));
+            panic!();
+        }
+    }
+}
+#[cfg(any(feature = "os", feature = "plugins"))]
+fn json_to_literal(value: &serde_json::Value) -> ast::Literal {
+    match value {
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(integer) => ast::Literal::number(integer),
+            None => ast::Literal::number(number.as_f64().unwrap_or(0.0))
+        },
+        serde_json::Value::String(text) => ast::Literal::string(text.clone()),
+        serde_json::Value::Bool(boolean) => ast::Literal::bool(*boolean),
+        serde_json::Value::Array(values) => ast::Literal::new_array(values.iter().map(json_to_literal).collect()),
+        // Nested objects are not written by store_set()
+        serde_json::Value::Null | serde_json::Value::Object(_) => ast::Literal::null(),
+    }
+}
+#[cfg(feature = "os")]
+fn read_store(path: &str) -> serde_json::Map<String, serde_json::Value> {
+    // A missing or unreadable store is treated as empty
+    match fs::read_to_string(path).ok().and_then(|content| serde_json::from_str(&content).ok()) {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new()
+    }
+}
+#[cfg(feature = "os")]
+fn check_store_arguments(path: &ast::Literal, key: &ast::Literal, function_name: &str, tok: &token::Token) {
+    check_path_argument(path, "1st argument (path)", function_name, tok);
+    if key.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 2nd argument (key) of type String, but received {:?} instead", function_name, key.literal_type));
+    }
+}
+#[cfg(feature = "os")]
+fn store_set(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns 1 if success, 0 if fail
+    let path = arguments.get(0).unwrap();
+    let key = arguments.get(1).unwrap();
+    let value = arguments.get(2).unwrap();
+    check_store_arguments(path, key, "store_set", tok);
+    let mut store = read_store(&path.value);
+    store.insert(key.value.clone(), literal_to_json(value, tok));
+    let content = serde_json::to_string_pretty(&serde_json::Value::Object(store)).unwrap();
+    let result = match fs::write(&path.value, content) {
+        Ok(_) => 1,
+        Err(_) => 0
+    };
+    ast::Literal::number(result)
+}
+#[cfg(feature = "os")]
+fn store_get(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns null Literal if the key does not exist
+    let path = arguments.get(0).unwrap();
+    let key = arguments.get(1).unwrap();
+    check_store_arguments(path, key, "store_get", tok);
+    match read_store(&path.value).get(&key.value) {
+        Some(value) => json_to_literal(value),
+        None => ast::Literal::null()
+    }
+}
+
+// Archives
+use std::io::{Read, Write};
+
+fn gzip_compress(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns byte array Literal
+    let text = arguments.get(0).unwrap();
+    if text.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("gzip_compress() expects one argument (text) of type String, but received {:?} instead", text.literal_type));
+    }
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    // Writing to a Vec cannot fail
+    encoder.write_all(text.value.as_bytes()).unwrap();
+    bytes_to_literal(&encoder.finish().unwrap())
+}
+fn gzip_decompress(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns string Literal if success, null Literal if the bytes are not valid gzip or not valid UTF-8
+    let bytes = literal_to_bytes(arguments.get(0).unwrap(), "gzip_decompress", tok);
+    let mut decoder = flate2::read::GzDecoder::new(&bytes[..]);
+    let mut text = String::new();
+    match decoder.read_to_string(&mut text) {
+        Ok(_) => ast::Literal::string(text),
+        Err(_) => ast::Literal::null()
+    }
+}
+#[cfg(feature = "os")]
+fn add_to_zip(writer: &mut zip::ZipWriter<fs::File>, path: &Path, name: &str) -> zip::result::ZipResult<()> {
+    // Folders are added recursively, entries are named relative to the folder's parent
+    let options = zip::write::FileOptions::default();
+    if path.is_dir() {
+        writer.add_directory(name, options)?;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let child_name = format!("{}/{}", name, entry.file_name().to_string_lossy());
+            add_to_zip(writer, &entry.path(), &child_name)?;
+        }
+    }
+    else {
+        writer.start_file(name, options)?;
+        writer.write_all(&fs::read(path)?)?;
+    }
+    Ok(())
+}
+#[cfg(feature = "os")]
+fn zip_create(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // zip_create(path, [file or folder paths])
+    // Returns 1 if success, 0 if fail
+    let path = arguments.get(0).unwrap();
+    let files = arguments.get(1).unwrap();
+    check_path_argument(path, "1st argument (path)", "zip_create", tok);
+    check_array_argument(files, "2nd argument (files)", "zip_create", tok);
+    for file in files.array_values.iter() {
+        check_path_argument(file, "2nd argument (files) to contain paths", "zip_create", tok);
+    }
+    let result = fs::File::create(&path.value).map_err(zip::result::ZipError::from).and_then(|file| {
+        let mut writer = zip::ZipWriter::new(file);
+        for file in files.array_values.iter() {
+            let file_path = Path::new(&file.value);
+            let name = match file_path.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => file.value.clone()
+            };
+            add_to_zip(&mut writer, file_path, &name)?;
+        }
+        writer.finish()?;
+        Ok(())
+    });
+    let result = match result {
+        Ok(_) => 1,
+        Err(_) => 0
+    };
+    ast::Literal::number(result)
+}
+#[cfg(feature = "os")]
+fn zip_extract(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Entries which would be written outside the destination folder are skipped
+    // Returns 1 if success, 0 if fail
+    let path = arguments.get(0).unwrap();
+    let destination = arguments.get(1).unwrap();
+    check_path_argument(path, "1st argument (path)", "zip_extract", tok);
+    check_path_argument(destination, "2nd argument (destination)", "zip_extract", tok);
+    let result = fs::File::open(&path.value)
+                    .map_err(zip::result::ZipError::from)
+                    .and_then(zip::ZipArchive::new)
+                    .and_then(|mut archive| archive.extract(&destination.value));
+    let result = match result {
+        Ok(_) => 1,
+        Err(_) => 0
+    };
+    ast::Literal::number(result)
+}
+
+// Path operations
+use std::path::{Path, PathBuf};
+
+fn check_path_argument(path: &ast::Literal, description: &str, function_name: &str, tok: &token::Token) {
+    if path.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects {} of type String, but received {:?} instead", function_name, description, path.literal_type));
+    }
+}
+fn optional_path_string(value: Option<&std::ffi::OsStr>) -> ast::Literal {
+    // Returns string Literal if the component exists, null Literal if not
+    match value {
+        Some(component) => ast::Literal::string(component.to_string_lossy().to_string()),
+        None => ast::Literal::null()
+    }
+}
+fn path_join(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Uses the separator of the current platform
+    let base = arguments.get(0).unwrap();
+    let child = arguments.get(1).unwrap();
+    check_path_argument(base, "1st argument (base)", "path_join", tok);
+    check_path_argument(child, "2nd argument (child)", "path_join", tok);
+    let mut path = PathBuf::from(&base.value);
+    path.push(&child.value);
+    ast::Literal::string(path.to_string_lossy().to_string())
+}
+fn basename(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Final component of the path, such as 'file.txt' in 'folder/file.txt'
+    let path = arguments.get(0).unwrap();
+    check_path_argument(path, "one argument", "basename", tok);
+    optional_path_string(Path::new(&path.value).file_name())
+}
+fn dirname(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Path without its final component, such as 'folder' in 'folder/file.txt'
+    let path = arguments.get(0).unwrap();
+    check_path_argument(path, "one argument", "dirname", tok);
+    optional_path_string(Path::new(&path.value).parent().map(|parent| parent.as_os_str()))
+}
+fn extension(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Extension without the dot, such as 'txt' in 'folder/file.txt'
+    let path = arguments.get(0).unwrap();
+    check_path_argument(path, "one argument", "extension", tok);
+    optional_path_string(Path::new(&path.value).extension())
+}
+
+// Web
+#[cfg(feature = "network")]
+fn server_config(address: &ast::Literal, port: &ast::Literal, function_name: &str, tok: &token::Token) -> rocket::Config {
+    use rocket::config::{Config, Environment};
+    if address.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 2nd argument (address) of type String, but received {:?} instead", function_name, address.literal_type));
+    }
+    if port.literal_type != ast::LiteralType::Number {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 3rd argument (port) of type Number, but received {:?} instead", function_name, port.literal_type));
+    }
+    // Do some integer checks
+    let port_float = Expr::to_float(&port);
+    if port_float.fract() != 0.0 {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is a float and is not a valid port for {}(). Only positive integers are allowed", port_float, function_name));
+    }
+    let port_integer = port_float as i32;
+    if port_integer < 0 {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is negative and is not a valid port for {}(). Only positive integers are allowed", port_float, function_name));
+    }
+    let port_integer = port_integer as u16;
+    match Config::build(Environment::Staging)
+                .address(&address.value)
+                .port(port_integer)
+                .finalize() {
+                    Ok(result) => result,
+                    Err(_) => {
+                        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("Either address or port of {}() is invalid", function_name));
+                        panic!();
+                    }
+                }
+}
+
+#[cfg(feature = "network")]
+fn serve_static_folder(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    use rocket_contrib::serve::StaticFiles;
+    // Returns number Literal (server handle) if success, null Literal if fail
+    let folderpath = arguments.get(0).unwrap();
+    let address = arguments.get(1).unwrap();
+    let port = arguments.get(2).unwrap();
+    if folderpath.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("serve_static_folder() expects 1st argument (folder_path) of type String, but received {:?} instead", folderpath.literal_type));
+    }
+    let config = server_config(address, port, "serve_static_folder", tok);
+    start_server(rocket::custom(config).mount("/", StaticFiles::from(&folderpath.value)), "serve_static_folder", tok)
+}
+
+#[cfg(feature = "network")]
+#[derive(Clone)]
+struct RouteHandler {
+    handler: Function,
+    param_segments: Vec<(usize, String)>, // (segment index, parameter name)
+    tok: token::Token,
+}
+
+#[cfg(feature = "network")]
+impl rocket::handler::Handler for RouteHandler {
+    fn handle<'r>(&self, request: &'r rocket::Request, data: rocket::Data) -> rocket::handler::Outcome<'r> {
+        use std::io::Read;
+        use rocket::http::Status;
+        use rocket::response::status::Custom;
+        // Request object: [method, path, params, query, body]
+        //      params => flat String array of path parameters [name1, value1, ...]
+        //      query  => flat String array of query parameters [name1, value1, ...]
+        let mut params = Vec::<ast::Literal>::new();
+        for (index, name) in &self.param_segments {
+            let value = match request.get_param::<String>(*index) {
+                Some(Ok(value)) => value,
+                _ => String::new()
+            };
+            params.push(ast::Literal::string(name.clone()));
+            params.push(ast::Literal::string(value));
+        }
+        let mut query = Vec::<ast::Literal>::new();
+        for pair in request.uri().query().unwrap_or("").split('&').filter(|pair| pair.len() > 0) {
+            let mut parts = pair.splitn(2, '=');
+            for part in [parts.next(), Some(parts.next().unwrap_or(""))].iter() {
+                let decoded = percent_decode_str(&part.unwrap().replace('+', " ")).decode_utf8_lossy().into_owned();
+                query.push(ast::Literal::string(decoded));
+            }
+        }
+        let mut body = String::new();
+        let _ = data.open().read_to_string(&mut body);
+        let request_literal = ast::Literal::new_array(vec![
+            ast::Literal::string(request.method().as_str().to_string()),
+            ast::Literal::string(request.uri().path().to_string()),
+            ast::Literal::new_array(params),
+            ast::Literal::new_array(query),
+            ast::Literal::string(body),
+        ]);
+
+        let result = self.handler.call(vec![request_literal], &self.tok).unwrap_or_else(ast::Literal::none);
+        // Handlers return either the body String, or [status, body] created by response()
+        let (status, body) = match result.literal_type {
+            ast::LiteralType::String => (200, result.value),
+            ast::LiteralType::Array if result.array_values.len() == 2
+                && result.array_values[0].literal_type == ast::LiteralType::Number
+                && result.array_values[1].literal_type == ast::LiteralType::String => {
+                (Expr::to_float(&result.array_values[0]) as u16, result.array_values[1].value.clone())
+            },
+            _ => {
+                self.tok.print_custom_error(ErrorClass::InvalidArgument, &format!("serve_routes() expects route handlers to return String or response(status, body), but received {:?} instead", result.literal_type));
+                panic!();
+            }
+        };
+        rocket::handler::Outcome::from(request, Custom(Status::raw(status), body))
+    }
+}
+
+#[cfg(feature = "network")]
+fn route(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array Literal [method, path, handler] for serve_routes()
+    let method = arguments.get(0).unwrap();
+    let path = arguments.get(1).unwrap();
+    let handler = arguments.get(2).unwrap();
+    if method.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("route() expects 1st argument (method) of type String, but received {:?} instead", method.literal_type));
+    }
+    if path.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("route() expects 2nd argument (path) of type String, but received {:?} instead", path.literal_type));
+    }
+    if handler.literal_type != ast::LiteralType::Function {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("route() expects 3rd argument (handler) of type Function, but received {:?} instead", handler.literal_type));
+    }
+    if !handler.function.as_ref().unwrap().accepts(1) {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("route() expects a handler with 1 argument (request), but received one with {} arguments instead", handler.function.as_ref().unwrap().arg_length()));
+    }
+    if method.value.to_uppercase().parse::<rocket::http::Method>().is_err() {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("'{}' is not a valid HTTP method for route()", method.value));
+    }
+    ast::Literal::new_array(arguments)
+}
+
+#[cfg(feature = "network")]
+fn response(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array Literal [status, body] for route handlers
+    let status = arguments.get(0).unwrap();
+    let body = arguments.get(1).unwrap();
+    let status_code = literal_to_index(status, "status", "response", tok);
+    if status_code < 100 || status_code > 999 {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is not a valid status for response(). Only 100 to 999 are allowed", status_code));
+    }
+    if body.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("response() expects 2nd argument (body) of type String, but received {:?} instead", body.literal_type));
+    }
+    ast::Literal::new_array(arguments)
+}
+
+#[cfg(feature = "network")]
+fn literal_to_routes(routes: &ast::Literal, tok: &token::Token) -> Vec<rocket::Route> {
+    check_array_argument(routes, "1st argument (routes)", "serve_routes", tok);
+    let mut result = Vec::<rocket::Route>::new();
+    for (index, entry) in routes.array_values.iter().enumerate() {
+        let valid = entry.literal_type == ast::LiteralType::Array && entry.array_values.len() == 3
+                    && entry.array_values[2].literal_type == ast::LiteralType::Function;
+        if !valid {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("serve_routes() expects every route to be created by route(), but route {} is not", index));
+        }
+        let method = entry.array_values[0].value.to_uppercase().parse::<rocket::http::Method>().unwrap();
+        let path = entry.array_values[1].value.clone();
+        // Dynamic segments look like /users/<id>
+        let param_segments = path.split('/')
+                            .filter(|segment| segment.len() > 0)
+                            .enumerate()
+                            .filter(|(_, segment)| segment.starts_with('<') && segment.ends_with('>'))
+                            .map(|(index, segment)| (index, segment.trim_matches(|c| c == '<' || c == '>' || c == '.').to_string()))
+                            .collect();
+        let handler = RouteHandler {
+            handler: entry.array_values[2].function.as_ref().unwrap().clone(),
+            param_segments,
+            tok: tok.clone(),
+        };
+        if rocket::http::uri::Origin::parse_route(&path).is_err() {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("'{}' is not a valid route path for serve_routes()", path));
+        }
+        result.push(rocket::Route::new(method, path, handler));
+    }
+    result
+}
+
+#[cfg(feature = "network")]
+fn serve_routes(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // serve_routes([route("GET", "/hello/<name>", handler), ...], address, port)
+    // Returns number Literal (server handle) if success, null Literal if fail
+    let routes = literal_to_routes(arguments.get(0).unwrap(), tok);
+    let config = server_config(arguments.get(1).unwrap(), arguments.get(2).unwrap(), "serve_routes", tok);
+    start_server(rocket::custom(config).mount("/", routes), "serve_routes", tok)
+}
+
+// Servers
+// Rocket 0.4 cannot be shut down once launched, so servers are run directly on hyper with a listener that can be closed
+#[cfg(feature = "network")]
+use rocket::http::hyper::net::{HttpListener, HttpStream, NetworkListener};
+use std::sync::Arc;
+
+#[cfg(feature = "network")]
+#[derive(Clone)]
+struct StoppableListener {
+    listener: Arc<Mutex<Option<HttpListener>>>, // Shared by every worker thread, None once stopped
+    stopped: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "network")]
+impl NetworkListener for StoppableListener {
+    type Stream = HttpStream;
+    fn accept(&mut self) -> Result<HttpStream, rocket::http::hyper::Error> {
+        let mut guard = self.listener.lock().unwrap();
+        if let Some(listener) = guard.as_mut() {
+            let stream = listener.accept();
+            if !self.stopped.load(Ordering::SeqCst) {
+                return stream;
+            }
+            // Woken up by server_stop(), closing the socket frees the port
+            *guard = None;
+        }
+        std::mem::drop(guard);
+        // hyper respawns worker threads which return, so stopped workers sleep forever instead
+        loop {
+            std::thread::park();
+        }
+    }
+    fn local_addr(&mut self) -> std::io::Result<std::net::SocketAddr> {
+        match self.listener.lock().unwrap().as_mut() {
+            Some(listener) => listener.local_addr(),
+            None => Err(std::io::Error::new(std::io::ErrorKind::NotConnected, "server stopped"))
+        }
+    }
+}
+
+#[cfg(feature = "network")]
+struct Server {
+    address: std::net::SocketAddr,
+    stopped: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "network")]
+lazy_static! {
+    // Handle of a server is its index
+    static ref SERVERS: Mutex<Vec<Server>> = Mutex::new(Vec::new());
+}
+
+#[cfg(feature = "network")]
+fn start_server(rocket: rocket::Rocket, function_name: &str, tok: &token::Token) -> ast::Literal {
+    let config = rocket.config();
+    let full_address = format!("{}:{}", config.address, config.port);
+    let workers = config.workers as usize;
+    let mut listener = match HttpListener::new(full_address.as_str()) {
+        Ok(listener) => listener,
+        Err(_) => {
+            //tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() failed to bind to {}", function_name, full_address));
+            return ast::Literal::null();
+        }
+    };
+    let address = match listener.local_addr() {
+        Ok(address) => address,
+        Err(_) => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() could not determine the address of the server", function_name));
+            panic!();
+        }
+    };
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stoppable = StoppableListener {
+        listener: Arc::new(Mutex::new(Some(listener))),
+        stopped: stopped.clone(),
+    };
+    let mut server = rocket::http::hyper::Server::new(stoppable);
+    // No keep-alive, so that stopped servers do not keep serving open connections
+    server.keep_alive(None);
+    match server.handle_threads(rocket, workers) {
+        // The listening guard joins the acceptor threads when dropped, which would never return
+        Ok(listening) => std::mem::forget(listening),
+        Err(_) => return ast::Literal::null()
+    }
+    let mut servers = SERVERS.lock().unwrap();
+    servers.push(Server { address, stopped });
+    ast::Literal::number(servers.len() - 1)
+}
+
+#[cfg(feature = "network")]
+fn literal_to_server(handle: &ast::Literal, function_name: &str, tok: &token::Token) -> usize {
+    let index = literal_to_index(handle, "server handle", function_name, tok);
+    if index >= SERVERS.lock().unwrap().len() {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{} is not a valid server handle for {}()", index, function_name));
+    }
+    index
+}
+
+#[cfg(feature = "network")]
+fn server_stop(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns true if the server was running, false if it had already been stopped
+    let index = literal_to_server(arguments.get(0).unwrap(), "server_stop", tok);
+    let (address, stopped) = {
+        let servers = SERVERS.lock().unwrap();
+        let server = servers.get(index).unwrap();
+        (server.address, server.stopped.clone())
+    };
+    if stopped.swap(true, Ordering::SeqCst) {
+        return ast::Literal::bool(false);
+    }
+    // Wake up the worker blocked in accept()
+    let _ = std::net::TcpStream::connect(address);
+    ast::Literal::bool(true)
+}
+
+#[cfg(feature = "network")]
+fn server_running(index: usize) -> bool {
+    !SERVERS.lock().unwrap().get(index).unwrap().stopped.load(Ordering::SeqCst)
+}
+
+#[cfg(feature = "network")]
+fn server_wait(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Blocks until the server is stopped, e.g. by one of its route handlers
+    let index = literal_to_server(arguments.get(0).unwrap(), "server_wait", tok);
+    while server_running(index) {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    ast::Literal::none()
+}
+
+#[cfg(feature = "network")]
+pub fn wait_for_servers() {
+    let count = SERVERS.lock().unwrap().len();
+    for index in 0..count {
+        while server_running(index) {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+#[cfg(feature = "network")]
+fn web_get(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns string Literal if success, null Literal if fail
+    let url = arguments.get(0).unwrap();
+    if url.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_get() expects one argument (url) of type String, but received {:?} instead", url.literal_type));
+    }
+    let result = match reqwest::blocking::get(&url.value).and_then(|response| response.text()) {
+        Ok(content) => ast::Literal::string(content),
+        Err(_) => {
+            //tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_get() failed to GET url: {}", url.value));
+            //panic!();
+            ast::Literal::null()
+        }
+    };
+    return result;
+}
+
+#[cfg(feature = "network")]
+fn web_get_bytes(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns byte array Literal if success, null Literal if fail
+    let url = arguments.get(0).unwrap();
+    if url.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_get_bytes() expects one argument (url) of type String, but received {:?} instead", url.literal_type));
+    }
+    match reqwest::blocking::get(&url.value).and_then(|response| response.bytes()) {
+        Ok(content) => bytes_to_literal(&content),
+        Err(_) => ast::Literal::null()
+    }
+}
+
+#[cfg(feature = "network")]
+fn response_to_literal(response: reqwest::blocking::Response) -> ast::Literal {
+    // [status code, headers, body] where headers is a flat String array [name1, value1, name2, value2, ...]
+    let status = ast::Literal::number(response.status().as_u16());
+    let mut headers = Vec::<ast::Literal>::new();
+    for (name, value) in response.headers() {
+        headers.push(ast::Literal::string(name.as_str().to_string()));
+        headers.push(ast::Literal::string(String::from_utf8_lossy(value.as_bytes()).to_string()));
+    }
+    let body = match response.text() {
+        Ok(text) => ast::Literal::string(text),
+        Err(_) => ast::Literal::null()
+    };
+    ast::Literal::new_array(vec![status, ast::Literal::new_array(headers), body])
+}
+
+#[cfg(feature = "network")]
+fn web_get_full(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns array Literal [status, headers, body] for any response including 4xx/5xx, null Literal if the request could not be made
+    let url = arguments.get(0).unwrap();
     if url.literal_type != ast::LiteralType::String {
-        tok.print_custom_error(&format!("web_post() expects 1st argument (url) of type String, but received {:?} instead", url.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_get_full() expects one argument (url) of type String, but received {:?} instead", url.literal_type));
     }
+    match reqwest::blocking::get(&url.value).map(response_to_literal) {
+        Ok(literal) => literal,
+        Err(_) => ast::Literal::null()
+    }
+}
+
+#[cfg(any(feature = "network", feature = "plugins"))]
+use std::collections::HashMap;
+
+fn literal_to_pairs(params: &ast::Literal, description: &str, function_name: &str, tok: &token::Token) -> Vec<(String, String)> {
+    // Parameters are given as a flat String array: [key1, value1, key2, value2, ...]
     if params.literal_type != ast::LiteralType::Array {
-        tok.print_custom_error(&format!("web_post() expects 2nd argument (parameters) of type Array, but received {:?} instead", params.literal_type));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects {} of type Array, but received {:?} instead", function_name, description, params.literal_type));
     }
     let original_array = &params.array_values;
     let length = original_array.len();
     if (length % 2) != 0 {
-        tok.print_custom_error(&format!("web_post() expects 2nd argument (parameters) to have even length, but received length {:?} instead", length));
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects {} to have even length, but received length {:?} instead", function_name, description, length));
     }
-    if original_array.len() > 0 {
-        let array_type = original_array.get(0).unwrap().literal_type;
-        if array_type != ast::LiteralType::String {
-            tok.print_custom_error(&format!("web_post() expects 2nd argument (parameters) of type Array to have String elements, but received {:?} elements instead", array_type));
-        }
+    if let Some(value) = original_array.iter().find(|value| value.literal_type != ast::LiteralType::String) {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects {} of type Array to have String elements, but received {:?} elements instead", function_name, description, value.literal_type));
+    }
+    original_array.chunks(2).map(|pair| (pair[0].value.clone(), pair[1].value.clone())).collect()
+}
+
+#[cfg(feature = "network")]
+fn send_params(method: reqwest::Method, arguments: &[ast::Literal], function_name: &str, form: bool, tok: &token::Token) -> ast::Literal {
+    // Sends the parameters as JSON, or form-encoded if form is true
+    // Returns string Literal if success, null Literal if fail
+    let url = arguments.get(0).unwrap();
+    let params = arguments.get(1).unwrap();
+
+    if url.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() expects 1st argument (url) of type String, but received {:?} instead", function_name, url.literal_type));
     }
     let mut map = HashMap::new();
-    let mut index = 0;
-    while index < length {
-        map.insert(original_array.get(index).unwrap().value.clone(), original_array.get(index + 1).unwrap().value.clone());
-        index += 2;
+    for (key, value) in literal_to_pairs(params, "2nd argument (parameters)", function_name, tok) {
+        map.insert(key, value);
     }
     let client = reqwest::blocking::Client::new();
-    let result = match client.post(&url.value).json(&map).send() {
-        Ok(content) => ast::Literal::string(content.text().unwrap()),
+    let request = client.request(method, &url.value);
+    let request = if form { request.form(&map) } else { request.json(&map) };
+    let result = match request.send().and_then(|response| response.text()) {
+        Ok(content) => ast::Literal::string(content),
         Err(_) => {
-            //tok.print_custom_error(&format!("web_post() failed to POST url: {}", url.value));
+            //tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}() failed to send to url: {}", function_name, url.value));
             //panic!();
             ast::Literal::null()
         }
     };
     return result;
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "network")]
+fn web_post(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    send_params(reqwest::Method::POST, &arguments, "web_post", false, tok)
+}
+#[cfg(feature = "network")]
+fn web_post_form(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    send_params(reqwest::Method::POST, &arguments, "web_post_form", true, tok)
+}
+#[cfg(feature = "network")]
+fn web_put(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    send_params(reqwest::Method::PUT, &arguments, "web_put", false, tok)
+}
+#[cfg(feature = "network")]
+fn web_patch(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    send_params(reqwest::Method::PATCH, &arguments, "web_patch", false, tok)
+}
+
+#[cfg(feature = "network")]
+fn web_delete(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns string Literal if success, null Literal if fail
+    let url = arguments.get(0).unwrap();
+    if url.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_delete() expects one argument (url) of type String, but received {:?} instead", url.literal_type));
+    }
+    let client = reqwest::blocking::Client::new();
+    match client.delete(&url.value).send().and_then(|response| response.text()) {
+        Ok(content) => ast::Literal::string(content),
+        Err(_) => ast::Literal::null()
+    }
+}
+
+#[cfg(feature = "network")]
+fn web_send_raw(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // web_send_raw(method, url, body, content_type)
+    // Returns string Literal if success, null Literal if fail
+    let method = arguments.get(0).unwrap();
+    let url = arguments.get(1).unwrap();
+    let body = arguments.get(2).unwrap();
+    let content_type = arguments.get(3).unwrap();
+    for (index, (literal, name)) in [(method, "method"), (url, "url"), (body, "body"), (content_type, "content_type")].iter().enumerate() {
+        if literal.literal_type != ast::LiteralType::String {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_send_raw() expects argument {} ({}) of type String, but received {:?} instead", index + 1, name, literal.literal_type));
+        }
+    }
+    let http_method = match reqwest::Method::from_bytes(method.value.to_uppercase().as_bytes()) {
+        Ok(result) => result,
+        Err(_) => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("'{}' is not a valid HTTP method for web_send_raw()", method.value));
+            panic!();
+        }
+    };
+    let client = reqwest::blocking::Client::new();
+    let request = client.request(http_method, &url.value)
+                .header(reqwest::header::CONTENT_TYPE, content_type.value.as_str())
+                .body(body.value.clone());
+    match request.send().and_then(|response| response.text()) {
+        Ok(content) => ast::Literal::string(content),
+        Err(_) => ast::Literal::null()
+    }
+}
+
+#[cfg(feature = "network")]
+fn web_request(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // web_request(method, url, body, headers, timeout, auth)
+    //      body    => raw String body, "" for none
+    //      headers => flat String array [name1, value1, name2, value2, ...]
+    //      timeout => milliseconds, 0 for none
+    //      auth    => [] for none, ["bearer", token] or ["basic", username, password]
+    // Returns array Literal [status, headers, body] like web_get_full(), null Literal if the request failed or timed out
+    let method = arguments.get(0).unwrap();
+    let url = arguments.get(1).unwrap();
+    let body = arguments.get(2).unwrap();
+    let timeout = arguments.get(4).unwrap();
+    let auth = arguments.get(5).unwrap();
+    if method.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_request() expects 1st argument (method) of type String, but received {:?} instead", method.literal_type));
+    }
+    if url.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_request() expects 2nd argument (url) of type String, but received {:?} instead", url.literal_type));
+    }
+    if body.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_request() expects 3rd argument (body) of type String, but received {:?} instead", body.literal_type));
+    }
+    let headers = literal_to_pairs(arguments.get(3).unwrap(), "4th argument (headers)", "web_request", tok);
+    let timeout_ms = literal_to_index(timeout, "timeout", "web_request", tok);
+    check_array_argument(auth, "6th argument (auth)", "web_request", tok);
+
+    let http_method = match reqwest::Method::from_bytes(method.value.to_uppercase().as_bytes()) {
+        Ok(result) => result,
+        Err(_) => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("'{}' is not a valid HTTP method for web_request()", method.value));
+            panic!();
+        }
+    };
+    let client = reqwest::blocking::Client::new();
+    let mut request = client.request(http_method, &url.value);
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value);
+    }
+    if body.value.len() > 0 {
+        request = request.body(body.value.clone());
+    }
+    if timeout_ms > 0 {
+        request = request.timeout(Duration::from_millis(timeout_ms as u64));
+    }
+    let auth_values: Vec<&str> = auth.array_values.iter().map(|value| value.value.as_str()).collect();
+    request = match auth_values.as_slice() {
+        [] => request,
+        ["bearer", token] => request.bearer_auth(token),
+        ["basic", username, password] => request.basic_auth(username, Some(password)),
+        _ => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("web_request() expects 6th argument (auth) to be [], [\"bearer\", token] or [\"basic\", username, password]"));
+            panic!();
+        }
+    };
+    match request.send().map(response_to_literal) {
+        Ok(literal) => literal,
+        Err(_) => ast::Literal::null()
+    }
+}
+
+#[cfg(feature = "network")]
+fn download_file(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Streams the response body straight into the file instead of buffering it in memory
+    // Returns number Literal of bytes written if success, null Literal if the request failed or the status is not 2xx
+    let url = arguments.get(0).unwrap();
+    let filepath = arguments.get(1).unwrap();
+    if url.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("download_file() expects 1st argument (url) of type String, but received {:?} instead", url.literal_type));
+    }
+    check_path_argument(filepath, "2nd argument (path)", "download_file", tok);
+    let mut response = match reqwest::blocking::get(&url.value) {
+        Ok(response) if response.status().is_success() => response,
+        _ => return ast::Literal::null()
+    };
+    let mut file = match fs::File::create(&filepath.value) {
+        Ok(file) => file,
+        Err(_) => return ast::Literal::null()
+    };
+    match response.copy_to(&mut file) {
+        Ok(bytes_written) => ast::Literal::number(bytes_written),
+        Err(_) => ast::Literal::null()
+    }
+}
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode, percent_decode_str};
+// Everything except the RFC 3986 unreserved characters is encoded
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+fn url_encode(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let text = arguments.get(0).unwrap();
+    if text.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("url_encode() expects one argument (text) of type String, but received {:?} instead", text.literal_type));
+    }
+    ast::Literal::string(utf8_percent_encode(&text.value, URL_ENCODE_SET).to_string())
+}
+
+fn url_decode(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns string Literal if success, null Literal if the decoded bytes are not valid UTF-8
+    let text = arguments.get(0).unwrap();
+    if text.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("url_decode() expects one argument (text) of type String, but received {:?} instead", text.literal_type));
+    }
+    match percent_decode_str(&text.value).decode_utf8() {
+        Ok(decoded) => ast::Literal::string(decoded.into_owned()),
+        Err(_) => ast::Literal::null()
+    }
+}
+
+fn build_query(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // ["q", "ari lang", "page", "2"] => "q=ari%20lang&page=2"
+    let pairs = literal_to_pairs(arguments.get(0).unwrap(), "one argument (parameters)", "build_query", tok);
+    let query: Vec<String> = pairs.iter()
+                        .map(|(key, value)| format!("{}={}", utf8_percent_encode(key, URL_ENCODE_SET), utf8_percent_encode(value, URL_ENCODE_SET)))
+                        .collect();
+    ast::Literal::string(query.join("&"))
+}
+
+// Host natives
+pub type HostFunction = Arc<dyn Fn(Vec<ast::Literal>) -> Result<ast::Literal, String> + Send + Sync>;
+
+struct HostNative {
+    name: String,
+    arity: usize,
+    function: HostFunction,
+}
+
+lazy_static! {
+    // Natives registered through Interpreter::register_native(), NativeType::Host holds the index
+    static ref HOST_NATIVES: Mutex<Vec<HostNative>> = Mutex::new(Vec::new());
+}
+
+pub fn register_host_native(name: &str, arity: usize, function: HostFunction) -> NativeType {
+    // Registering the same name again replaces the previous implementation
+    let mut host_natives = HOST_NATIVES.lock().unwrap();
+    let native = HostNative { name: name.to_owned(), arity, function };
+    match host_natives.iter().position(|n| n.name == name) {
+        Some(index) => {
+            host_natives[index] = native;
+            NativeType::Host(index)
+        },
+        None => {
+            host_natives.push(native);
+            NativeType::Host(host_natives.len() - 1)
+        }
+    }
+}
+
+pub fn host_natives() -> Vec<(String, NativeType)> {
+    // Used to define the host natives again whenever the globals are reset
+    HOST_NATIVES.lock().unwrap().iter().enumerate()
+        .map(|(index, native)| (native.name.to_owned(), NativeType::Host(index)))
+        .collect()
+}
+
+fn host_native_arity(index: usize) -> usize {
+    HOST_NATIVES.lock().unwrap()[index].arity
+}
+
+fn call_host_native(index: usize, arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    let (name, function) = {
+        // Unlock before calling, the host function may take a while
+        let host_natives = HOST_NATIVES.lock().unwrap();
+        (host_natives[index].name.to_owned(), host_natives[index].function.clone())
+    };
+    match function(arguments) {
+        Ok(literal) => literal,
+        Err(message) => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("{}(): {}", name, message));
+            ast::Literal::none()
+        }
+    }
+}
+
+// Plugins
+// A plugin is a shared library exporting two C functions:
+//      void ari_plugin_init(void (*register)(const char* name, size_t arity, char* (*function)(const char* arguments)))
+//      void ari_plugin_free(char* result)
+// ari_plugin_init() calls 'register' once for every native function it provides.
+// A native function receives its arguments as a JSON array and returns a JSON value, which is freed with ari_plugin_free().
+// Returning {"error": "message"} or NULL reports an error.
+#[cfg(feature = "plugins")]
+use std::ffi::{CStr, CString};
+#[cfg(feature = "plugins")]
+use std::os::raw::c_char;
+
+#[cfg(feature = "plugins")]
+type PluginFunction = extern "C" fn(*const c_char) -> *mut c_char;
+#[cfg(feature = "plugins")]
+type PluginFree = extern "C" fn(*mut c_char);
+#[cfg(feature = "plugins")]
+type PluginRegister = extern "C" fn(*const c_char, usize, PluginFunction);
+#[cfg(feature = "plugins")]
+type PluginInit = extern "C" fn(PluginRegister);
+
+#[cfg(feature = "plugins")]
+lazy_static! {
+    // Loaded libraries must outlive every native function they registered
+    static ref PLUGINS: Mutex<Vec<libloading::Library>> = Mutex::new(Vec::new());
+    // Filled by register_plugin_function() while ari_plugin_init() runs
+    static ref PLUGIN_FUNCTIONS: Mutex<Vec<(String, usize, PluginFunction)>> = Mutex::new(Vec::new());
+}
+
+#[cfg(feature = "plugins")]
+extern "C" fn register_plugin_function(name: *const c_char, arity: usize, function: PluginFunction) {
+    if name.is_null() {
+        return;
+    }
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned();
+    PLUGIN_FUNCTIONS.lock().unwrap().push((name, arity, function));
+}
+
+#[cfg(feature = "plugins")]
+fn contains_function(literal: &ast::Literal) -> bool {
+    literal.literal_type == ast::LiteralType::Function || literal.array_values.iter().any(contains_function)
+}
+
+#[cfg(feature = "plugins")]
+fn call_plugin_function(function: PluginFunction, free: PluginFree, arguments: Vec<ast::Literal>) -> Result<ast::Literal, String> {
+    if arguments.iter().any(contains_function) {
+        return Err("Function arguments cannot be passed to plugins".to_string());
+    }
+    let arguments_json = serde_json::Value::Array(arguments.iter().map(|argument| literal_to_json(argument, &token::Token::none())).collect());
+    let arguments_c = CString::new(arguments_json.to_string()).map_err(|_| "arguments contain a null character".to_string())?;
+    let result_c = function(arguments_c.as_ptr());
+    if result_c.is_null() {
+        return Err("failed without a message".to_string());
+    }
+    let result = unsafe { CStr::from_ptr(result_c) }.to_string_lossy().into_owned();
+    free(result_c);
+    let value: serde_json::Value = serde_json::from_str(&result).map_err(|_| format!("returned invalid JSON: {}", result))?;
+    if let Some(message) = value.get("error") {
+        return Err(message.as_str().map(|m| m.to_owned()).unwrap_or_else(|| message.to_string()));
+    }
+    Ok(json_to_literal(&value))
+}
+
+#[cfg(feature = "plugins")]
+fn load_plugin(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns 1 if success, 0 if the library cannot be loaded or is not an Ari plugin
+    let path = arguments.get(0).unwrap();
+    check_path_argument(path, "one argument (path)", "load_plugin", tok);
+    let library = match unsafe { libloading::Library::new(&path.value) } {
+        Ok(library) => library,
+        Err(_) => return ast::Literal::number(0)
+    };
+    let (init, free) = unsafe {
+        match (library.get::<PluginInit>(b"ari_plugin_init\0"), library.get::<PluginFree>(b"ari_plugin_free\0")) {
+            (Ok(init), Ok(free)) => (*init, *free),
+            _ => return ast::Literal::number(0)
+        }
+    };
+    PLUGIN_FUNCTIONS.lock().unwrap().clear();
+    init(register_plugin_function);
+    let registered = std::mem::take(&mut *PLUGIN_FUNCTIONS.lock().unwrap());
+    PLUGINS.lock().unwrap().push(library);
+
+    for (name, arity, function) in registered {
+        let native_type = register_host_native(&name, arity, Arc::new(move |arguments| call_plugin_function(function, free, arguments)));
+        ENV.lock().unwrap().define_global(name, ast::Literal::new_function(Function::new_native(native_type)));
+    }
+    ast::Literal::number(1)
+}
+
+// FFI
+// ffi_call("libm.so.6", "cos", ["double"], [0], "double")
+// Types: int (32-bit), long (64-bit), float, double, string (const char*) and void for the return type only
+#[cfg(feature = "plugins")]
+use libffi::middle::{Arg, Cif, CodePtr, Type};
+
+#[cfg(feature = "plugins")]
+lazy_static! {
+    // Libraries stay loaded so that repeated calls do not reload them
+    // Each is shared with the calls using it, so the lock is not held while foreign code runs
+    static ref FFI_LIBRARIES: Mutex<HashMap<String, std::sync::Arc<libloading::Library>>> = Mutex::new(HashMap::new());
+}
+
+#[cfg(feature = "plugins")]
+enum FfiValue {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(*const c_char),
+}
+
+#[cfg(feature = "plugins")]
+fn ffi_type(name: &str, is_return: bool, tok: &token::Token) -> Type {
+    match name {
+        "int" => Type::i32(),
+        "long" => Type::i64(),
+        "float" => Type::f32(),
+        "double" => Type::f64(),
+        "string" => Type::pointer(),
+        "void" if is_return => Type::void(),
+        _ => {
+            tok.print_custom_error(ErrorClass::InvalidArgument, &format!("ffi_call() does not support the type '{}'", name));
+            panic!();
+        }
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn ffi_value(type_name: &str, literal: &ast::Literal, strings: &mut Vec<CString>, tok: &token::Token) -> FfiValue {
+    let expected = if type_name == "string" { ast::LiteralType::String } else { ast::LiteralType::Number };
+    if literal.literal_type != expected {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("ffi_call() expects an argument of type {:?} for '{}', but received {:?} instead", expected, type_name, literal.literal_type));
+    }
+    let number = Expr::to_float(literal);
+    match type_name {
+        "int" => FfiValue::Int(number as i32),
+        "long" => FfiValue::Long(Expr::to_integer(literal).unwrap_or(number as i64)),
+        "float" => FfiValue::Float(number as f32),
+        "double" => FfiValue::Double(number),
+        _ => {
+            let string = match CString::new(literal.value.clone()) {
+                Ok(string) => string,
+                Err(_) => {
+                    tok.print_custom_error(ErrorClass::InvalidArgument, "ffi_call() cannot pass a string containing a null character");
+                    panic!();
+                }
+            };
+            // The CString's buffer does not move when the CString itself is moved into 'strings'
+            let pointer = string.as_ptr();
+            strings.push(string);
+            FfiValue::String(pointer)
+        }
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn ffi_call(arguments: Vec<ast::Literal>, tok: &token::Token) -> ast::Literal {
+    // Returns the result converted to a Literal, null if the library or symbol cannot be found
+    let library_path = arguments.get(0).unwrap();
+    let symbol = arguments.get(1).unwrap();
+    let arg_types = arguments.get(2).unwrap();
+    let args = arguments.get(3).unwrap();
+    let return_type = arguments.get(4).unwrap();
+    check_path_argument(library_path, "1st argument (library)", "ffi_call", tok);
+    if symbol.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("ffi_call() expects 2nd argument (symbol) of type String, but received {:?} instead", symbol.literal_type));
+    }
+    check_array_argument(arg_types, "3rd argument (arg_types)", "ffi_call", tok);
+    check_array_argument(args, "4th argument (args)", "ffi_call", tok);
+    if return_type.literal_type != ast::LiteralType::String {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("ffi_call() expects 5th argument (ret_type) of type String, but received {:?} instead", return_type.literal_type));
+    }
+    if arg_types.array_values.len() != args.array_values.len() {
+        tok.print_custom_error(ErrorClass::InvalidArgument, &format!("ffi_call() received {} argument types but {} arguments", arg_types.array_values.len(), args.array_values.len()));
+    }
+
+    let type_names: Vec<String> = arg_types.array_values.iter().map(|t| t.value.clone()).collect();
+    let cif = Cif::new(type_names.iter().map(|name| ffi_type(name, false, tok)), ffi_type(&return_type.value, true, tok));
+    let mut strings = Vec::<CString>::new();
+    let values: Vec<FfiValue> = type_names.iter().zip(args.array_values.iter())
+                                    .map(|(name, literal)| ffi_value(name, literal, &mut strings, tok))
+                                    .collect();
+    let ffi_args: Vec<Arg> = values.iter().map(|value| match value {
+        FfiValue::Int(v) => Arg::new(v),
+        FfiValue::Long(v) => Arg::new(v),
+        FfiValue::Float(v) => Arg::new(v),
+        FfiValue::Double(v) => Arg::new(v),
+        FfiValue::String(v) => Arg::new(v),
+    }).collect();
+
+    let library = {
+        let mut libraries = FFI_LIBRARIES.lock().unwrap();
+        match libraries.get(&library_path.value) {
+            Some(library) => library.clone(),
+            None => match unsafe { libloading::Library::new(&library_path.value) } {
+                Ok(library) => {
+                    let library = std::sync::Arc::new(library);
+                    libraries.insert(library_path.value.clone(), library.clone());
+                    library
+                },
+                Err(_) => return ast::Literal::null()
+            }
+        }
+    };
+    let code = match unsafe { library.get::<*const std::ffi::c_void>(symbol.value.as_bytes()) } {
+        Ok(address) => CodePtr::from_ptr(*address),
+        Err(_) => return ast::Literal::null()
+    };
+    unsafe {
+        match return_type.value.as_str() {
+            "int" => ast::Literal::number(cif.call::<i32>(code, &ffi_args)),
+            "long" => ast::Literal::number(cif.call::<i64>(code, &ffi_args)),
+            "float" => ast::Literal::number(cif.call::<f32>(code, &ffi_args)),
+            "double" => ast::Literal::number(cif.call::<f64>(code, &ffi_args)),
+            "string" => {
+                let result = cif.call::<*const c_char>(code, &ffi_args);
+                if result.is_null() {
+                    ast::Literal::null()
+                }
+                else {
+                    ast::Literal::string(CStr::from_ptr(result).to_string_lossy().into_owned())
+                }
+            },
+            _ => {
+                cif.call::<()>(code, &ffi_args);
+                ast::Literal::null()
+            }
+        }
+    }
+}
@@ -2,27 +2,381 @@
 
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "network")]
 extern crate rocket;
 
 
-use std::fs;
-use std::io;
-use std::io::Write;
+#[cfg(feature = "repl")]
+use rustyline::error::ReadlineError;
 mod token;
 mod ast;
 mod scanner;
 mod parser;
 mod environment;
 mod function;
+mod resolver;
+mod prelude;
+#[cfg(feature = "os")]
+mod cache;
+mod dump;
+mod fmt;
+mod lint;
+mod debugger;
+mod trace;
+mod profile;
+#[cfg(feature = "repl")]
+mod completion;
+mod limits;
+mod optimize;
 use ari_errors;
 use crate::ari_errors::SCRIPT;
 use crate::ari_errors::BORDER_LENGTH;
+pub use ast::{Literal, LiteralType};
+pub use ari_errors::{Error, Io, Color, ColorMode, ErrorFormat, set_io, set_color_mode, set_error_format, set_deny_warnings};
+pub use limits::{Limits, set_limits, spawn_with_stack, MAIN_STACK_SIZE};
+use std::sync::Once;
 
 pub fn get_version() -> String {
     return "Ari 0.1.0".to_owned();
 }
 
+/// Embeds Ari in another application.
+/// Every Interpreter shares the same global environment.
+pub struct Interpreter {}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        prelude::load_prelude();
+        Interpreter {}
+    }
+
+    /// Defines a global native function which is implemented in Rust.
+    /// The function receives exactly 'arity' arguments, and an Err message is reported as an Ari error.
+    pub fn register_native<F>(&mut self, name: &str, arity: usize, function: F)
+        where F: Fn(Vec<Literal>) -> Result<Literal, String> + Send + Sync + 'static {
+        let native_type = function::register_host_native(name, arity, std::sync::Arc::new(function));
+        let native = function::Function::new_native(native_type);
+        environment::ENV.lock().unwrap().define_global(name.to_owned(), Literal::new_function(native));
+    }
+
+    /// Replaces the limits on call depth, array length, memory and running time
+    pub fn set_limits(&mut self, limits: Limits) {
+        limits::set_limits(limits);
+    }
+
+    pub fn run(&mut self, source: &str) {
+        run(source, "", 1);
+    }
+}
+
+/// Runs Ari code and returns the value of the last statement.
+/// Errors are returned instead of being printed, and the process does not exit.
+pub fn eval_source(source: &str) -> Result<Literal, Error> {
+    catch_errors(|| evaluate(source, "", 1)).map(|literal| literal.unwrap_or_else(Literal::none))
+}
+
+fn catch_errors<T, F: FnOnce() -> T + std::panic::UnwindSafe>(f: F) -> Result<Option<T>, Error> {
+    // Runs in library mode, so that errors are returned instead of exiting
+    // Ok(None) if the code exited early, such as with 'bai'
+    let was_library = std::mem::replace(&mut *ari_errors::LIBRARY.lock().unwrap(), true);
+    silence_library_panics();
+    let result = std::panic::catch_unwind(f);
+    *ari_errors::LIBRARY.lock().unwrap() = was_library;
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(payload) => {
+            ari_errors::set_parsing(false);
+            // The environment lock may have been held when the error was raised
+            environment::ENV.clear_poison();
+            environment::ENV.lock().unwrap().remove_stack();
+            if payload.is::<ari_errors::Exit>() {
+                return Ok(None);
+            }
+            match payload.downcast::<Error>() {
+                Ok(error) => Err(*error),
+                Err(payload) => std::panic::resume_unwind(payload),
+            }
+        }
+    }
+}
+
+/// The script followed by every module it imports, directly or through other modules
+pub fn script_files(script_name: &str) -> Vec<String> {
+    let mut files = vec![script_name.to_owned()];
+    let mut index = 0;
+    while index < files.len() {
+        let contents = match ari_errors::read_file(&files[index]) {
+            Ok(content) => content,
+            Err(_) => String::new(),
+        };
+        index += 1;
+        // A file with syntax errors may still be imported once it is fixed, so its errors are ignored
+        let tokens = match catch_errors(|| { scanner::Scanner::new(&contents, &files[index - 1], 1).scan_tokens() }) {
+            Ok(Some(tokens)) => tokens,
+            _ => continue,
+        };
+        for pair in tokens.windows(2) {
+            if pair[0].token_type == token::TokenType::Import && pair[1].token_type == token::TokenType::String
+                && !files.iter().any(|file| **file == *pair[1].literal) {
+                files.push(pair[1].literal.to_string());
+            }
+        }
+    }
+    files
+}
+
+/// Prints the tokens from the scanner, or the statements from the parser with '--dump-ast'
+pub fn dump_script(script_name: &str, dump_ast: bool) {
+    ari_errors::set_script_name(script_name);
+    let contents = match ari_errors::read_file(script_name) {
+        Ok(content) => content,
+        Err(_) => {
+            ari_errors::print_file_not_found(script_name);
+            return;
+        }
+    };
+    if dump_ast {
+        // The parser checks variables against the environment, so the prelude must be defined
+        prelude::load_prelude();
+        print!("{}", dump::dump_statements(&parse(&contents, script_name, 1)));
+    }
+    else {
+        let mut scanner_struct = scanner::Scanner::new(&contents, script_name, 1);
+        print!("{}", dump::dump_tokens(&scanner_struct.scan_tokens()));
+    }
+}
+
+/// Rewrites a script with canonical formatting.
+/// With 'check', only reports whether the script is already formatted.
+/// Returns false if the script is not formatted when checking, and exits with a syntax error if it is invalid.
+pub fn format_script(script_name: &str, check: bool) -> bool {
+    ari_errors::set_script_name(script_name);
+    let contents = match ari_errors::read_file(script_name) {
+        Ok(content) => content,
+        Err(_) => {
+            ari_errors::print_file_not_found(script_name);
+            return false;
+        }
+    };
+    // The parser checks variables against the environment, so the prelude must be defined
+    prelude::load_prelude();
+    let formatted = catch_errors(|| {
+        let statements = parse(&contents, script_name, 1);
+        let formatted = fmt::Formatter::new(&contents).format(&statements);
+        // Formatting must never change what the script does
+        let unchanged = dump::dump_statements(&statements) == dump::dump_statements(&parse(&formatted, script_name, 1));
+        (formatted, unchanged)
+    });
+    let formatted = match formatted {
+        Ok(Some((formatted, true))) => formatted,
+        Ok(_) => {
+            eprintln!("{}: formatting would change the script, so it was left unchanged", script_name);
+            return false;
+        },
+        Err(error) => {
+            print_parse_error(script_name, &error);
+            ari_errors::exit_with_code(ari_errors::EXIT_SYNTAX_ERROR);
+            return false;
+        }
+    };
+    if formatted == contents {
+        return true;
+    }
+    if check {
+        println!("{} is not formatted", script_name);
+        return false;
+    }
+    match ari_errors::write_file(script_name, &formatted) {
+        Ok(_) => {
+            println!("Formatted {}", script_name);
+            true
+        },
+        Err(e) => {
+            eprintln!("Failed to write {}: {}", script_name, e);
+            false
+        }
+    }
+}
+
+/// Prints warnings from static checks over a script, except for the 'allowed' rules.
+/// Returns false if there are any warnings, and exits with a usage error for an unknown rule
+/// or a syntax error if the script is invalid.
+pub fn lint_script(script_name: &str, allowed: &[String]) -> bool {
+    for rule in allowed {
+        if !lint::RULES.contains(&rule.as_str()) {
+            eprintln!("Unknown lint rule {}. Available rules: {}", rule, lint::RULES.join(", "));
+            ari_errors::exit_with_code(ari_errors::EXIT_USAGE);
+            return false;
+        }
+    }
+    ari_errors::set_script_name(script_name);
+    let contents = match ari_errors::read_file(script_name) {
+        Ok(content) => content,
+        Err(_) => {
+            ari_errors::print_file_not_found(script_name);
+            return false;
+        }
+    };
+    // The parser checks variables against the environment, so the prelude must be defined
+    prelude::load_prelude();
+    let statements = match catch_errors(|| parse(&contents, script_name, 1)) {
+        Ok(Some(statements)) => statements,
+        Ok(None) => return false,
+        Err(error) => {
+            print_parse_error(script_name, &error);
+            ari_errors::exit_with_code(ari_errors::EXIT_SYNTAX_ERROR);
+            return false;
+        }
+    };
+    let warnings: Vec<lint::Warning> = lint::Linter::new(&contents).lint(&statements).into_iter()
+                                        .filter(|warning| !allowed.iter().any(|rule| rule == warning.rule))
+                                        .collect();
+    print_warnings(script_name, &contents, &warnings);
+    match warnings.len() {
+        0 => println!("{}: no warnings", script_name),
+        1 => println!("\n{}: 1 warning", script_name),
+        n => println!("\n{}: {} warnings", script_name, n),
+    }
+    warnings.is_empty()
+}
+
+fn print_warnings(script_name: &str, contents: &str, warnings: &[lint::Warning]) {
+    let source_lines: Vec<&str> = contents.lines().collect();
+    for warning in warnings {
+        let source = source_lines.get(warning.line_number.saturating_sub(1)).unwrap_or(&"");
+        ari_errors::print_warning(&format!("{} [{}]", warning.message, warning.rule), script_name, source, warning.index, warning.line_number);
+    }
+}
+
+/// Scans, parses and resolves a script without running it.
+/// Returns false and prints the error if the script is invalid.
+pub fn check_script(script_name: &str) -> bool {
+    ari_errors::set_script_name(script_name);
+    let contents = match ari_errors::read_file(script_name) {
+        Ok(content) => content,
+        Err(_) => {
+            ari_errors::print_file_not_found(script_name);
+            return false;
+        }
+    };
+    // The parser checks variables against the environment, so the prelude must be defined
+    prelude::load_prelude();
+    match catch_errors(|| { parse(&contents, script_name, 1); }) {
+        Ok(_) => {
+            println!("{}: OK", script_name);
+            true
+        },
+        Err(error) => {
+            print_parse_error(script_name, &error);
+            false
+        }
+    }
+}
+
+fn print_parse_error(script_name: &str, error: &Error) {
+    eprintln!("{}: {}", script_name, error);
+    if error.line_number != 0 {
+        eprintln!("    Line {} | {}", error.line_number, error.source);
+    }
+}
+
+/// Same as eval_source(), but everything written by print/println is returned instead of going to stdout.
+pub fn eval_source_with_output(source: &str) -> (Result<Literal, Error>, String) {
+    let previous = std::mem::replace(&mut *ari_errors::OUTPUT.lock().unwrap(), Some(String::new()));
+    let result = eval_source(source);
+    let output = std::mem::replace(&mut *ari_errors::OUTPUT.lock().unwrap(), previous).unwrap_or_default();
+    (result, output)
+}
+
+fn silence_library_panics() {
+    // Errors raised in library mode unwind as panics, which should not print the usual panic message
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let payload = info.payload();
+            if !payload.is::<Error>() && !payload.is::<ari_errors::Exit>() {
+                default_hook(info);
+            }
+        }));
+    });
+}
+
+// How a script is run
+enum RunMode {
+    Normal,
+    Debug, // Step debugger
+    Trace, // Log every statement and expression
+    Profile, // Report the time spent in functions and loops
+}
+
 pub fn run_script(script_name : &str){
+    run_script_file(script_name, RunMode::Normal);
+}
+
+pub fn debug_script(script_name : &str){
+    // Runs a script in the step debugger
+    run_script_file(script_name, RunMode::Debug);
+}
+
+pub fn trace_script(script_name : &str){
+    // Runs a script while logging each evaluation to stderr
+    run_script_file(script_name, RunMode::Trace);
+}
+
+pub fn profile_script(script_name : &str){
+    // Runs a script and reports the slowest functions and loops at exit
+    run_script_file(script_name, RunMode::Profile);
+}
+
+fn run_script_file(script_name : &str, mode : RunMode){
+    start_script(script_name);
+    ari_errors::set_script_name(script_name);
+    let contents = match ari_errors::read_file(script_name) {
+        Ok(content) => content,
+        Err(_) => {
+            ari_errors::print_file_not_found(script_name);
+            panic!();
+        }
+    };
+    run_script_contents(script_name, false, &contents, mode);
+}
+
+pub fn run_embedded_script(script_name : &str, contents: &str){
+    // Script which was built into the executable with 'ari build'
+    start_script(script_name);
+    ari_errors::set_script_name(script_name);
+    run_script_contents(script_name, true, contents, RunMode::Normal);
+}
+
+#[cfg(feature = "os")]
+pub fn compile_script(script_name : &str) -> bool {
+    // Writes the parsed script to a .aric cache for faster startup
+    // Returns false if the cache could not be written
+    ari_errors::set_script_name(script_name);
+    let contents = match ari_errors::read_file(script_name) {
+        Ok(content) => content,
+        Err(_) => {
+            ari_errors::print_file_not_found(script_name);
+            return false;
+        }
+    };
+    // The parser checks variables against the environment, so the prelude must be defined
+    prelude::load_prelude();
+    let statements = parse(&contents, script_name, 1);
+    match cache::save(script_name, &contents, statements) {
+        Ok(path) => {
+            println!("Compiled {} to {}", script_name, path);
+            true
+        },
+        Err(e) => {
+            eprintln!("Failed to compile {}: {}", script_name, e);
+            false
+        }
+    }
+}
+
+fn start_script(script_name : &str){
     {
         // Block statement to ensure mutex is unlocked
         let script_ref : &mut bool = &mut SCRIPT.lock().unwrap();
@@ -43,19 +397,46 @@ pub fn run_script(script_name : &str){
     ari_errors::print_white(&running, true, false);
     ari_errors::print_green(&lower, true, true);
     ari_errors::print_white("\n", false, false);
-    
-    let contents = match fs::read_to_string(script_name) {
-        Ok(content) => content,
-        Err(_) => {
-            ari_errors::print_red("Error: ", false, true);
-            ari_errors::print_white(&format!("{} does not exist.", script_name), false, true);
-            ari_errors::exit();
-            panic!();
-        }
+}
+
+fn run_script_contents(script_name : &str, embedded : bool, contents : &str, mode : RunMode){
+    // Scripts built into an executable with 'ari build' have no .aric cache, and their users cannot fix warnings
+    prelude::load_prelude();
+    // Started after the prelude, which should not be stepped through, traced or profiled
+    match mode {
+        RunMode::Debug => debugger::start(contents),
+        RunMode::Trace => trace::start(),
+        RunMode::Profile => profile::start(),
+        RunMode::Normal => {}
+    }
+    #[cfg(feature = "os")]
+    let cached = if embedded { None } else { cache::load(script_name, contents) };
+    #[cfg(not(feature = "os"))]
+    let cached = None;
+    let statements = match cached {
+        Some(statements) => statements,
+        None => parse(contents, script_name, 1)
+    };
+    if !embedded {
+        let warnings: Vec<lint::Warning> = lint::Linter::new(contents).lint(&statements).into_iter()
+                                            .filter(|warning| lint::RUN_RULES.contains(&warning.rule))
+                                            .collect();
+        print_warnings(script_name, contents, &warnings);
+        ari_errors::check_denied_warnings();
+    }
+    // Stepping and tracing show the code as it was written
+    let statements = match mode {
+        RunMode::Debug | RunMode::Trace => statements,
+        RunMode::Normal | RunMode::Profile => optimize(statements)
     };
-    run(&contents, 1);
+    evaluate_statements(statements);
+    // Keep running until every timer has finished and every server has been stopped
+    function::wait_for_timers();
+    #[cfg(feature = "network")]
+    function::wait_for_servers();
 }
 
+#[cfg(feature = "repl")]
 pub fn run_interpreter(){
     {
         // Block statement to ensure mutex is unlocked
@@ -74,30 +455,186 @@ pub fn run_interpreter(){
     ari_errors::print_white(&version, true, true);
     ari_errors::print_green(&lower, true, true);
     ari_errors::print_white("", false, false);
-    let mut line_number = 0;
+    prelude::load_prelude();
+    let mut editor = match rustyline::Editor::<completion::ReplHelper, rustyline::history::DefaultHistory>::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            println!("Error!\n{}", e);
+            return;
+        }
+    };
+    // Tab completes variable and native function names
+    editor.set_helper(Some(completion::ReplHelper {}));
+    let mut line_number = 1;
     loop{
-        line_number += 1;
-        print!("\n> ");
-        io::stdout().flush().unwrap();
-        let mut input_line = String::new();
-        match io::stdin().read_line(&mut input_line){
-            Ok(_)=>{
-                run(&input_line.trim(), line_number);
-            },
-            Err(e)=>{
-                println!("Error!\n{}", e)
+        println!();
+        // Keep reading lines until every block, bracket and string is closed
+        let mut input = String::new();
+        let mut prompt = "> ";
+        loop {
+            match editor.readline(prompt) {
+                Ok(input_line) => {
+                    input.push_str(&input_line);
+                    input.push('\n');
+                },
+                Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {
+                    return;
+                },
+                Err(e) => {
+                    println!("Error!\n{}", e);
+                    return;
+                }
+            }
+            if is_input_complete(&input) {
+                break;
             }
+            prompt = "... ";
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(input);
+        if input.starts_with(':') {
+            run_meta_command(input);
+            continue;
         }
+        run(input, "", line_number);
+        line_number += input.lines().count();
     }
 
 }
 
-pub fn run(input: &str, line_number: usize){
-    let mut scanner_struct = scanner::Scanner::new(input, line_number);
+#[cfg(feature = "repl")]
+fn run_meta_command(input: &str) {
+    // Interpreter commands which are handled before the input reaches the scanner
+    let mut parts = input.splitn(2, char::is_whitespace);
+    let command = parts.next().unwrap_or("");
+    let argument = parts.next().unwrap_or("").trim();
+    match command {
+        ":vars" => {
+            let variables = environment::ENV.lock().unwrap().global_variables();
+            if variables.is_empty() {
+                println!("No variables defined.");
+            }
+            for (name, literal) in variables {
+                println!("{}: {}", name, literal.describe());
+            }
+        },
+        ":clear" => {
+            environment::ENV.lock().unwrap().reset();
+            prelude::load_prelude();
+            println!("Cleared all variables.");
+        },
+        ":load" => {
+            if argument.is_empty() {
+                println!("Usage: :load [script_name]");
+                return;
+            }
+            match ari_errors::read_file(argument) {
+                Ok(contents) => run(&contents, argument, 1),
+                Err(_) => eprintln!("{} does not exist.", argument),
+            };
+        },
+        ":quit" | ":q" => {
+            ari_errors::exit();
+        },
+        ":help" => {
+            println!(":vars           List defined variables");
+            println!(":clear          Remove all defined variables");
+            println!(":load [script]  Run a script in the current session");
+            println!(":quit           Exit the interpreter");
+        },
+        _ => {
+            println!("Unknown command {}. Type :help for a list of commands.", command);
+        }
+    }
+}
+
+#[cfg(feature = "repl")]
+fn is_input_complete(input: &str) -> bool {
+    // Checks for unterminated blocks, brackets, parentheses and strings in interpreter input
+    let mut depth: i32 = 0;
+    let mut in_string = false;
+    let mut comment_depth = 0; // Block comments nest
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if comment_depth > 0 {
+            if c == '/' && chars.peek() == Some(&'*') {
+                chars.next();
+                comment_depth += 1;
+            }
+            else if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                comment_depth -= 1;
+            }
+            continue;
+        }
+        if in_string {
+            if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '/' => {
+                if chars.peek() == Some(&'/') {
+                    // Skip line comment
+                    while let Some(&next_c) = chars.peek() {
+                        if next_c == '\n' {
+                            break;
+                        }
+                        chars.next();
+                    }
+                }
+                else if chars.peek() == Some(&'*') {
+                    chars.next();
+                    comment_depth += 1;
+                }
+            },
+            _ => {}
+        }
+    }
+    // Unbalanced closing brackets are left for the parser to report
+    return !in_string && comment_depth == 0 && depth <= 0;
+}
+
+pub fn run(input: &str, file: &str, line_number: usize){
+    evaluate(input, file, line_number);
+}
+
+fn evaluate(input: &str, file: &str, line_number: usize) -> Literal {
+    evaluate_statements(parse(input, file, line_number))
+}
+
+fn parse(input: &str, file: &str, line_number: usize) -> Vec<Box<ast::Statement>> {
+    // Scans, parses and resolves. 'file' is shown in errors, and is empty for code typed into the interpreter.
+    let mut scanner_struct = scanner::Scanner::new(input, file, line_number);
     let tokens = scanner_struct.scan_tokens();
     let mut parser_struct = parser::Parser::new(tokens);
-    let statements = parser_struct.parse();
+    let mut statements = parser_struct.parse();
+    let mut resolver_struct = resolver::Resolver::new();
+    resolver_struct.resolve(&mut statements);
+    statements
+}
+
+fn optimize(statements: Vec<Box<ast::Statement>>) -> Vec<Box<ast::Statement>> {
+    // Resolved again, for the variables which hold hoisted calls
+    let mut statements = optimize::Optimizer::new(&statements).optimize(statements);
+    let mut resolver_struct = resolver::Resolver::new();
+    resolver_struct.resolve(&mut statements);
+    statements
+}
+
+fn evaluate_statements(statements: Vec<Box<ast::Statement>>) -> Literal {
+    // Returns the value of the last statement
+    limits::start_clock();
+    let mut result = Literal::none();
     for s in statements {
-        s.evaluate_statement();
+        result = s.evaluate_statement();
     }
+    result
 }
\ No newline at end of file
@@ -0,0 +1,183 @@
+use crate::ast;
+use crate::token;
+use ari_errors::ErrorClass;
+use std::cell::Cell;
+use std::mem;
+use std::sync::Mutex;
+use std::thread;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+///////////////////////////////////////////
+// Limits
+///////////////////////////////////////////
+// Stops runaway scripts with an Ari error instead of letting them abort or hang:
+//      Function::call() checks the call depth, the length and size of returned values and the timeout,
+//      operators which build strings and arrays check their size before allocating them,
+//      and every loop iteration checks the timeout.
+// The memory limit applies to each string or array on its own, estimated from its text and elements,
+// rather than to the whole process.
+// Deep recursion would otherwise overflow the Rust stack, so the call depth is limited by default.
+// The default is high enough for ordinary recursive code, and the stack checks below still stop
+// recursion which would need more stack than the thread has.
+// Scripts run on threads with a large stack, and the parser, resolver, linter and evaluator check
+// how much of it is left, so deeply nested code also stops with an error instead of aborting.
+
+/// Limits for running scripts, where None means unlimited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub max_call_depth: Option<usize>,
+    pub max_array_length: Option<usize>,
+    pub max_memory: Option<usize>, // Bytes of a single string or array, including nested arrays
+    pub timeout: Option<Duration>, // Wall-clock time for each run
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_call_depth: Some(DEFAULT_MAX_CALL_DEPTH),
+            max_array_length: None,
+            max_memory: None,
+            timeout: None,
+        }
+    }
+}
+
+const UNLIMITED: usize = usize::MAX;
+const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+static MAX_CALL_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CALL_DEPTH);
+static MAX_ARRAY_LENGTH: AtomicUsize = AtomicUsize::new(UNLIMITED);
+static MAX_MEMORY: AtomicUsize = AtomicUsize::new(UNLIMITED);
+static HAS_TIMEOUT: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref LIMITS: Mutex<Limits> = Mutex::new(Limits::default());
+    static ref DEADLINE: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+pub const MAIN_STACK_SIZE: usize = 512 * 1024 * 1024; // Virtual memory, only the used part is allocated
+pub const THREAD_STACK_SIZE: usize = 64 * 1024 * 1024; // For spawn() and timers
+const STACK_MARGIN: usize = 1024 * 1024; // Kept free for reporting the error
+const STACK_ERROR: &str = "Maximum recursion depth exceeded, as the code is nested too deeply or recurses without end";
+
+thread_local! {
+    static CALL_DEPTH: Cell<usize> = Cell::new(0); // User function calls on this thread which have not returned
+    static STACK_LIMIT: Cell<usize> = Cell::new(0); // Lowest usable stack address, 0 if the thread was not started by spawn_with_stack()
+}
+
+pub fn set_limits(limits: Limits) {
+    MAX_CALL_DEPTH.store(limits.max_call_depth.unwrap_or(UNLIMITED), Ordering::SeqCst);
+    MAX_ARRAY_LENGTH.store(limits.max_array_length.unwrap_or(UNLIMITED), Ordering::SeqCst);
+    MAX_MEMORY.store(limits.max_memory.unwrap_or(UNLIMITED), Ordering::SeqCst);
+    HAS_TIMEOUT.store(limits.timeout.is_some(), Ordering::SeqCst);
+    *LIMITS.lock().unwrap() = limits;
+}
+
+pub fn start_clock() {
+    // Called before each run, so the timeout applies to every script or interpreter input separately
+    *DEADLINE.lock().unwrap() = LIMITS.lock().unwrap().timeout.map(|timeout| Instant::now() + timeout);
+}
+
+pub struct CallGuard {}
+impl Drop for CallGuard {
+    // Also runs when an error unwinds the call
+    fn drop(&mut self) {
+        CALL_DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
+
+pub fn enter_call(tok: &token::Token) -> CallGuard {
+    let depth = CALL_DEPTH.with(|depth| {
+        depth.set(depth.get() + 1);
+        depth.get()
+    });
+    let guard = CallGuard {};
+    let max_call_depth = MAX_CALL_DEPTH.load(Ordering::Relaxed);
+    if depth > max_call_depth {
+        tok.print_custom_error(ErrorClass::CallDepth, &format!("Maximum call depth of {} exceeded, which may be caused by infinite recursion", max_call_depth));
+    }
+    guard
+}
+
+fn stack_address() -> usize {
+    // The stack grows downwards, so deeper calls have lower addresses
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+pub fn spawn_with_stack<F, T>(stack_size: usize, function: F) -> thread::JoinHandle<T>
+    where F: FnOnce() -> T + Send + 'static, T: Send + 'static {
+    thread::Builder::new().stack_size(stack_size).spawn(move || {
+        let limit = stack_address().saturating_sub(stack_size - STACK_MARGIN);
+        STACK_LIMIT.with(|stack_limit| stack_limit.set(limit));
+        function()
+    }).expect("Failed to spawn thread")
+}
+
+pub fn is_stack_exhausted() -> bool {
+    // Always false on threads of unknown stack size, such as those of an embedding application
+    let limit = STACK_LIMIT.with(|stack_limit| stack_limit.get());
+    limit != 0 && stack_address() < limit
+}
+
+pub fn check_stack(tok: &token::Token) {
+    if is_stack_exhausted() {
+        tok.print_custom_error(ErrorClass::CallDepth, STACK_ERROR);
+    }
+}
+
+pub fn stack_error() -> &'static str {
+    STACK_ERROR
+}
+
+pub fn check_array(literal: &ast::Literal, tok: &token::Token) {
+    if literal.literal_type == ast::LiteralType::Array {
+        check_array_length(literal.array_values.len(), tok);
+    }
+}
+
+pub fn check_array_length(length: usize, tok: &token::Token) {
+    // Also called before building an array, with the length it will have
+    let max_array_length = MAX_ARRAY_LENGTH.load(Ordering::Relaxed);
+    if length > max_array_length {
+        tok.print_custom_error(ErrorClass::ArrayTooLong, &format!("Array of {} elements exceeds the maximum of {} elements", length, max_array_length));
+    }
+    check_memory(length.saturating_mul(mem::size_of::<ast::Literal>()), tok);
+}
+
+pub fn check_string_length(length: usize, tok: &token::Token) {
+    // Called before building a string of 'length' bytes
+    check_memory(length, tok);
+}
+
+pub fn check_value(literal: &ast::Literal, tok: &token::Token) {
+    check_array(literal, tok);
+    if MAX_MEMORY.load(Ordering::Relaxed) != UNLIMITED {
+        // Only measured with a memory limit, as nested arrays make it linear in the size of the value
+        check_memory(size_of(literal), tok);
+    }
+}
+
+fn size_of(literal: &ast::Literal) -> usize {
+    literal.value.len() + literal.array_values.iter().map(|value| mem::size_of::<ast::Literal>() + size_of(value)).sum::<usize>()
+}
+
+fn check_memory(bytes: usize, tok: &token::Token) {
+    let max_memory = MAX_MEMORY.load(Ordering::Relaxed);
+    if bytes > max_memory {
+        tok.print_custom_error(ErrorClass::MemoryLimit, &format!("Value of {} bytes exceeds the memory limit of {} bytes", bytes, max_memory));
+    }
+}
+
+pub fn check_time(tok: &token::Token) {
+    if !HAS_TIMEOUT.load(Ordering::Relaxed) {
+        return;
+    }
+    let deadline = *DEADLINE.lock().unwrap();
+    if let Some(deadline) = deadline {
+        if Instant::now() > deadline {
+            let timeout = LIMITS.lock().unwrap().timeout.unwrap_or_default();
+            tok.print_custom_error(ErrorClass::TimedOut, &format!("Timed out after {} seconds", timeout.as_secs_f32()));
+        }
+    }
+}
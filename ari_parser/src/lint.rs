@@ -0,0 +1,267 @@
+use crate::ast;
+use crate::environment::ENV;
+use crate::token;
+use crate::limits;
+use std::collections::HashSet;
+
+///////////////////////////////////////////
+// Linter
+///////////////////////////////////////////
+// Static checks over the parsed statements for 'ari lint'.
+// Every rule can be turned off with '--allow [rule]'.
+// Running a script also reports the RUN_RULES as warnings, which '--deny-warnings' turns into an error.
+
+pub const RULES: [&str; 7] = [
+    "unused-variable", // Declared with 'let' or 'fn' but never read. Names starting with '_' are ignored
+    "unreachable-code", // Statements after 'return', 'break' or 'continue' in the same block
+    "shadowed-name", // Declaration which hides a variable or argument of an enclosing scope
+    "assignment-in-condition", // 'if (x = 1)', which was probably meant to be 'if (x == 1)'
+    "empty-block", // '{}' body of if/else/while/for or a bare block
+    "constant-condition", // 'if (1)', or a 'while' loop whose condition is a literal which is always false
+    "shadowed-native", // Declaration which hides a native or prelude function, such as 'let map = 1;'
+];
+
+pub const RUN_RULES: [&str; 3] = ["unused-variable", "constant-condition", "shadowed-native"];
+
+pub struct Warning {
+    pub rule: &'static str,
+    pub message: String,
+    pub line_number: usize,
+    pub index: usize, // 1-based column which the diagnostic points to
+}
+
+struct Declaration {
+    name: String,
+    line_number: usize,
+    index: usize,
+    report_unused: bool,
+}
+
+struct Frame {
+    declarations: Vec<Declaration>,
+    used: HashSet<String>,
+}
+
+pub struct Linter {
+    source_lines: Vec<String>,
+    builtins: HashSet<String>,
+    frames: Vec<Frame>,
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    pub fn new(source: &str) -> Linter {
+        Linter {
+            source_lines: source.lines().map(|line| line.to_owned()).collect(),
+            builtins: ENV.lock().unwrap().builtin_names(),
+            frames: Vec::<Frame>::new(),
+            warnings: Vec::<Warning>::new(),
+        }
+    }
+
+    pub fn lint(mut self, statements: &[Box<ast::Statement>]) -> Vec<Warning> {
+        self.begin_frame();
+        self.lint_statements(statements);
+        self.end_frame();
+        self.warnings.sort_by_key(|w| (w.line_number, w.index));
+        self.warnings
+    }
+
+    fn lint_statements(&mut self, statements: &[Box<ast::Statement>]) {
+        let mut exit_keyword: Option<&str> = None;
+        for s in statements {
+            if let Some(keyword) = exit_keyword.take() {
+                let index = self.first_column(s.line_number);
+                self.warn("unreachable-code", format!("Unreachable code after '{}'", keyword), s.line_number, index);
+            }
+            exit_keyword = match s.statement_type {
+                ast::StatementType::Return => Some("return"),
+                ast::StatementType::Break => Some("break"),
+                ast::StatementType::Continue => Some("continue"),
+                _ => None,
+            };
+            self.lint_statement(s);
+        }
+    }
+
+    fn lint_statement(&mut self, statement: &ast::Statement) {
+        limits::check_stack(&statement.token_name);
+        match statement.statement_type {
+            ast::StatementType::Block => {
+                if statement.statements.is_empty() && !statement.is_function {
+                    let index = self.first_column(statement.line_number);
+                    self.warn("empty-block", "Empty block".to_string(), statement.line_number, index);
+                }
+                self.begin_frame();
+                self.lint_statements(&statement.statements);
+                self.end_frame();
+            },
+            ast::StatementType::Function => {
+                self.declare(&statement.token_name, !statement.is_exported);
+                self.begin_frame();
+                for (i, argument) in statement.tokens.iter().enumerate() {
+                    if let Some(default) = statement.defaults.get(i) {
+                        self.lint_expr(default);
+                    }
+                    self.declare(argument, false);
+                }
+                self.lint_option_statement(&statement.then_branch);
+                self.end_frame();
+            },
+            ast::StatementType::Let => {
+                // The initialiser is checked first, so 'let x = x + 1;' reads the outer 'x'
+                self.lint_option_expr(&statement.expr);
+                if statement.tokens.is_empty() {
+                    self.declare(&statement.token_name, !statement.is_exported);
+                }
+                for tok in &statement.tokens {
+                    self.declare(tok, !statement.is_exported);
+                }
+            },
+            ast::StatementType::If | ast::StatementType::While => {
+                if let Some(condition) = statement.expr.as_ref() {
+                    if condition.expr_type == ast::ExprType::Assign || condition.expr_type == ast::ExprType::ArrayAssign {
+                        let message = format!("Assignment to '{}' used as a condition, did you mean '=='?", condition.operator.lexeme);
+                        self.warn("assignment-in-condition", message, condition.operator.line_number, condition.operator.start);
+                    }
+                    // 'while (true)' is the usual infinite loop, which is left with 'break'
+                    let is_loop = statement.statement_type == ast::StatementType::While;
+                    match constant_value(condition) {
+                        Some(value) if !(is_loop && value) => {
+                            let message = format!("Condition is always {}", value);
+                            self.warn("constant-condition", message, condition.operator.line_number, condition.operator.start);
+                        },
+                        _ => {}
+                    }
+                }
+                self.lint_option_expr(&statement.expr);
+                self.lint_option_statement(&statement.then_branch);
+                self.lint_option_statement(&statement.else_branch);
+            },
+            ast::StatementType::ForIn => {
+                self.lint_option_expr(&statement.expr);
+                self.begin_frame();
+                self.declare(&statement.token_name, false);
+                self.lint_option_statement(&statement.then_branch);
+                self.end_frame();
+            },
+            ast::StatementType::Import => {
+                // Imported names are not reported as unused, since the module may be imported for its side effects
+                for name in ast::Statement::exported_names(&statement.statements) {
+                    let mut tok = statement.token_name.clone();
+                    tok.lexeme = token::intern(&name);
+                    self.declare(&tok, false);
+                }
+            },
+            _ => {
+                self.lint_option_expr(&statement.expr);
+            }
+        }
+    }
+
+    fn lint_expr(&mut self, expr: &ast::Expr) {
+        limits::check_stack(&expr.operator);
+        if expr.expr_type == ast::ExprType::MultipleAssign {
+            // The targets are written, not read
+            self.lint_option_expr(&expr.right);
+            return;
+        }
+        if expr.expr_type == ast::ExprType::Variable {
+            // Also counts for outer declarations with the same name, which may be read before they are declared
+            for frame in &mut self.frames {
+                frame.used.insert(expr.operator.lexeme.to_string());
+            }
+        }
+        for argument in &expr.arguments {
+            self.lint_expr(argument);
+        }
+        self.lint_option_expr(&expr.left);
+        self.lint_option_expr(&expr.right);
+    }
+
+    fn lint_option_expr(&mut self, expr: &Option<Box<ast::Expr>>) {
+        if let Some(e) = expr.as_ref() {
+            self.lint_expr(e);
+        }
+    }
+    fn lint_option_statement(&mut self, statement: &Option<Box<ast::Statement>>) {
+        if let Some(s) = statement.as_ref() {
+            self.lint_statement(s);
+        }
+    }
+
+    fn declare(&mut self, tok: &token::Token, report_unused: bool) {
+        if self.builtins.contains(&*tok.lexeme) {
+            self.warn("shadowed-native", format!("'{}' hides the built-in function of the same name", tok.lexeme), tok.line_number, tok.start);
+        }
+        let outer = self.frames.iter().rev().skip(1)
+                        .flat_map(|frame| frame.declarations.iter())
+                        .find(|declaration| *declaration.name == *tok.lexeme)
+                        .map(|declaration| declaration.line_number);
+        if let Some(line_number) = outer {
+            self.warn("shadowed-name", format!("'{}' shadows the variable declared at line {}", tok.lexeme, line_number), tok.line_number, tok.start);
+        }
+        let declaration = Declaration {
+            name: tok.lexeme.to_string(),
+            line_number: tok.line_number,
+            index: tok.start,
+            report_unused: report_unused && !tok.lexeme.starts_with('_'),
+        };
+        self.frames.last_mut().unwrap().declarations.push(declaration);
+    }
+
+    fn begin_frame(&mut self) {
+        self.frames.push(Frame { declarations: Vec::<Declaration>::new(), used: HashSet::<String>::new() });
+    }
+    fn end_frame(&mut self) {
+        let frame = self.frames.pop().unwrap();
+        for declaration in frame.declarations {
+            if declaration.report_unused && !frame.used.contains(&declaration.name) {
+                self.warn("unused-variable", format!("'{}' is never used", declaration.name), declaration.line_number, declaration.index);
+            }
+        }
+    }
+
+    fn first_column(&self, line_number: usize) -> usize {
+        // Column of the first character on a line, for statements without a token
+        match line_number.checked_sub(1).and_then(|index| self.source_lines.get(index)) {
+            Some(line) => line.len() - line.trim_start().len() + 1,
+            None => 1,
+        }
+    }
+
+    fn warn(&mut self, rule: &'static str, message: String, line_number: usize, index: usize) {
+        self.warnings.push(Warning { rule, message, line_number, index });
+    }
+}
+
+fn constant_value(condition: &ast::Expr) -> Option<bool> {
+    // Truthiness of a condition which is a literal
+    if condition.expr_type != ast::ExprType::Literal {
+        return None;
+    }
+    Some(ast::Expr::is_truthy(&condition.literal))
+}
+
+#[cfg(test)]
+mod tests {
+    fn rules(source: &str) -> Vec<&'static str> {
+        super::Linter::new(source).lint(&crate::parse(source, "", 1)).into_iter().map(|warning| warning.rule).collect()
+    }
+
+    #[test]
+    fn reports_each_rule() {
+        assert_eq!(rules("fn f() { let unused = 1; }"), vec!["unused-variable", "unused-variable"]);
+        assert_eq!(rules("fn _f() { return 1; println 2; }"), vec!["unreachable-code"]);
+        assert_eq!(rules("let _x = 1; fn _f(_x) { return _x; }"), vec!["shadowed-name"]);
+        assert_eq!(rules("let _x = 1; if (_x = 2) { println _x; }"), vec!["assignment-in-condition"]);
+        assert_eq!(rules("let _x = 1; if (_x == 1) {}"), vec!["empty-block"]);
+        assert_eq!(rules("if (1) { println 1; }"), vec!["constant-condition"]);
+        assert_eq!(rules("let _map = 1; let length = 2; println length;"), vec!["shadowed-native"]);
+    }
+
+    #[test]
+    fn names_starting_with_an_underscore_may_be_unused() {
+        assert!(rules("let _unused = 1; fn _helper(_a) { return 1; }").is_empty());
+    }
+}
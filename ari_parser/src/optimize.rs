@@ -0,0 +1,369 @@
+use crate::ast;
+use crate::token;
+use crate::limits;
+use std::collections::HashSet;
+
+///////////////////////////////////////////
+// Optimizer
+///////////////////////////////////////////
+// Rewrites the parsed statements of a script before it runs. The resolver must run again afterwards.
+// 'ari fmt', 'ari lint' and '--dump-ast' see the statements as written.
+//
+//      Constant folding     => '2 * 60', '"a" + "b"', '!true' and '(1)' become literals.
+//                              Operations which would raise an error, such as '1 / 0', are left for runtime
+//      Dead branches        => 'if' with a literal condition is replaced by the branch which is taken,
+//                              and 'while' with a literal false condition is removed
+//      Loop-invariant calls => Calls to pure natives in a 'while' condition, such as 'length(arr)' in 'i < length(arr)',
+//                              are made once before the loop when nothing in the loop can change their arguments
+//
+// Hoisted values are held in variables named '#invariant0', '#invariant1' ..., which scripts cannot write or see.
+// Nothing is hoisted from scripts which start threads, timers or servers, or call eval(),
+// since those can change a variable while the loop runs.
+
+// Natives whose result only depends on their arguments
+const PURE_NATIVES: [&str; 36] = [
+    "power", "log", "modulo", "absolute", "floor", "ceiling", "max", "min", "round", "clamp",
+    "sin", "cos", "tan", "asin", "acos", "atan", "atan2", "sqrt", "exp", "pi", "e",
+    "typeof", "to_string", "to_number", "length", "split", "join", "to_lowercase", "to_uppercase", "trim",
+    "replace", "substring", "index_of", "contains", "sum", "mean",
+];
+
+// Natives which run code concurrently with the loop, or define variables at runtime
+const CONCURRENT_NATIVES: [&str; 7] = ["spawn", "set_timeout", "set_interval", "route", "serve_routes", "eval", "load_plugin"];
+
+pub struct Optimizer {
+    written: HashSet<String>, // Every name which the script declares or assigns
+    can_hoist: bool,
+    hoisted_count: usize,
+}
+
+impl Optimizer {
+    pub fn new(statements: &[Box<ast::Statement>]) -> Optimizer {
+        let mut written = HashSet::<String>::new();
+        let mut read = HashSet::<String>::new();
+        for s in statements {
+            collect_statement(s, &mut written, &mut read);
+        }
+        let can_hoist = !CONCURRENT_NATIVES.iter().any(|name| read.contains(*name));
+        Optimizer {
+            written,
+            can_hoist,
+            hoisted_count: 0,
+        }
+    }
+
+    pub fn optimize(&mut self, statements: Vec<Box<ast::Statement>>) -> Vec<Box<ast::Statement>> {
+        let mut optimized = Vec::<Box<ast::Statement>>::new();
+        for s in statements {
+            let mut statement = match self.optimize_statement(s) {
+                Some(statement) => statement,
+                None => continue
+            };
+            if statement.statement_type == ast::StatementType::While && self.can_hoist {
+                optimized.extend(self.hoist(&mut statement));
+            }
+            optimized.push(statement);
+        }
+        optimized
+    }
+
+    fn optimize_statement(&mut self, mut statement: Box<ast::Statement>) -> Option<Box<ast::Statement>> {
+        // None if the statement never does anything
+        limits::check_stack(&statement.token_name);
+        fold_option_expr(&mut statement.expr);
+        match statement.statement_type {
+            ast::StatementType::Block | ast::StatementType::Import => {
+                let statements = std::mem::take(&mut statement.statements);
+                statement.statements = self.optimize(statements);
+            },
+            ast::StatementType::Function => {
+                for default in &mut statement.defaults {
+                    fold_expr(default);
+                }
+                statement.then_branch = self.optimize_branch(statement.then_branch.take());
+            },
+            ast::StatementType::If => {
+                if let Some(taken) = constant_condition(&statement.expr) {
+                    let branch = if taken { statement.then_branch.take() } else { statement.else_branch.take() };
+                    return branch.and_then(|branch| self.optimize_statement(branch));
+                }
+                statement.then_branch = self.optimize_branch(statement.then_branch.take());
+                statement.else_branch = statement.else_branch.take().and_then(|branch| self.optimize_statement(branch));
+            },
+            ast::StatementType::While => {
+                if constant_condition(&statement.expr) == Some(false) {
+                    return None;
+                }
+                statement.then_branch = self.optimize_branch(statement.then_branch.take());
+            },
+            ast::StatementType::ForIn => {
+                statement.then_branch = self.optimize_branch(statement.then_branch.take());
+            },
+            _ => {}
+        }
+        Some(statement)
+    }
+
+    fn optimize_branch(&mut self, branch: Option<Box<ast::Statement>>) -> Option<Box<ast::Statement>> {
+        // A branch which is removed still needs a statement in its place
+        branch.map(|branch| self.optimize_statement(branch).unwrap_or_else(|| Box::new(ast::Statement::new_block(Vec::<Box<ast::Statement>>::new(), false))))
+    }
+
+    fn hoist(&mut self, statement: &mut ast::Statement) -> Vec<Box<ast::Statement>> {
+        // Moves the invariant calls of a loop condition into 'let' statements which run before the loop.
+        // Only the condition is searched, because it always runs first, so the calls are made at the same point
+        let mut loop_written = HashSet::<String>::new();
+        let mut loop_read = HashSet::<String>::new();
+        if let Some(condition) = statement.expr.as_ref() {
+            collect_expr(condition, &mut loop_written, &mut loop_read);
+        }
+        if let Some(body) = statement.then_branch.as_ref() {
+            collect_statement(body, &mut loop_written, &mut loop_read);
+        }
+        // A user-defined function called in the loop could assign any variable it can see
+        if !self.only_pure_calls(statement.expr.as_deref()) || !statement.then_branch.as_deref().map_or(true, |body| self.only_pure_calls_in(body)) {
+            return Vec::<Box<ast::Statement>>::new();
+        }
+        let mut hoisted = Vec::<Box<ast::Statement>>::new();
+        if let Some(condition) = statement.expr.as_mut() {
+            self.hoist_expr(condition, &loop_written, &mut hoisted);
+        }
+        hoisted
+    }
+
+    fn hoist_expr(&mut self, expr: &mut ast::Expr, loop_written: &HashSet<String>, hoisted: &mut Vec<Box<ast::Statement>>) {
+        if self.is_pure_call(expr) && self.is_invariant(expr, loop_written) {
+            let mut tok = expr.operator.clone();
+            tok.token_type = token::TokenType::Identifier;
+            tok.lexeme = token::intern(&format!("#invariant{}", self.hoisted_count));
+            self.hoisted_count += 1;
+            let call = std::mem::replace(expr, ast::Expr::variable(tok.clone()));
+            hoisted.push(Box::new(ast::Statement::new_let(Some(Box::new(call)), tok)));
+            return;
+        }
+        for argument in &mut expr.arguments {
+            self.hoist_expr(argument, loop_written, hoisted);
+        }
+        if let Some(left) = expr.left.as_mut() {
+            self.hoist_expr(left, loop_written, hoisted);
+        }
+        if let Some(right) = expr.right.as_mut() {
+            self.hoist_expr(right, loop_written, hoisted);
+        }
+    }
+
+    fn is_invariant(&self, expr: &ast::Expr, loop_written: &HashSet<String>) -> bool {
+        // True if the expression has the same value on every iteration of the loop
+        let children_invariant = expr.arguments.iter().all(|argument| self.is_invariant(argument, loop_written))
+            && expr.left.as_ref().map_or(true, |left| self.is_invariant(left, loop_written))
+            && expr.right.as_ref().map_or(true, |right| self.is_invariant(right, loop_written));
+        match expr.expr_type {
+            ast::ExprType::Literal | ast::ExprType::None => true,
+            ast::ExprType::Variable => !loop_written.contains(&*expr.operator.lexeme),
+            ast::ExprType::Call | ast::ExprType::MethodCall => self.is_pure_call(expr) && children_invariant,
+            ast::ExprType::Assign | ast::ExprType::ArrayAssign | ast::ExprType::MultipleAssign => false,
+            _ => children_invariant
+        }
+    }
+
+    fn is_pure_call(&self, expr: &ast::Expr) -> bool {
+        // A call to a pure native, which the script has not redefined
+        if expr.expr_type != ast::ExprType::Call && expr.expr_type != ast::ExprType::MethodCall {
+            return false;
+        }
+        match expr.right.as_ref() {
+            Some(callee) => callee.expr_type == ast::ExprType::Variable && callee.depth.is_none()
+                            && PURE_NATIVES.contains(&&*callee.operator.lexeme)
+                            && !self.written.contains(&*callee.operator.lexeme),
+            None => false
+        }
+    }
+
+    fn only_pure_calls(&self, expr: Option<&ast::Expr>) -> bool {
+        let expr = match expr {
+            Some(expr) => expr,
+            None => return true
+        };
+        if (expr.expr_type == ast::ExprType::Call || expr.expr_type == ast::ExprType::MethodCall) && !self.is_pure_call(expr) {
+            return false;
+        }
+        expr.arguments.iter().all(|argument| self.only_pure_calls(Some(argument)))
+            && self.only_pure_calls(expr.left.as_deref())
+            && self.only_pure_calls(expr.right.as_deref())
+    }
+
+    fn only_pure_calls_in(&self, statement: &ast::Statement) -> bool {
+        self.only_pure_calls(statement.expr.as_deref())
+            && statement.defaults.iter().all(|default| self.only_pure_calls(Some(default)))
+            && statement.statements.iter().all(|s| self.only_pure_calls_in(s))
+            && statement.then_branch.as_deref().map_or(true, |s| self.only_pure_calls_in(s))
+            && statement.else_branch.as_deref().map_or(true, |s| self.only_pure_calls_in(s))
+    }
+}
+
+fn collect_statement(statement: &ast::Statement, written: &mut HashSet<String>, read: &mut HashSet<String>) {
+    // Names declared or assigned, and names read, anywhere in the statement
+    match statement.statement_type {
+        ast::StatementType::Let | ast::StatementType::Import => {
+            written.extend(statement.declared_names());
+            written.extend(ast::Statement::exported_names(&statement.statements));
+        },
+        ast::StatementType::Function | ast::StatementType::ForIn => {
+            written.insert(statement.token_name.lexeme.to_string());
+            written.extend(statement.tokens.iter().map(|tok| tok.lexeme.to_string()));
+        },
+        _ => {}
+    }
+    if let Some(expr) = statement.expr.as_ref() {
+        collect_expr(expr, written, read);
+    }
+    for default in &statement.defaults {
+        collect_expr(default, written, read);
+    }
+    for s in &statement.statements {
+        collect_statement(s, written, read);
+    }
+    if let Some(s) = statement.then_branch.as_ref() {
+        collect_statement(s, written, read);
+    }
+    if let Some(s) = statement.else_branch.as_ref() {
+        collect_statement(s, written, read);
+    }
+}
+
+fn collect_expr(expr: &ast::Expr, written: &mut HashSet<String>, read: &mut HashSet<String>) {
+    match expr.expr_type {
+        ast::ExprType::Variable => {
+            read.insert(expr.operator.lexeme.to_string());
+        },
+        ast::ExprType::Assign | ast::ExprType::ArrayAssign => {
+            written.insert(expr.operator.lexeme.to_string());
+        },
+        ast::ExprType::MultipleAssign => {
+            written.extend(expr.arguments.iter().map(|target| target.operator.lexeme.to_string()));
+        },
+        _ => {}
+    }
+    for argument in &expr.arguments {
+        collect_expr(argument, written, read);
+    }
+    if let Some(left) = expr.left.as_ref() {
+        collect_expr(left, written, read);
+    }
+    if let Some(right) = expr.right.as_ref() {
+        collect_expr(right, written, read);
+    }
+}
+
+fn constant_condition(condition: &Option<Box<ast::Expr>>) -> Option<bool> {
+    // Truthiness of a condition which has been folded into a literal
+    match condition.as_ref() {
+        Some(expr) if expr.expr_type == ast::ExprType::Literal && is_scalar(&expr.literal) => Some(ast::Expr::is_truthy(&expr.literal)),
+        _ => None
+    }
+}
+
+fn fold_option_expr(expr: &mut Option<Box<ast::Expr>>) {
+    if let Some(e) = expr.as_mut() {
+        fold_expr(e);
+    }
+}
+
+fn fold_expr(expr: &mut ast::Expr) {
+    limits::check_stack(&expr.operator);
+    for argument in &mut expr.arguments {
+        fold_expr(argument);
+    }
+    fold_option_expr(&mut expr.left);
+    fold_option_expr(&mut expr.right);
+    if can_fold(expr) {
+        // Evaluated the same way as at runtime, so the result is identical
+        let literal = expr.execute();
+        *expr = ast::Expr::new(ast::ExprType::Literal, Vec::<Box<ast::Expr>>::new(), None, None, expr.operator.clone(), literal);
+    }
+}
+
+fn can_fold(expr: &ast::Expr) -> bool {
+    // True if every operand is a literal and evaluating the expression cannot raise an error
+    let left = expr.left.as_ref().filter(|left| left.expr_type == ast::ExprType::Literal && is_scalar(&left.literal)).map(|left| &left.literal);
+    let right = expr.right.as_ref().filter(|right| right.expr_type == ast::ExprType::Literal && is_scalar(&right.literal)).map(|right| &right.literal);
+    match expr.expr_type {
+        ast::ExprType::Grouping => right.is_some(),
+        ast::ExprType::Logical => left.is_some() && right.is_some(),
+        ast::ExprType::Unary => match (expr.operator.token_type, right) {
+            (token::TokenType::Minus, Some(right)) => right.literal_type == ast::LiteralType::Number,
+            (token::TokenType::Bang, Some(_)) => true,
+            _ => false
+        },
+        ast::ExprType::Binary => {
+            let (left, right) = match (left, right) {
+                (Some(left), Some(right)) => (left, right),
+                _ => return false
+            };
+            let numbers = left.literal_type == ast::LiteralType::Number && right.literal_type == ast::LiteralType::Number;
+            let strings = left.literal_type == ast::LiteralType::String && right.literal_type == ast::LiteralType::String;
+            match expr.operator.token_type {
+                token::TokenType::Plus => ast::Expr::add_or_concat(left.literal_type, right.literal_type).is_ok()
+                                          && left.literal_type != ast::LiteralType::Bool && left.literal_type != ast::LiteralType::Null,
+                token::TokenType::Minus | token::TokenType::Star => numbers,
+                token::TokenType::Slash => numbers && ast::Expr::divide(left, right).is_ok(),
+                token::TokenType::Percent => numbers && ast::Expr::remainder(left, right).is_ok(),
+                token::TokenType::Greater | token::TokenType::GreaterEqual |
+                token::TokenType::Less | token::TokenType::LessEqual => numbers || strings,
+                token::TokenType::EqualEqual | token::TokenType::BangEqual => true,
+                _ => false
+            }
+        },
+        _ => false
+    }
+}
+
+fn is_scalar(literal: &ast::Literal) -> bool {
+    matches!(literal.literal_type, ast::LiteralType::Number | ast::LiteralType::String | ast::LiteralType::Bool | ast::LiteralType::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dump;
+
+    fn optimized(source: &str) -> String {
+        // The statements after optimization, as shown by '--dump-ast'
+        dump::dump_statements(&crate::optimize(crate::parse(source, "", 1)))
+    }
+
+    #[test]
+    fn folds_constant_expressions() {
+        let statements = optimized("let seconds = 60 * 60; let greeting = \"a\" + \"b\"; let negated = !true;");
+        assert!(statements.contains("Literal Number 3600"), "{}", statements);
+        assert!(statements.contains("Literal String \"ab\""), "{}", statements);
+        assert!(statements.contains("Literal Bool false"), "{}", statements);
+        assert!(!statements.contains("Binary"), "{}", statements);
+    }
+
+    #[test]
+    fn leaves_errors_for_runtime() {
+        let statements = optimized("let quotient = 1 / 0;");
+        assert!(statements.contains("Binary /"), "{}", statements);
+    }
+
+    #[test]
+    fn removes_dead_branches() {
+        let statements = optimized("if (true) { println \"taken\"; } else { println \"skipped\"; } while (false) { println \"never\"; }");
+        assert!(!statements.contains("If"), "{}", statements);
+        assert!(!statements.contains("While"), "{}", statements);
+        assert!(statements.contains("taken"), "{}", statements);
+        assert!(!statements.contains("skipped"), "{}", statements);
+    }
+
+    #[test]
+    fn hoists_invariant_calls() {
+        let statements = optimized("let hoisted = [1, 2, 3]; let h = 0; while (h < length(hoisted)) { h = h + 1; }");
+        assert!(statements.contains("Let #invariant0"), "{}", statements);
+    }
+
+    #[test]
+    fn keeps_calls_whose_arguments_change() {
+        let statements = optimized("let changed = [1, 2, 3]; let c = 0; while (c < length(changed)) { changed = [1]; c = c + 1; }");
+        assert!(!statements.contains("#invariant"), "{}", statements);
+    }
+}
@@ -1,11 +1,15 @@
 use crate::token;
 use crate::ast;
+use crate::limits;
+use crate::scanner;
+use ari_errors::ErrorClass;
 use ari_errors;
 
 pub struct Parser {
 
     tokens: Vec<token::Token>,
     current: usize,
+    imports: Vec<String>, // Paths of the modules currently being imported, to catch circular imports
 }
 
 impl Parser {
@@ -13,26 +17,87 @@ impl Parser {
         Parser {
             tokens,
             current: 0,
+            imports: Vec::<String>::new(),
         }
     }
 
     pub fn parse(&mut self) -> Vec<Box<ast::Statement>> {
-
+        // Errors from here on are syntax errors, which exit with a different code to runtime errors
+        let was_parsing = ari_errors::set_parsing(true);
         let mut statements = Vec::<Box<ast::Statement>>::new();
         while !self.is_at_end() {
             statements.push(self.declaration().unwrap());
         }
+        ari_errors::set_parsing(was_parsing);
         return statements;
     }
 
     fn declaration(&mut self) -> Option<Box<ast::Statement>> {
-        if self.check_next_tokens(vec![token::TokenType::Fn]) {
-            return self.function_declaration("function");
+        limits::check_stack(&self.peek());
+        let line_number = self.peek().line_number;
+        let declaration = if self.check_next_tokens(vec![token::TokenType::Import]) {
+            self.import_statement()
+        }
+        else if self.check_next_tokens(vec![token::TokenType::Export]) {
+            self.export_declaration()
         }
-        if self.check_next_tokens(vec![token::TokenType::Let]) {
-            return self.let_declaration();
+        else if self.check_next_tokens(vec![token::TokenType::Fn]) {
+            self.function_declaration("function")
         }
-        return self.statement();
+        else if self.check_next_tokens(vec![token::TokenType::Let]) {
+            self.let_declaration()
+        }
+        else if self.check_next_tokens(vec![token::TokenType::Const]) {
+            self.const_declaration()
+        }
+        else {
+            self.statement()
+        };
+        return declaration.map(|mut statement| {
+            statement.line_number = line_number;
+            statement
+        });
+    }
+    // Importing exported declarations from another script
+    fn import_statement(&mut self) -> Option<Box<ast::Statement>> {
+        let tok = self.consume(token::TokenType::String, ari_errors::ErrorType::ExpectModulePath);
+        self.consume(token::TokenType::Semicolon, ari_errors::ErrorType::ExpectSemicolon);
+        let path = tok.literal.to_string();
+        if self.imports.contains(&path) {
+            tok.print_custom_error(ErrorClass::ImportFailed, &format!("'{}' is imported circularly", path));
+        }
+        let contents = match ari_errors::read_file(&path) {
+            Ok(content) => content,
+            Err(_) => {
+                tok.print_custom_error(ErrorClass::ImportFailed, &format!("Module '{}' does not exist", path));
+                return None;
+            }
+        };
+        let mut scanner_struct = scanner::Scanner::new(&contents, &path, 1);
+        let mut module_parser = Parser::new(scanner_struct.scan_tokens());
+        module_parser.imports = self.imports.clone();
+        module_parser.imports.push(path);
+        let statements = module_parser.parse();
+        return Some(Box::new(ast::Statement::new_import(statements, tok)));
+    }
+    fn export_declaration(&mut self) -> Option<Box<ast::Statement>> {
+        let mut declaration = if self.check_next_tokens(vec![token::TokenType::Fn]) {
+            self.function_declaration("function")
+        }
+        else if self.check_next_tokens(vec![token::TokenType::Let]) {
+            self.let_declaration()
+        }
+        else if self.check_next_tokens(vec![token::TokenType::Const]) {
+            self.const_declaration()
+        }
+        else {
+            self.print_error(ari_errors::ErrorType::ExpectExportDeclaration);
+            None
+        };
+        if let Some(statement) = declaration.as_mut() {
+            statement.is_exported = true;
+        }
+        return declaration;
     }
     // Declaring new functions
     fn function_declaration(&mut self, func_type: &str) -> Option<Box<ast::Statement>> {
@@ -42,15 +107,38 @@ impl Parser {
             "class" => ari_errors::ErrorType::ExpectClassName,
             _ => panic!("function_declaration() does not implement {}", func_type)
         };
+        let doc = self.previous().literal.to_string(); // Text of the '///' comments before 'fn'
         let tok = self.consume(token::TokenType::Identifier, error_type); // Name of the function
         self.consume(token::TokenType::LeftParen, ari_errors::ErrorType::ExpectLeftParen);
         let mut arguments = Vec::<token::Token>::new(); // Arguments of the function
+        let mut defaults = Vec::<Box<ast::Expr>>::new(); // Default values, such as 'greeting = "hi"'
+        let mut is_variadic = false; // '...rest' collects the remaining arguments
         if !self.check(token::TokenType::RightParen) {
             loop {
                 if arguments.len()  >= 255 {
                     self.print_error(ari_errors::ErrorType::TooManyArguments);
                 }
-                arguments.push(self.consume(token::TokenType::Identifier, ari_errors::ErrorType::ExpectArgumentName));
+                if self.check_next_tokens(vec![token::TokenType::DotDotDot]) {
+                    let argument = self.consume(token::TokenType::Identifier, ari_errors::ErrorType::ExpectArgumentName);
+                    if self.check(token::TokenType::Comma) || self.check(token::TokenType::Equal) {
+                        argument.print_custom_error(ErrorClass::Syntax, &format!("Variadic argument '...{}' must be the last argument, without a default value", argument.lexeme));
+                    }
+                    is_variadic = true;
+                    arguments.push(argument);
+                    defaults.push(Box::new(ast::Expr::none()));
+                    break;
+                }
+                let argument = self.consume(token::TokenType::Identifier, ari_errors::ErrorType::ExpectArgumentName);
+                if self.check_next_tokens(vec![token::TokenType::Equal]) {
+                    defaults.push(self.expression().unwrap());
+                }
+                else {
+                    if defaults.last().map_or(false, |d| d.expr_type != ast::ExprType::None) {
+                        argument.print_custom_error(ErrorClass::Syntax, &format!("Argument '{}' must have a default value, as the arguments before it do", argument.lexeme));
+                    }
+                    defaults.push(Box::new(ast::Expr::none()));
+                }
+                arguments.push(argument);
                 if !self.check_next_tokens(vec![token::TokenType::Comma]) {
                     break;
                 }
@@ -59,9 +147,30 @@ impl Parser {
         self.consume(token::TokenType::RightParen, ari_errors::ErrorType::ExpectRightParen);
         self.consume(token::TokenType::LeftBrace, ari_errors::ErrorType::ExpectLeftBrace);
         let body = Some(Box::new(ast::Statement::new_block(self.block(), true))); // Body of the function
-        return Some(Box::new(ast::Statement::new_function(body, tok, arguments)));
+        let mut function = ast::Statement::new_function(body, tok, arguments);
+        function.defaults = defaults;
+        function.is_variadic = is_variadic;
+        function.doc = doc;
+        return Some(Box::new(function));
     }
     fn let_declaration(&mut self) -> Option<Box<ast::Statement>> {
+        if self.check_next_tokens(vec![token::TokenType::LeftParen]) {
+            // Destructuring, such as 'let (a, b) = [1, 2];'
+            let mut names = Vec::<token::Token>::new();
+            loop {
+                names.push(self.consume(token::TokenType::Identifier, ari_errors::ErrorType::ExpectVariableName));
+                if !self.check_next_tokens(vec![token::TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(token::TokenType::RightParen, ari_errors::ErrorType::ExpectRightParen);
+            self.consume(token::TokenType::Equal, ari_errors::ErrorType::InvalidVariableDefinition);
+            let initialisation = self.expression();
+            self.consume(token::TokenType::Semicolon, ari_errors::ErrorType::ExpectSemicolon);
+            let mut statement = ast::Statement::new_let(initialisation, names[0].clone());
+            statement.tokens = names;
+            return Some(Box::new(statement));
+        }
         let tok = self.consume(token::TokenType::Identifier, ari_errors::ErrorType::ExpectVariableName);
         let initialisation = if self.check_next_tokens(vec![token::TokenType::Equal]) {
             self.expression()
@@ -72,14 +181,30 @@ impl Parser {
         self.consume(token::TokenType::Semicolon, ari_errors::ErrorType::ExpectSemicolon);
         return Some(Box::new(ast::Statement::new_let(initialisation, tok)));
     }
+    fn const_declaration(&mut self) -> Option<Box<ast::Statement>> {
+        // Same as 'let', but the value is required and the variable cannot be reassigned
+        let mut declaration = self.let_declaration();
+        if let Some(statement) = declaration.as_mut() {
+            if statement.expr.as_ref().map_or(true, |expr| expr.expr_type == ast::ExprType::None) {
+                statement.token_name.print_custom_error(ErrorClass::Syntax, &format!("Constant '{}' must be given a value", statement.token_name.lexeme));
+            }
+            statement.is_const = true;
+        }
+        return declaration;
+    }
 
     fn statement(&mut self) -> Option<Box<ast::Statement>> {
+        limits::check_stack(&self.peek());
         let mut include_semicolon = true;
+        let line_number = self.peek().line_number;
         if self.check_next_tokens(vec![token::TokenType::For]) {
             // For
-            return self.for_statement();
+            return self.for_statement().map(|mut statement| {
+                statement.line_number = line_number;
+                statement
+            });
         }
-        let stmt = if self.check_next_tokens(vec![token::TokenType::If]) {
+        let mut stmt = if self.check_next_tokens(vec![token::TokenType::If]) {
             // If
             include_semicolon = false;
             let (condition_expr, then_branch, else_branch) = self.if_statement();
@@ -88,8 +213,10 @@ impl Parser {
         else if self.check_next_tokens(vec![token::TokenType::While]) {
             // While
             include_semicolon = false;
-            let (condition_expr, body_branch) = self.while_statement();
-            ast::Statement::new_while(condition_expr, body_branch)
+            let (condition_expr, body_branch, parenthesis) = self.while_statement();
+            let mut while_statement = ast::Statement::new_while(condition_expr, body_branch);
+            while_statement.token_name = parenthesis;
+            while_statement
         }
         else if self.check_next_tokens(vec![token::TokenType::Return]) {
             // Return from function
@@ -98,11 +225,15 @@ impl Parser {
         }
         else if self.check_next_tokens(vec![token::TokenType::Break]) {
             // Break loop
-            ast::Statement::new_break()
+            let mut break_statement = ast::Statement::new_break();
+            break_statement.token_name = self.previous();
+            break_statement
         }
         else if self.check_next_tokens(vec![token::TokenType::Continue]) {
             // Continue loop
-            ast::Statement::new_continue()
+            let mut continue_statement = ast::Statement::new_continue();
+            continue_statement.token_name = self.previous();
+            continue_statement
         }
         else if self.check_next_tokens(vec![token::TokenType::Print]) {
             // Print
@@ -123,17 +254,16 @@ impl Parser {
         }
         else {
             // Create expression
-            let expr = self.expression();
-            // Check if variable exists prematurely, not sure if buggy because different from original
-            let e = expr.clone().unwrap();
-            if e.expr_type == ast::ExprType::Variable {
-                e.evaluate_expr();
+            let mut expr = self.expression();
+            if self.check(token::TokenType::Comma) {
+                expr = Some(self.multiple_assignment(expr.unwrap()));
             }
             ast::Statement::new_expression(expr)
         };
         if include_semicolon {
             self.consume(token::TokenType::Semicolon, ari_errors::ErrorType::ExpectSemicolon);
         }
+        stmt.line_number = line_number;
         return Some(Box::new(stmt));
     }
 
@@ -149,17 +279,28 @@ impl Parser {
         return (condition_expr, then_branch, else_branch);
     }
 
-    fn while_statement(&mut self) -> (Option<Box<ast::Expr>>, Option<Box<ast::Statement>>) {
+    fn while_statement(&mut self) -> (Option<Box<ast::Expr>>, Option<Box<ast::Statement>>, token::Token) {
+        // The closing parenthesis is kept for errors raised by the loop, since the condition may be a literal without a token
         self.consume(token::TokenType::LeftParen, ari_errors::ErrorType::ExpectLeftParen);
         let condition_expr = self.expression();
-        self.consume(token::TokenType::RightParen, ari_errors::ErrorType::ExpectRightParen);
+        let parenthesis = self.consume(token::TokenType::RightParen, ari_errors::ErrorType::ExpectRightParen);
         let body_branch = self.statement();
-        return (condition_expr, body_branch);
+        return (condition_expr, body_branch, parenthesis);
     }
 
     fn for_statement(&mut self) -> Option<Box<ast::Statement>> {
         self.consume(token::TokenType::LeftParen, ari_errors::ErrorType::ExpectLeftParen);
 
+        // 'for (x in items)'
+        if self.check(token::TokenType::Identifier) && self.tokens.get(self.current + 1).map_or(false, |tok| tok.token_type == token::TokenType::In) {
+            let variable = self.advance();
+            self.advance(); // Consume 'in'
+            let iterable_expr = self.expression();
+            self.consume(token::TokenType::RightParen, ari_errors::ErrorType::InvalidForLoop);
+            let body_branch = self.statement();
+            return Some(Box::new(ast::Statement::new_for_in(variable, iterable_expr, body_branch)));
+        }
+
         // Initialisation
         let init_statement = if self.check_next_tokens(vec![token::TokenType::Semicolon]) {
             None
@@ -184,25 +325,31 @@ impl Parser {
         self.consume(token::TokenType::Semicolon, ari_errors::ErrorType::ExpectSemicolon);
 
         // Increment
-        let increment_expr = if self.check_next_tokens(vec![token::TokenType::RightParen]) {
+        let increment_expr = if self.check(token::TokenType::RightParen) {
             Box::new(ast::Expr::none())
         }
         else {
             self.expression().unwrap()
         };
-        self.consume(token::TokenType::RightParen, ari_errors::ErrorType::InvalidForLoop);
-        
+        let parenthesis = self.consume(token::TokenType::RightParen, ari_errors::ErrorType::InvalidForLoop);
 
         // Put everything together 
         let mut body_branch = self.statement();
         if increment_expr.expr_type != ast::ExprType::None {
             let statements = vec![body_branch.unwrap(), Box::new(ast::Statement::new_expression(Some(increment_expr)))];
-            body_branch = Some(Box::new(ast::Statement::new_block(statements, false)));
-        }
-        body_branch = Some(Box::new(ast::Statement::new_while(condition_expr, body_branch)));
+            let mut increment_block = ast::Statement::new_block(statements, false);
+            increment_block.is_for_loop = true;
+            body_branch = Some(Box::new(increment_block));
+        }
+        let mut while_statement = ast::Statement::new_while(condition_expr, body_branch);
+        while_statement.token_name = parenthesis;
+        while_statement.is_for_loop = true;
+        body_branch = Some(Box::new(while_statement));
         if !init_statement.is_none() {
             let statements = vec![init_statement.unwrap(), body_branch.unwrap()];
-            body_branch = Some(Box::new(ast::Statement::new_block(statements, false)));
+            let mut init_block = ast::Statement::new_block(statements, false);
+            init_block.is_for_loop = true;
+            body_branch = Some(Box::new(init_block));
         }
 
         return body_branch;
@@ -229,6 +376,7 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Option<Box<ast::Expr>> {
+        limits::check_stack(&self.peek());
         return self.assignment();
     }
 
@@ -245,16 +393,43 @@ impl Parser {
             else if expr.expr_type == ast::ExprType::ArrayAccess {
                 //println!("hooh");
                 //let equals_token = self.previous();
-                let ref_token = expr.left.unwrap().operator.clone();
-                let index_expr = expr.right.clone();
-                let value_expr = self.or().unwrap();
-                return Some(Box::new(ast::Expr::array_assign(index_expr, Some(value_expr), ref_token)));
+                // Collect the indexes of 'm[1][2]' down to the variable
+                let mut indices = Vec::<Box<ast::Expr>>::new();
+                let mut target = expr.clone();
+                while target.expr_type == ast::ExprType::ArrayAccess {
+                    indices.insert(0, target.right.unwrap());
+                    target = target.left.unwrap();
+                }
+                if target.expr_type == ast::ExprType::Variable {
+                    let ref_token = target.operator.clone();
+                    let value_expr = self.or().unwrap();
+                    return Some(Box::new(ast::Expr::array_assign(indices, Some(value_expr), ref_token)));
+                }
             }
             self.print_error(ari_errors::ErrorType::InvalidAssignment);
         }
         return Some(expr);
     }
 
+    fn multiple_assignment(&mut self, first: Box<ast::Expr>) -> Box<ast::Expr> {
+        // 'a, b = b, a', where every value is evaluated before any variable is assigned
+        let mut targets = vec![first];
+        while self.check_next_tokens(vec![token::TokenType::Comma]) {
+            targets.push(self.or().unwrap());
+        }
+        if targets.iter().any(|target| target.expr_type != ast::ExprType::Variable) {
+            self.print_error(ari_errors::ErrorType::InvalidAssignment);
+        }
+        let equals = self.consume(token::TokenType::Equal, ari_errors::ErrorType::InvalidAssignment);
+        let mut values = vec![self.or().unwrap()];
+        while self.check_next_tokens(vec![token::TokenType::Comma]) {
+            values.push(self.or().unwrap());
+        }
+        // A single value is destructured, such as 'q, r = divmod(7, 2)'
+        let value_expr = if values.len() == 1 { values.pop().unwrap() } else { Box::new(ast::Expr::array_creation(equals.clone(), values)) };
+        return Box::new(ast::Expr::multiple_assign(targets, Some(value_expr), equals));
+    }
+
     fn or(&mut self) -> Option<Box<ast::Expr>> {
         let mut expr = self.and();
         while self.check_next_tokens(vec![token::TokenType::Or]) {
@@ -286,8 +461,56 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Option<Box<ast::Expr>>{
-        let mut expr = self.term();
+        let mut expr = self.range();
         while self.check_next_tokens(vec![token::TokenType::Greater, token::TokenType::GreaterEqual, token::TokenType::Less, token::TokenType::LessEqual]) {
+            let operator = self.previous();
+            let right = self.range();
+            expr = Some(Box::new(ast::Expr::binary(expr, right, operator)));
+        }
+        return expr
+    }
+    fn range(&mut self) -> Option<Box<ast::Expr>>{
+        // Ranges don't chain, so '0..n + 1' is the only form
+        let expr = self.bitwise_or();
+        if self.check_next_tokens(vec![token::TokenType::DotDot, token::TokenType::DotDotEqual]) {
+            let operator = self.previous();
+            let right = self.bitwise_or();
+            return Some(Box::new(ast::Expr::binary(expr, right, operator)));
+        }
+        return expr
+    }
+
+    // Bitwise operators bind tighter than comparisons, like in Python, so 'flags & MASK == 0' needs no parentheses
+    fn bitwise_or(&mut self) -> Option<Box<ast::Expr>>{
+        let mut expr = self.bitwise_xor();
+        while self.check_next_tokens(vec![token::TokenType::Pipe]) {
+            let operator = self.previous();
+            let right = self.bitwise_xor();
+            expr = Some(Box::new(ast::Expr::binary(expr, right, operator)));
+        }
+        return expr
+    }
+    fn bitwise_xor(&mut self) -> Option<Box<ast::Expr>>{
+        let mut expr = self.bitwise_and();
+        while self.check_next_tokens(vec![token::TokenType::Caret]) {
+            let operator = self.previous();
+            let right = self.bitwise_and();
+            expr = Some(Box::new(ast::Expr::binary(expr, right, operator)));
+        }
+        return expr
+    }
+    fn bitwise_and(&mut self) -> Option<Box<ast::Expr>>{
+        let mut expr = self.shift();
+        while self.check_next_tokens(vec![token::TokenType::Ampersand]) {
+            let operator = self.previous();
+            let right = self.shift();
+            expr = Some(Box::new(ast::Expr::binary(expr, right, operator)));
+        }
+        return expr
+    }
+    fn shift(&mut self) -> Option<Box<ast::Expr>>{
+        let mut expr = self.term();
+        while self.check_next_tokens(vec![token::TokenType::LessLess, token::TokenType::GreaterGreater]) {
             let operator = self.previous();
             let right = self.term();
             expr = Some(Box::new(ast::Expr::binary(expr, right, operator)));
@@ -297,7 +520,7 @@ impl Parser {
 
     fn term(&mut self) -> Option<Box<ast::Expr>>{
         let mut expr = self.factor();
-        while self.check_next_tokens(vec![token::TokenType::Minus, token::TokenType::Plus]) {
+        while self.check_next_tokens(vec![token::TokenType::Minus, token::TokenType::Plus, token::TokenType::PlusPlus]) {
             let operator = self.previous();
             let right = self.factor();
             expr = Some(Box::new(ast::Expr::binary(expr, right, operator)));
@@ -308,7 +531,7 @@ impl Parser {
     fn factor(&mut self) -> Option<Box<ast::Expr>>{
         //let mut expr = self.unary();
         let mut expr = self.array_creation();
-        while self.check_next_tokens(vec![token::TokenType::Slash, token::TokenType::Star]) {
+        while self.check_next_tokens(vec![token::TokenType::Slash, token::TokenType::Star, token::TokenType::Percent, token::TokenType::TildeSlash]) {
             let operator = self.previous();
             //let right = self.unary();
             let right = self.array_creation();
@@ -330,16 +553,19 @@ impl Parser {
                 }
             }
             let parentheses = self.consume(token::TokenType::RightBracket, ari_errors::ErrorType::ExpectRightBracket);
-            return Some(Box::new(ast::Expr::array_creation(parentheses, array_values)));
+            // Array literals can be indexed and have methods too, such as '[1, 2].map(f)'
+            return self.call(Some(Box::new(ast::Expr::array_creation(parentheses, array_values))));
         }
         return self.unary();
     }
 
 
     fn unary(&mut self) -> Option<Box<ast::Expr>>{
+        limits::check_stack(&self.peek()); // Such as '- - - 1', which does not go through expression()
         if self.check_next_tokens(vec![token::TokenType::Bang, token::TokenType::Minus]) {
             let operator = self.previous();
-            let right = self.unary();
+            // Through array_creation(), so that array literals such as -[1, 2] can be negated
+            let right = self.array_creation();
             return Some(Box::new(ast::Expr::unary(right, operator)));
         }
         //return self.call();
@@ -349,32 +575,53 @@ impl Parser {
     // Array access
     fn array_access(&mut self) -> Option<Box<ast::Expr>>{
         let expr = self.primary(); //  Array reference
-        if self.check_next_tokens(vec![token::TokenType::LeftBracket]) {
-            if self.check(token::TokenType::RightBracket) {
-                self.print_error(ari_errors::ErrorType::NoArrayAccessIndex);
-                panic!();
-            }
-            else {
-                let index_expr = self.expression(); // Array index expression
-                if self.check_next_tokens(vec![token::TokenType::Comma]) {
-                    self.previous().print_error(ari_errors::ErrorType::ArrayAccessComma);
-                }
-                let brackets = self.consume(token::TokenType::RightBracket, ari_errors::ErrorType::ExpectRightBracket);
-                return Some(Box::new(ast::Expr::array_access(expr, index_expr, brackets)));
+        return self.call(expr);
+    }
+    fn finish_access(&mut self, expr: Option<Box<ast::Expr>>) -> Option<Box<ast::Expr>>{
+        if self.check(token::TokenType::RightBracket) {
+            self.print_error(ari_errors::ErrorType::NoArrayAccessIndex);
+            panic!();
+        }
+        // Slice bounds may be omitted, such as 's[:3]'
+        let index_expr = if self.check(token::TokenType::Colon) { Some(Box::new(ast::Expr::none())) } else { self.expression() }; // Array index expression
+        if self.check_next_tokens(vec![token::TokenType::Colon]) {
+            let end_expr = if self.check(token::TokenType::RightBracket) || self.check(token::TokenType::Colon) { Some(Box::new(ast::Expr::none())) } else { self.expression() };
+            let mut step_expr = Some(Box::new(ast::Expr::none()));
+            if self.check_next_tokens(vec![token::TokenType::Colon]) && !self.check(token::TokenType::RightBracket) {
+                step_expr = self.expression();
             }
+            let brackets = self.consume(token::TokenType::RightBracket, ari_errors::ErrorType::ExpectRightBracket);
+            let bounds = vec![index_expr.unwrap(), end_expr.unwrap(), step_expr.unwrap()];
+            return Some(Box::new(ast::Expr::slice(expr, bounds, brackets)));
         }
-        else {
-            return self.call(expr);
+        if self.check_next_tokens(vec![token::TokenType::Comma]) {
+            self.previous().print_error(ari_errors::ErrorType::ArrayAccessComma);
         }
+        let brackets = self.consume(token::TokenType::RightBracket, ari_errors::ErrorType::ExpectRightBracket);
+        return Some(Box::new(ast::Expr::array_access(expr, index_expr, brackets)));
     }
 
-    // Function calling/invocation
+    // Function calling/invocation, and indexing, which can be chained such as 'm[1][2]' or 'handlers[0](x)'
     fn call(&mut self, mut expr: Option<Box<ast::Expr>>) -> Option<Box<ast::Expr>>{
         //let mut expr = self.primary();
         loop {
             if self.check_next_tokens(vec![token::TokenType::LeftParen]) {
                 expr = self.finish_call(expr);
             }
+            else if self.check_next_tokens(vec![token::TokenType::LeftBracket]) {
+                expr = self.finish_access(expr);
+            }
+            else if self.check_next_tokens(vec![token::TokenType::Dot]) {
+                // Method call, such as 'arr.map(f)', which calls 'map(arr, f)'
+                let name = self.consume(token::TokenType::Identifier, ari_errors::ErrorType::ExpectFunctionName);
+                self.consume(token::TokenType::LeftParen, ari_errors::ErrorType::ExpectLeftParen);
+                let mut method_call = self.finish_call(Some(Box::new(ast::Expr::variable(name))));
+                if let Some(call) = method_call.as_mut() {
+                    call.expr_type = ast::ExprType::MethodCall;
+                    call.arguments.insert(0, expr.unwrap());
+                }
+                expr = method_call;
+            }
             else {
                 break;
             }
@@ -388,7 +635,33 @@ impl Parser {
                 if arguments.len()  >= 255 {
                     self.print_error(ari_errors::ErrorType::TooManyArguments);
                 }
-                arguments.push(self.expression().unwrap());
+                let is_named = self.check(token::TokenType::Identifier)
+                    && self.tokens.get(self.current + 1).map_or(false, |t| t.token_type == token::TokenType::Equal);
+                if is_named {
+                    // Named argument, such as 'linspace(start = 0, end = 1, n = 50)'
+                    let name = self.advance();
+                    self.advance();
+                    if arguments.iter().any(|a| a.expr_type == ast::ExprType::NamedArgument && a.operator.lexeme == name.lexeme) {
+                        name.print_custom_error(ErrorClass::Syntax, &format!("Argument '{}' was given more than once", name.lexeme));
+                    }
+                    arguments.push(Box::new(ast::Expr::named_argument(self.expression(), name)));
+                    if !self.check_next_tokens(vec![token::TokenType::Comma]) {
+                        break;
+                    }
+                    continue;
+                }
+                if arguments.iter().any(|a| a.expr_type == ast::ExprType::NamedArgument) {
+                    let tok = self.peek();
+                    tok.print_custom_error(ErrorClass::Syntax, "Positional arguments must come before named arguments");
+                }
+                if self.check_next_tokens(vec![token::TokenType::DotDotDot]) {
+                    // Spread, such as 'f(...arr)'
+                    let spread_token = self.previous();
+                    arguments.push(Box::new(ast::Expr::spread(self.expression(), spread_token)));
+                }
+                else {
+                    arguments.push(self.expression().unwrap());
+                }
                 if !self.check_next_tokens(vec![token::TokenType::Comma]) {
                     break;
                 }
@@ -401,19 +674,21 @@ impl Parser {
     fn primary(&mut self) -> Option<Box<ast::Expr>>{
         //println!("->{:?}", self.peek().token_type);
         if self.check_next_tokens(vec![token::TokenType::Null]){
-            return Some(Box::new(ast::Expr::literal(ast::Literal::null())));
+            return Some(self.literal(ast::Literal::null()));
         }
         if self.check_next_tokens(vec![token::TokenType::False]){
-            return Some(Box::new(ast::Expr::literal(ast::Literal::bool(false))));
+            return Some(self.literal(ast::Literal::bool(false)));
         }
         if self.check_next_tokens(vec![token::TokenType::True]) {
-            return Some(Box::new(ast::Expr::literal(ast::Literal::bool(true))));
+            return Some(self.literal(ast::Literal::bool(true)));
         }
         if self.check_next_tokens(vec![token::TokenType::Number]) {
-            return Some(Box::new(ast::Expr::literal(ast::Literal::number(self.previous().literal))));
+            let literal = self.previous().literal.to_string();
+            return Some(self.literal(ast::Literal::number(ast::Number::parse(&literal).unwrap())));
         }
         if self.check_next_tokens(vec![token::TokenType::String]) {
-            return Some(Box::new(ast::Expr::literal(ast::Literal::string(self.previous().literal))));
+            let literal = self.previous().literal.to_string();
+            return Some(self.literal(ast::Literal::string(literal)));
         }
         if self.check_next_tokens(vec![token::TokenType::LeftParen]) {
             let expr = self.expression();
@@ -427,6 +702,13 @@ impl Parser {
         None
     }
 
+    fn literal(&mut self, literal: ast::Literal) -> Box<ast::Expr> {
+        // Keeps the literal's token so that errors can underline it
+        let mut expr = ast::Expr::literal(literal);
+        expr.operator = self.previous();
+        Box::new(expr)
+    }
+
     fn consume(&mut self, token_type: token::TokenType, error_type: ari_errors::ErrorType) -> token::Token {
         if self.check(token_type){
             return self.advance();
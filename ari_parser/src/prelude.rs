@@ -0,0 +1,18 @@
+///////////////////////////////////////////
+// Prelude
+///////////////////////////////////////////
+// Standard library helpers written in Ari itself.
+// The files are compiled into the executable and run before any user code,
+// so their functions are defined in the global environment like the natives.
+
+const PRELUDE: [&str; 2] = [
+    include_str!("prelude/math.ari"),
+    include_str!("prelude/array.ari"),
+];
+
+pub fn load_prelude() {
+    for source in PRELUDE.iter() {
+        crate::run(source, "", 1);
+    }
+    crate::environment::ENV.lock().unwrap().mark_prelude();
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+///////////////////////////////////////////
+// Profiler
+///////////////////////////////////////////
+// 'ari --profile script.ari' counts the calls of every function and the iterations of every loop,
+// along with the time spent in them, then prints a report sorted by time when the script exits.
+// Function::call() and while loops only record while ENABLED is set, so normal runs are not slowed down.
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default)]
+struct Entry {
+    count: usize, // Calls of a function or iterations of a loop
+    total: Duration,
+    active: usize, // Recursive calls which have not returned, so their time is only counted once
+}
+
+lazy_static! {
+    static ref ENTRIES: Mutex<HashMap<String, Entry>> = Mutex::new(HashMap::new());
+}
+
+pub fn start() {
+    ENABLED.store(true, Ordering::SeqCst);
+    ari_errors::add_exit_hook(report);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn enter(name: &str) -> Instant {
+    ENTRIES.lock().unwrap().entry(name.to_owned()).or_default().active += 1;
+    Instant::now()
+}
+
+pub fn exit(name: &str, started: Instant, count: usize) {
+    let elapsed = started.elapsed();
+    let mut entries = ENTRIES.lock().unwrap();
+    let entry = entries.entry(name.to_owned()).or_default();
+    entry.count += count;
+    entry.active = entry.active.saturating_sub(1);
+    if entry.active == 0 {
+        entry.total += elapsed;
+    }
+}
+
+fn report() {
+    let entries = ENTRIES.lock().unwrap();
+    let mut rows: Vec<(&String, &Entry)> = entries.iter().filter(|(_, entry)| entry.count > 0).collect();
+    rows.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(b.0)));
+    eprintln!();
+    eprintln!("Profile (sorted by total time):");
+    eprintln!("{:>12} {:>12} {:>12}  Name", "Total (ms)", "Count", "Average (ms)");
+    for (name, entry) in rows {
+        let total = entry.total.as_secs_f64() * 1000.0;
+        eprintln!("{:>12.3} {:>12} {:>12.4}  {}", total, entry.count, total / entry.count as f64, name);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::ast;
+use crate::limits;
+use ari_errors::ErrorClass;
+use std::collections::HashSet;
+
+///////////////////////////////////////////
+// Resolver
+///////////////////////////////////////////
+// Runs between parsing and evaluation.
+// Binds each variable reference to the number of environments between its use and its declaration,
+// so that EnvManager can index the environment directly instead of searching the whole stack.
+//
+// Scopes mirror the environments created at runtime:
+//      Block    => 1 scope
+//      ForIn    => 1 scope for the loop variable, then the body
+//      Function => the enclosing local scopes, which the closure shares, 1 scope for the arguments, then the body block
+//      Import   => 1 scope for the module, which cannot see the importer's local scopes
+// Top-level variables are not tracked and resolve to the global environment.
+// Also rejects 'return' outside of a function, and 'break'/'continue' outside of a loop.
+
+pub struct Resolver {
+    scopes: Vec<HashSet<String>>,
+    function_depth: usize, // Number of enclosing functions
+    loop_depth: usize, // Number of enclosing loops in the innermost function
+}
+
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver {
+            scopes: Vec::<HashSet<String>>::new(),
+            function_depth: 0,
+            loop_depth: 0,
+        }
+    }
+
+    pub fn with_scopes(scopes: Vec<HashSet<String>>) -> Resolver {
+        // Resolves code which runs inside existing local environments, such as with eval()
+        Resolver {
+            scopes,
+            function_depth: 0,
+            loop_depth: 0,
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &mut Vec<Box<ast::Statement>>) {
+        let was_parsing = ari_errors::set_parsing(true);
+        for s in statements {
+            self.resolve_statement(s);
+        }
+        ari_errors::set_parsing(was_parsing);
+    }
+
+    fn resolve_statement(&mut self, statement: &mut ast::Statement) {
+        limits::check_stack(&statement.token_name);
+        match statement.statement_type {
+            ast::StatementType::Block => {
+                self.begin_scope();
+                for s in &mut statement.statements {
+                    self.resolve_statement(s);
+                }
+                self.end_scope();
+            },
+            ast::StatementType::Function => {
+                // Declare before resolving the body so that the function can call itself
+                self.declare(&statement.token_name.lexeme);
+                statement.capture_depth = self.scopes.len();
+                self.resolve_function(statement);
+            },
+            ast::StatementType::Let => {
+                // Resolve the initialiser first, so 'let x = x + 1;' refers to the outer 'x'
+                self.resolve_option_expr(&mut statement.expr);
+                for name in statement.declared_names() {
+                    self.declare(&name);
+                }
+            },
+            ast::StatementType::If => {
+                self.resolve_option_expr(&mut statement.expr);
+                self.resolve_option_statement(&mut statement.then_branch);
+                self.resolve_option_statement(&mut statement.else_branch);
+            },
+            ast::StatementType::While => {
+                self.resolve_option_expr(&mut statement.expr);
+                self.loop_depth += 1;
+                self.resolve_option_statement(&mut statement.then_branch);
+                self.loop_depth -= 1;
+            },
+            ast::StatementType::ForIn => {
+                // The loop variable lives in a scope of its own around the body
+                self.resolve_option_expr(&mut statement.expr);
+                self.begin_scope();
+                self.declare(&statement.token_name.lexeme);
+                self.loop_depth += 1;
+                self.resolve_option_statement(&mut statement.then_branch);
+                self.loop_depth -= 1;
+                self.end_scope();
+            },
+            ast::StatementType::Return => {
+                if self.function_depth == 0 {
+                    statement.token_name.print_custom_error(ErrorClass::Syntax, "'return' can only be used inside a function");
+                }
+                self.resolve_option_expr(&mut statement.expr);
+            },
+            ast::StatementType::Expression |
+            ast::StatementType::Print | ast::StatementType::Println | ast::StatementType::Bai => {
+                self.resolve_option_expr(&mut statement.expr);
+            },
+            ast::StatementType::Import => {
+                // A module only sees its own top-level scope and the globals
+                let enclosing_scopes = std::mem::replace(&mut self.scopes, vec![HashSet::<String>::new()]);
+                let enclosing_depths = (std::mem::replace(&mut self.function_depth, 0), std::mem::replace(&mut self.loop_depth, 0));
+                // Functions can call the ones declared after them, as they can at the top level of a script
+                for s in statement.statements.iter().filter(|s| s.statement_type == ast::StatementType::Function) {
+                    self.declare(&s.token_name.lexeme);
+                }
+                for s in &mut statement.statements {
+                    self.resolve_statement(s);
+                }
+                self.scopes = enclosing_scopes;
+                (self.function_depth, self.loop_depth) = enclosing_depths;
+                for name in ast::Statement::exported_names(&statement.statements) {
+                    self.declare(&name);
+                }
+            },
+            ast::StatementType::Break | ast::StatementType::Continue => {
+                if self.loop_depth == 0 {
+                    let keyword = if statement.statement_type == ast::StatementType::Break { "break" } else { "continue" };
+                    statement.token_name.print_custom_error(ErrorClass::Syntax, &format!("'{}' can only be used inside a loop", keyword));
+                }
+            },
+        }
+    }
+
+    fn resolve_function(&mut self, statement: &mut ast::Statement) {
+        // The enclosing local scopes are pushed again when the function is called, so they keep their depths
+        // A loop around the declaration cannot be left from inside the function
+        let enclosing_loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        self.function_depth += 1;
+        self.begin_scope();
+        for (i, arg) in statement.tokens.iter().enumerate() {
+            // A default is evaluated before its own argument is defined, but after the ones before it
+            if let Some(default) = statement.defaults.get_mut(i) {
+                self.resolve_expr(default);
+            }
+            self.declare(&arg.lexeme);
+        }
+        self.resolve_option_statement(&mut statement.then_branch);
+        self.end_scope();
+        self.function_depth -= 1;
+        self.loop_depth = enclosing_loop_depth;
+    }
+
+    fn resolve_expr(&mut self, expr: &mut ast::Expr) {
+        limits::check_stack(&expr.operator);
+        match expr.expr_type {
+            ast::ExprType::Variable | ast::ExprType::Assign | ast::ExprType::ArrayAssign => {
+                // 'operator' holds the variable token
+                expr.depth = self.resolve_local(&expr.operator.lexeme);
+            },
+            _ => {}
+        }
+        for arg in &mut expr.arguments {
+            self.resolve_expr(arg);
+        }
+        self.resolve_option_expr(&mut expr.left);
+        self.resolve_option_expr(&mut expr.right);
+    }
+
+    fn resolve_local(&mut self, name: &str) -> Option<usize> {
+        // Number of scopes between the innermost scope and the declaration
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains(name) {
+                return Some(depth);
+            }
+        }
+        // Global
+        return None;
+    }
+
+    fn resolve_option_expr(&mut self, expr: &mut Option<Box<ast::Expr>>) {
+        if let Some(e) = expr.as_mut() {
+            self.resolve_expr(e);
+        }
+    }
+    fn resolve_option_statement(&mut self, statement: &mut Option<Box<ast::Statement>>) {
+        if let Some(s) = statement.as_mut() {
+            self.resolve_statement(s);
+        }
+    }
+
+    fn declare(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_owned());
+        }
+    }
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashSet::<String>::new());
+    }
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+}
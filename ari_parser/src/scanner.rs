@@ -1,34 +1,54 @@
 use crate::token;
 use ari_errors;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct Scanner <'a>{
     source: &'a str,
+    chars: Vec<char>, // Positions such as 'start' and 'current' count characters, not bytes
+    file: &'a str,
     pub tokens: Vec<token::Token>,
     start: usize,
     current: usize,
     line_index: usize,
     line_number: usize,
+    first_line_number: usize,
+    doc_comment: Vec<String>, // Lines of the '///' comments since the last token, given to the next 'fn'
 }
 impl Scanner <'_>{
-    pub fn new<'a>(source: &'a str, line_number: usize) -> Scanner<'a> {
+    pub fn new<'a>(source: &'a str, file: &'a str, line_number: usize) -> Scanner<'a> {
         Scanner {
             source: source,
+            chars: source.chars().collect(),
+            file,
             tokens: Vec::<token::Token>::new(),
             start: 0,
             current: 0,
             line_index: 0,
             line_number,
+            first_line_number: line_number,
+            doc_comment: Vec::<String>::new(),
         }
     }
 
     pub fn scan_tokens(&mut self) -> Vec<token::Token> {
+        let was_parsing = ari_errors::set_parsing(true);
         while !self.is_at_end() {
             self.start = self.current;
             self.scan_token();
         }
         let line = self.get_current_line();
-        self.tokens.push(token::Token::new(token::TokenType::Eof, "", "", self.line_number, self.current - self.line_index, &line));
+        // Just after the end of the last line, where a missing ';' or '}' would be
+        let end = self.current - self.line_index + 1;
+        self.tokens.push(token::Token::new(token::TokenType::Eof, "", "", self.file, self.line_number, end, end, &line));
+        // Tokens are given their line once it is complete, the tokens of a line share it
+        let lines: Vec<Arc<str>> = self.source.lines().map(Arc::from).collect();
+        for tok in self.tokens.iter_mut() {
+            if let Some(line) = tok.line_number.checked_sub(self.first_line_number).and_then(|index| lines.get(index)) {
+                tok.source = Arc::clone(line);
+            }
+        }
+        ari_errors::set_parsing(was_parsing);
         return self.tokens.clone();
     }
 
@@ -40,6 +60,7 @@ impl Scanner <'_>{
             '<' => '=',
             '>' => '=',
             '/' => '/',
+            '+' => '+',
             _ => '\0'
         });
         match c {
@@ -50,11 +71,31 @@ impl Scanner <'_>{
             '{' => {self.add_token(token::TokenType::LeftBrace, "");},
             '}' => {self.add_token(token::TokenType::RightBrace, "");},
             ',' => {self.add_token(token::TokenType::Comma, "");},
-            '.' => {self.add_token(token::TokenType::Dot, "");},
+            '.' => {
+                let token_type = if !self.check_next_token('.') {token::TokenType::Dot}
+                    else if self.check_next_token('=') {token::TokenType::DotDotEqual}
+                    else if self.check_next_token('.') {token::TokenType::DotDotDot}
+                    else {token::TokenType::DotDot};
+                self.add_token(token_type, "");},
             '-' => {self.add_token(token::TokenType::Minus, "");},
-            '+' => {self.add_token(token::TokenType::Plus, "");},
+            '+' => {self.add_token(
+                if next_c_bool {token::TokenType::PlusPlus}
+                else {token::TokenType::Plus}
+                , "");},
             ';' => {self.add_token(token::TokenType::Semicolon, "");},
             '*' => {self.add_token(token::TokenType::Star, "");},
+            '%' => {self.add_token(token::TokenType::Percent, "");},
+            ':' => {self.add_token(token::TokenType::Colon, "");},
+            '&' => {self.add_token(token::TokenType::Ampersand, "");},
+            '|' => {self.add_token(token::TokenType::Pipe, "");},
+            '^' => {self.add_token(token::TokenType::Caret, "");},
+            '~' => {
+                if self.check_next_token('/') {
+                    self.add_token(token::TokenType::TildeSlash, "");
+                }
+                else {
+                    self.print_error(ari_errors::ErrorType::UnknownToken);
+                }},
             '!' => {self.add_token(
                 if next_c_bool {token::TokenType::BangEqual}
                 else {token::TokenType::Bang}
@@ -63,19 +104,30 @@ impl Scanner <'_>{
                 if next_c_bool {token::TokenType::EqualEqual}
                 else {token::TokenType::Equal}
                 , "");},
-            '<' => {self.add_token(
-                if next_c_bool {token::TokenType::LessEqual}
-                else {token::TokenType::Less}
-                , "");},
-            '>' => {self.add_token(
-                if next_c_bool {token::TokenType::GreaterEqual}
-                else {token::TokenType::Greater}
-                , "");},
+            '<' => {
+                let token_type = if next_c_bool {token::TokenType::LessEqual}
+                    else if self.check_next_token('<') {token::TokenType::LessLess}
+                    else {token::TokenType::Less};
+                self.add_token(token_type, "");},
+            '>' => {
+                let token_type = if next_c_bool {token::TokenType::GreaterEqual}
+                    else if self.check_next_token('>') {token::TokenType::GreaterGreater}
+                    else {token::TokenType::Greater};
+                self.add_token(token_type, "");},
             '/' => {
                 if next_c_bool {
+                    // '///' is a doc comment, but '////' is not
+                    let is_doc = self.peek() == '/' && self.peek_next() != '/';
                     while self.peek() != '\n' && !self.is_at_end(){
                         self.advance();
                     }
+                    if is_doc {
+                        let text = self.text(self.start + 3, self.current).trim().to_owned();
+                        self.doc_comment.push(text);
+                    }
+                }
+                else if self.check_next_token('*') {
+                    self.consume_block_comment();
                 }
                 else {
                     self.add_token(token::TokenType::Slash, "");
@@ -104,10 +156,13 @@ impl Scanner <'_>{
         }
     }
     fn get_char(&mut self, index: usize) -> char{
-        return self.source.chars().nth(index).unwrap();
+        return self.chars[index];
+    }
+    fn text(&self, start: usize, end: usize) -> String {
+        self.chars[start..end].iter().collect()
     }
     fn get_current_line(&mut self) -> String {
-        return self.source[self.line_index .. self.current].to_owned();
+        return self.text(self.line_index, self.current);
     }
     fn advance_line(&mut self) {
         self.line_index = self.current;
@@ -118,9 +173,15 @@ impl Scanner <'_>{
         return self.get_char(self.current - 1);
     }
     fn add_token(&mut self, token_type: token::TokenType, literal: &str){
-        let text = &self.source[self.start..self.current];
-        let line = self.get_current_line();
-        self.tokens.push(token::Token::new(token_type, text, literal, self.line_number, self.current - self.line_index, &line));
+        let text = self.text(self.start, self.current);
+        // A string may start on an earlier line, in which case it is underlined from the start of this line
+        let start = if self.start >= self.line_index { self.start - self.line_index + 1 } else { 1 };
+        // The line is filled in by scan_tokens(), once all of it has been scanned
+        self.tokens.push(token::Token::new(token_type, &text, literal, self.file, self.line_number, start, self.current - self.line_index, ""));
+        if token_type != token::TokenType::Export {
+            // A doc comment belongs to the declaration right after it, 'export' may come in between
+            self.doc_comment.clear();
+        }
         //println!("Line {}\n", self.line_number);
     }
     fn check_next_token(&mut self, expected : char) -> bool{
@@ -137,7 +198,7 @@ impl Scanner <'_>{
         return self.get_char(self.current);
     }
     fn peek_next(&mut self) -> char{
-        if self.current + 1 >= self.source.len(){
+        if self.current + 1 >= self.chars.len(){
             return '\0';
         }
         return self.get_char(self.current + 1);
@@ -145,7 +206,10 @@ impl Scanner <'_>{
     fn consume_string_lexeme(&mut self){
         while self.peek() != '"' && !self.is_at_end(){
             if self.peek() == '\n'{
-                self.line_number += 1;
+                // Columns on the following lines of the string are counted from the start of their line
+                self.advance();
+                self.advance_line();
+                continue;
             }
             self.advance();
         }
@@ -153,30 +217,103 @@ impl Scanner <'_>{
             self.print_error(ari_errors::ErrorType::ConsumeStringLexeme);
         }
         self.advance();
-        self.add_token(token::TokenType::String, &self.source[self.start + 1 .. self.current - 1].to_owned());
+        self.add_token(token::TokenType::String, &self.text(self.start + 1, self.current - 1));
+    }
+    fn consume_block_comment(&mut self){
+        // Comments nest, so '/* a /* b */ c */' is a single comment
+        let opening = (self.line_number, self.line_index);
+        let mut depth = 1;
+        while !self.is_at_end() {
+            if self.peek() == '/' && self.peek_next() == '*' {
+                self.current += 2;
+                depth += 1;
+            }
+            else if self.peek() == '*' && self.peek_next() == '/' {
+                self.current += 2;
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            }
+            else if self.advance() == '\n' {
+                self.advance_line();
+            }
+        }
+        // Point to the opening '/*' rather than the end of the script
+        let end = (self.line_number, self.line_index, self.current);
+        (self.line_number, self.line_index) = opening;
+        self.current = self.start + 2;
+        self.print_error(ari_errors::ErrorType::UnterminatedComment);
+        (self.line_number, self.line_index, self.current) = end;
     }
     fn consume_number_lexeme(&mut self){
-        while self.peek().is_numeric() {
+        // '0x', '0b' and '0o' prefixes, '_' separators and exponents such as '1e-3' are normalised into a decimal literal
+        let radix = match (self.get_char(self.start), self.peek()) {
+            ('0', 'x') | ('0', 'X') => 16,
+            ('0', 'b') | ('0', 'B') => 2,
+            ('0', 'o') | ('0', 'O') => 8,
+            _ => 10
+        };
+        if radix != 10 {
+            self.advance(); // Consume the prefix
+            while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+                self.advance();
+            }
+            let digits: String = self.chars[self.start + 2 .. self.current].iter().filter(|&&c| c != '_').collect();
+            match i64::from_str_radix(&digits, radix) {
+                Ok(value) => self.add_token(token::TokenType::Number, &value.to_string()),
+                Err(_) => self.print_error(ari_errors::ErrorType::InvalidNumber)
+            }
+            return;
+        }
+        while self.peek().is_numeric() || self.peek() == '_' {
             self.advance();
         }
         if self.peek() == '.' && self.peek_next().is_numeric() {
             self.advance(); // Consume the '.'
+            while self.peek().is_numeric() || self.peek() == '_' {
+                self.advance();
+            }
+        }
+        let has_exponent = (self.peek() == 'e' || self.peek() == 'E') && match self.peek_next() {
+            '+' | '-' => self.current + 2 < self.chars.len() && self.get_char(self.current + 2).is_numeric(),
+            c => c.is_numeric()
+        };
+        if has_exponent {
+            self.advance(); // Consume the 'e'
+            if self.peek() == '+' || self.peek() == '-' {
+                self.advance();
+            }
             while self.peek().is_numeric() {
                 self.advance();
             }
         }
-        self.add_token(token::TokenType::Number, &self.source[self.start .. self.current].to_owned());
+        let text = self.text(self.start, self.current);
+        if has_exponent {
+            // Kept as written, the parser reads it as a float
+            let digits = text.replace('_', "");
+            match digits.parse::<f64>() {
+                Ok(_) => self.add_token(token::TokenType::Number, &digits),
+                Err(_) => self.print_error(ari_errors::ErrorType::InvalidNumber)
+            }
+        }
+        else {
+            // Kept as written, so that integers beyond the precision of f64 stay exact
+            self.add_token(token::TokenType::Number, &text.replace('_', ""));
+        }
     }
     fn consume_identifier(&mut self){
         while self.peek().is_alphanumeric() || self.peek() == '_' {
             self.advance();
         }
-        let text = &self.source[self.start .. self.current].to_owned();
-        let keyword_type = match self.get_reserved_keyword(text){
+        let text = self.text(self.start, self.current);
+        let keyword_type = match self.get_reserved_keyword(&text){
             Some(keyword) => {keyword},
             None => {token::TokenType::Identifier}
         };
-        self.add_token(keyword_type, "");
+        // The doc comment is kept as the literal of 'fn' until the parser stores it in the declaration
+        let literal = if keyword_type == token::TokenType::Fn { self.doc_comment.join("\n") } else { String::new() };
+        self.add_token(keyword_type, &literal);
     }
 
     fn get_reserved_keyword(&mut self, keyword : &str) -> Option<token::TokenType>{
@@ -197,21 +334,26 @@ impl Scanner <'_>{
             "this" => Some(token::TokenType::This),
             "true" => Some(token::TokenType::True),
             "let" => Some(token::TokenType::Let),
+            "const" => Some(token::TokenType::Const),
             "while" => Some(token::TokenType::While),
+            "in" => Some(token::TokenType::In),
             "bai" => Some(token::TokenType::Bai),
             "break" => Some(token::TokenType::Break),
             "continue" => Some(token::TokenType::Continue),
+            "import" => Some(token::TokenType::Import),
+            "export" => Some(token::TokenType::Export),
             _ => None
         }
     }
     fn is_at_end(&mut self)-> bool{
-        return self.current >= self.source.chars().count();
+        return self.current >= self.chars.len();
     }
     fn is_alpha(&mut self, c : char) -> bool{
         c.is_alphabetic() || c == '_'
     }
 
     fn print_error(&mut self, error: ari_errors::ErrorType){
-        ari_errors::print_error(error, &self.get_current_line(), self.current - self.line_index, self.line_number)
+        let index = self.current - self.line_index;
+        ari_errors::print_error_span(error, self.file, &self.get_current_line(), index, index, self.line_number)
     }
 }
\ No newline at end of file
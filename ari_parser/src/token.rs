@@ -1,15 +1,49 @@
 use ari_errors;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+// Longer text, such as a big string literal, is not interned so that it can be freed
+const MAX_INTERNED_LENGTH: usize = 64;
+
+lazy_static! {
+    // Names, keywords and short literals which tokens share, so cloning a token never copies its text
+    static ref INTERNED: Mutex<HashSet<Arc<str>>> = Mutex::new(HashSet::new());
+}
+
+pub fn intern(text: &str) -> Arc<str> {
+    if text.len() > MAX_INTERNED_LENGTH {
+        return Arc::from(text);
+    }
+    let mut interned = INTERNED.lock().unwrap();
+    match interned.get(text) {
+        Some(shared) => Arc::clone(shared),
+        None => {
+            let shared: Arc<str> = Arc::from(text);
+            interned.insert(Arc::clone(&shared));
+            shared
+        }
+    }
+}
 
 #[allow(dead_code)]
 #[derive(Debug)]
 #[derive(Clone, Copy)]
 #[derive(PartialEq)] // For equality comparisons
+#[derive(Serialize, Deserialize)]
 pub enum TokenType {
     LeftBracket, RightBracket, // Square Brackets
     LeftParen, RightParen, LeftBrace, RightBrace,
-    Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+    Comma, Dot, Minus, Plus, Semicolon, Slash, Star, Percent,
+    Ampersand, Pipe, Caret, // Bitwise and, or, xor
+    Colon, // Slices
   
     // One or two character tokens.
+    PlusPlus, // Array concatenation
+    TildeSlash, // Floor division, since '//' starts a comment
+    LessLess, GreaterGreater, // Bit shifts
+    DotDot, DotDotEqual, // Ranges
+    DotDotDot, // Variadic arguments and spreading
     Bang, BangEqual,
     Equal, EqualEqual,
     Greater, GreaterEqual,
@@ -22,6 +56,13 @@ pub enum TokenType {
     And, Class, Else, False, For, Fn, If, Null, Or,
     Print, Println, Return, Super, This, True, Let, While,
     Bai, // Quit
+    Const, // 'let' which cannot be reassigned
+
+    // Module keywords
+    Import, Export,
+
+    // 'for (x in items)'
+    In,
 
     // Loop keywords
     Break, // Quit while loop
@@ -36,36 +77,41 @@ pub enum TokenType {
 }
 
 #[derive(Debug)]
+#[derive(Serialize, Deserialize)]
 pub struct Token{
     pub token_type: TokenType,
-    pub lexeme: String,
-    pub literal: String, // Either number or string
+    pub lexeme: Arc<str>,
+    pub literal: Arc<str>, // Either number or string
+    pub file: Arc<str>, // Script or module which the token was scanned from, empty in the interpreter
     pub line_number: usize,
-    pub index: usize,
-    pub source: String,
+    pub start: usize, // 1-based column of the first character
+    pub index: usize, // 1-based column of the last character
+    pub source: Arc<str>, // Line of code which the token ends on, shared by the tokens of that line
 }
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: &str, literal: &str, line_number: usize, index: usize, source: &str) -> Token {
+    pub fn new(token_type: TokenType, lexeme: &str, literal: &str, file: &str, line_number: usize, start: usize, index: usize, source: &str) -> Token {
         //println!("{:?}", token_type);
         Token {
             token_type,
-            lexeme: lexeme.to_owned(), // Name of variables/keywords/arguments etc
-            literal: literal.to_owned(), // Value such as string/number/bool etc
+            lexeme: intern(lexeme), // Name of variables/keywords/arguments etc
+            literal: intern(literal), // Value such as string/number/bool etc
+            file: intern(file),
             line_number,
+            start,
             index,
-            source: source.to_owned(),
+            source: Arc::from(source),
         }
     }
     pub fn none() -> Token{
         //println!("none");
-        Token::new(TokenType::None, "", "", 0, 0, "")
+        Token::new(TokenType::None, "", "", "", 0, 0, 0, "")
     }
 
     pub fn print_error(&self, error: ari_errors::ErrorType) {
-        ari_errors::print_error(error, &self.source, self.index + 1, self.line_number);
+        ari_errors::print_error_span(error, &self.file, &self.source, self.start, self.index, self.line_number);
     }
-    pub fn print_custom_error(&self, message: &str) {
-        ari_errors::print_custom_error(message, &self.source, self.index + 1, self.line_number);
+    pub fn print_custom_error(&self, class: ari_errors::ErrorClass, message: &str) {
+        ari_errors::print_custom_error_span(class, message, &self.file, &self.source, self.start, self.index, self.line_number);
     }
 }
 
@@ -73,11 +119,13 @@ impl Clone for Token { // Enables Token to be copied
     fn clone(&self) -> Token {
         Token {
             token_type: self.token_type,
-            lexeme: self.lexeme.clone(),
-            literal: self.literal.clone(),
+            lexeme: Arc::clone(&self.lexeme),
+            literal: Arc::clone(&self.literal),
+            file: Arc::clone(&self.file),
             line_number: self.line_number,
+            start: self.start,
             index: self.index,
-            source: self.source.clone(),
+            source: Arc::clone(&self.source),
         }
     }
 }
@@ -0,0 +1,92 @@
+use crate::ast;
+use crate::fmt;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+///////////////////////////////////////////
+// Tracing
+///////////////////////////////////////////
+// 'ari --trace script.ari' logs every statement before it runs and every expression with the Literal it evaluated to.
+// The log goes to stderr so it can be separated from the script's own output.
+// evaluate_statement() and evaluate_expr() only come here while ENABLED is set, so normal runs are not slowed down.
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static DEPTH: Cell<usize> = Cell::new(0); // Nesting of the statement/expression being evaluated
+    static LINE: Cell<usize> = Cell::new(0); // Line of the innermost statement
+}
+
+struct Nested {}
+impl Nested {
+    fn enter() -> Nested {
+        DEPTH.with(|depth| depth.set(depth.get() + 1));
+        Nested {}
+    }
+}
+impl Drop for Nested {
+    // Also runs when an error unwinds the evaluation
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
+
+pub fn start() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn statement(statement: &ast::Statement) -> ast::Literal {
+    // Statements made by the parser, like the increment of a 'for' loop, have no line of their own
+    let outer_line = LINE.with(|line| line.get());
+    if statement.line_number != 0 {
+        LINE.with(|line| line.set(statement.line_number));
+    }
+    // Blocks only group other statements, so they are not logged
+    let result = if statement.statement_type == ast::StatementType::Block {
+        statement.execute()
+    }
+    else {
+        log(&fmt::format_statement(statement));
+        let _nested = Nested::enter();
+        statement.execute()
+    };
+    // Back to the caller's line after the body of a function
+    if statement.line_number != 0 {
+        LINE.with(|line| line.set(outer_line));
+    }
+    result
+}
+
+pub fn expr(expr: &ast::Expr) -> ast::Literal {
+    // Literals evaluate to themselves, so logging them is only noise
+    if expr.expr_type == ast::ExprType::Literal {
+        return expr.execute();
+    }
+    let result = {
+        let _nested = Nested::enter();
+        expr.execute()
+    };
+    log(&format!("{} => {}", fmt::format_expr(expr), describe(&result)));
+    result
+}
+
+fn describe(literal: &ast::Literal) -> String {
+    match literal.literal_type {
+        ast::LiteralType::Array => {
+            let values: Vec<&str> = literal.array_values.iter().map(|value| value.value.as_str()).collect();
+            format!("{} [{}]", literal.describe(), values.join(", "))
+        },
+        ast::LiteralType::None => "None".to_string(),
+        _ => literal.describe(),
+    }
+}
+
+fn log(text: &str) {
+    let depth = DEPTH.with(|depth| depth.get());
+    let line = LINE.with(|line| line.get());
+    eprintln!("[trace] line {:>4} | {}{}", line, "  ".repeat(depth), text);
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use ari_parser::Limits;
+use common::{eval, run_with_limits};
+use std::time::Duration;
+
+#[test]
+fn reduce_accepts_an_initial_value_of_any_type() {
+    assert_eq!(eval(r#"fn count_chars(total, s) { return total + length(s); } reduce(["ab", "c"], 0, count_chars);"#), "3");
+    assert_eq!(eval(r#"fn join(text, n) { return text + to_string(n); } reduce([1, 2], "", join);"#), "12");
+}
+
+#[test]
+fn pop_returns_the_removed_element() {
+    assert_eq!(eval("let arr = [1, 2, 3]; let last = null; arr, last = pop(arr); [arr, last];"), "[[1, 2], 3]");
+    assert_eq!(eval("pop([]);"), "[[], null]");
+}
+
+#[test]
+fn push_does_not_change_other_variables() {
+    assert_eq!(eval("let a = [1]; let b = a; a = push(a, 2); [a, b];"), "[[1, 2], [1]]");
+    assert_eq!(eval("let a = [1]; fn grow() { a = push(a, 9); return 2; } a = push(a, grow()); a;"), "[1, 2]");
+}
+
+#[test]
+fn push_loops_take_linear_time() {
+    // Copying the array on every push would take far longer than the timeout
+    let limits = Limits { timeout: Some(Duration::from_secs(10)), ..Limits::default() };
+    let source = "let arr = []; let i = 0; while (i < 50000) { arr = push(arr, i); i = i + 1; } length(arr);";
+    assert_eq!(run_with_limits(source, limits).0.unwrap(), "50000");
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::eval;
+
+#[test]
+fn closures_share_the_variables_they_capture() {
+    let source = "
+        fn make_counter() {
+            let count = 0;
+            fn increment() { count = count + 1; return count; }
+            return increment;
+        }
+        let counter = make_counter();
+        counter(); counter();
+        counter();
+    ";
+    assert_eq!(eval(source), "3");
+}
+
+#[test]
+fn two_closures_over_the_same_variable_see_each_others_changes() {
+    let source = "
+        fn make_pair() {
+            let value = 0;
+            fn set(v) { value = v; }
+            fn get() { return value; }
+            return [set, get];
+        }
+        let (set, get) = make_pair();
+        set(5);
+        get();
+    ";
+    assert_eq!(eval(source), "5");
+}
+
+#[test]
+fn each_call_captures_a_new_scope() {
+    let source = "
+        fn make_counter() {
+            let count = 0;
+            fn increment() { count = count + 1; return count; }
+            return increment;
+        }
+        let first = make_counter();
+        let second = make_counter();
+        first(); first();
+        [first(), second()];
+    ";
+    assert_eq!(eval(source), "[3, 1]");
+}
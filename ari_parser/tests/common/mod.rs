@@ -0,0 +1,47 @@
+// Helpers shared by the integration tests, which each run as their own process
+#![allow(dead_code)] // Each test file uses some of the helpers
+
+use ari_parser::{eval_source_with_output, set_limits, spawn_with_stack, Error, Limits};
+use std::sync::Mutex;
+
+// The environment, captured output and limits are global, so snippets run one at a time.
+// Each runs on a thread started by spawn_with_stack(), so deep recursion is caught by the stack checks.
+static LOCK: Mutex<()> = Mutex::new(());
+const STACK_SIZE: usize = 16 * 1024 * 1024;
+
+pub fn run_with_limits(source: &str, limits: Limits) -> (Result<String, Error>, String) {
+    // The value of the last statement as text, such as '[1, "a"]', and everything printed
+    let _lock = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let source = source.to_owned();
+    spawn_with_stack(STACK_SIZE, move || {
+        set_limits(limits);
+        let (result, output) = eval_source_with_output(&source);
+        (result.map(|literal| literal.to_text()), output)
+    }).join().unwrap()
+}
+
+pub fn eval(source: &str) -> String {
+    match run_with_limits(source, Limits::default()).0 {
+        Ok(value) => value,
+        Err(error) => panic!("{} failed with {}", source, error),
+    }
+}
+
+pub fn output(source: &str) -> String {
+    let (result, output) = run_with_limits(source, Limits::default());
+    if let Err(error) = result {
+        panic!("{} failed with {}", source, error);
+    }
+    output
+}
+
+pub fn error_with_limits(source: &str, limits: Limits) -> Error {
+    match run_with_limits(source, limits).0 {
+        Ok(value) => panic!("{} returned {} instead of an error", source, value),
+        Err(error) => error,
+    }
+}
+
+pub fn error(source: &str) -> Error {
+    error_with_limits(source, Limits::default())
+}
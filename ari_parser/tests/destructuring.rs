@@ -0,0 +1,20 @@
+mod common;
+
+use common::{error, eval};
+
+#[test]
+fn let_destructures_arrays() {
+    assert_eq!(eval("let (a, b) = [1, 2]; [b, a];"), "[2, 1]");
+    assert_eq!(eval("fn pair() { return [\"x\", 3]; } let (name, count) = pair(); name + to_string(count);"), "x3");
+}
+
+#[test]
+fn multiple_assignment_swaps() {
+    assert_eq!(eval("let a = 1; let b = 2; a, b = b, a; [a, b];"), "[2, 1]");
+}
+
+#[test]
+fn lengths_must_match() {
+    assert!(error("let (a, b) = [1, 2, 3];").message.contains("cannot be destructured"));
+    assert!(error("let (a, b) = 5;").message.contains("cannot be destructured"));
+}
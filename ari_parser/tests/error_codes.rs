@@ -0,0 +1,26 @@
+mod common;
+
+use common::error;
+
+#[test]
+fn each_class_of_error_has_its_own_code() {
+    assert_eq!(error("1 + true;").code, "E0102");
+    assert_eq!(error("length(5);").code, "E0103");
+    assert_eq!(error("fn add(a, b) { return a + b; } add(1);").code, "E0104");
+    assert_eq!(error("let x = 1; x();").code, "E0105");
+    assert_eq!(error("[1, 2][2];").code, "E0106");
+    assert_eq!(error("1 / 0;").code, "E0107");
+}
+
+#[test]
+fn native_errors_mentioning_an_index_are_invalid_arguments() {
+    // The message of index_of() mentions an index, but the error is in its argument
+    assert_eq!(error("index_of(5, 1);").code, "E0103");
+    assert_eq!(error("[1] - [1, 2];").code, "E0102");
+}
+
+#[test]
+fn syntax_errors_have_the_syntax_code() {
+    assert_eq!(error("return 1;").code, "E0025");
+    assert_eq!(error("let x = 1").code, "E0009");
+}
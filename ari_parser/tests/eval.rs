@@ -0,0 +1,21 @@
+mod common;
+
+use common::{error, eval, output};
+
+#[test]
+fn returns_the_value_of_the_last_statement() {
+    assert_eq!(eval("let x = 2; x + 1;"), "3");
+    assert_eq!(eval(r#"[1, "a"];"#), r#"[1, "a"]"#);
+}
+
+#[test]
+fn captures_printed_output() {
+    assert_eq!(output(r#"print "a"; println "b";"#), "ab\n");
+}
+
+#[test]
+fn errors_are_returned_instead_of_exiting() {
+    let error = error("undefined_name;");
+    assert_eq!(error.code, "E0101");
+    assert_eq!(error.line_number, 1);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::{error, eval};
+use std::fs;
+
+fn write_module(name: &str, contents: &str) -> String {
+    // Modules are imported by path, so each test writes its own into the temporary directory
+    let path = std::env::temp_dir().join(format!("ari_test_{}_{}.ari", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[test]
+fn only_exported_declarations_are_visible() {
+    let path = write_module("shapes", "
+        export let sides = 4;
+        export fn area(width) { return width * helper(); }
+        fn helper() { return sides; }
+    ");
+    assert_eq!(eval(&format!(r#"import "{}"; [sides, area(3)];"#, path)), "[4, 12]");
+    assert_eq!(error(&format!(r#"import "{}"; helper();"#, path)).code, "E0101");
+}
+
+#[test]
+fn missing_modules_cannot_be_imported() {
+    assert_eq!(error(r#"import "does_not_exist.ari";"#).code, "E0108");
+}
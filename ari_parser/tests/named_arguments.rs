@@ -0,0 +1,17 @@
+mod common;
+
+use common::eval;
+
+#[test]
+fn named_arguments_of_user_functions() {
+    let greet = r#"fn greet(name, greeting = "hi") { return greeting + " " + name; } "#;
+    assert_eq!(eval(&format!(r#"{} greet("bob");"#, greet)), "hi bob");
+    assert_eq!(eval(&format!(r#"{} greet("bob", greeting = "yo");"#, greet)), "yo bob");
+    assert_eq!(eval(&format!(r#"{} greet(greeting = "yo", name = "bob");"#, greet)), "yo bob");
+}
+
+#[test]
+fn named_arguments_of_natives() {
+    assert_eq!(eval("modulo(value = 7, modulus = 3);"), "1");
+    assert_eq!(eval("clamp(5, min = 0, max = 3);"), "3");
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{error, eval};
+
+#[test]
+fn integers_stay_exact() {
+    assert_eq!(eval("9007199254740993 + 1;"), "9007199254740994");
+    assert_eq!(eval("9007199254740993 == 9007199254740992;"), "false");
+    assert_eq!(eval("3000000000 * 3;"), "9000000000");
+    assert_eq!(eval("1_000_000 + 0xFF;"), "1000255");
+}
+
+#[test]
+fn floats_are_64_bit() {
+    assert_eq!(eval("0.1 + 0.2;"), "0.30000000000000004");
+    assert_eq!(eval("16777217 + 0.5;"), "16777217.5");
+}
+
+#[test]
+fn integer_division_and_remainder() {
+    assert_eq!(eval("-7 ~/ 2;"), "-4");
+    assert_eq!(eval("7 ~/ 2;"), "3");
+    assert_eq!(eval("-7 % 3;"), "-1");
+    assert_eq!(eval("9007199254740993 % 10;"), "3");
+}
+
+#[test]
+fn large_integers_in_ranges_and_indices() {
+    assert_eq!(eval("9007199254740993..9007199254740995;"), "[9007199254740993, 9007199254740994]");
+    assert_eq!(eval("[10, 20, 30][2.0];"), "30");
+    assert!(error("[10, 20, 30][1.5];").message.contains("is a float"));
+}
+
+#[test]
+fn floats_keep_their_decimal_point() {
+    assert_eq!(eval("2.0;"), "2.0");
+    assert_eq!(eval("2.0 + 1;"), "3.0");
+    assert_eq!(eval("4 / 2;"), "2.0");
+    assert_eq!(eval("1e3;"), "1000.0");
+    assert_eq!(eval("2 * 3;"), "6");
+    assert_eq!(eval("to_number(\"2.0\");"), "2.0");
+}
+
+#[test]
+fn integers_and_floats_can_be_told_apart() {
+    assert_eq!(eval("[is_integer(2), is_integer(2.0), is_integer(\"2\")];"), "[true, false, false]");
+    assert_eq!(eval("[is_integer(floor(2.5)), is_integer(sum([1, 2, 3])), sum([1, 2, 3])];"), "[true, true, 6]");
+    assert_eq!(eval("2 == 2.0;"), "true");
+}
@@ -0,0 +1,20 @@
+mod common;
+
+use ari_parser::Limits;
+use common::{error_with_limits, eval};
+
+#[test]
+fn ranges_count_up_by_one() {
+    assert_eq!(eval("0..5;"), "[0, 1, 2, 3, 4]");
+    assert_eq!(eval("1..=3;"), "[1, 2, 3]");
+    assert_eq!(eval("5..2;"), "[]");
+    assert_eq!(eval("let range_total = 0; for (i in 1..=4) { range_total = range_total + i; } range_total;"), "10");
+}
+
+#[test]
+fn ranges_are_checked_before_they_are_created() {
+    let limits = Limits { max_array_length: Some(1000), ..Limits::default() };
+    let error = error_with_limits("0..1e15;", limits);
+    assert_eq!(error.code, "E0110");
+    assert!(error.message.contains("1000000000000000 elements"), "{}", error.message);
+}
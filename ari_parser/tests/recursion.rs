@@ -0,0 +1,25 @@
+mod common;
+
+use ari_parser::Limits;
+use common::{error, error_with_limits};
+
+#[test]
+fn deep_recursion_is_an_error() {
+    let limits = Limits { max_call_depth: None, ..Limits::default() };
+    let error = error_with_limits("fn endless(n) { return 1 + endless(n + 1); } endless(0);", limits);
+    assert!(error.message.starts_with("Maximum recursion depth exceeded"), "{}", error.message);
+}
+
+#[test]
+fn call_depth_is_limited() {
+    let limits = Limits { max_call_depth: Some(50), ..Limits::default() };
+    let error = error_with_limits("fn nested(n) { return 1 + nested(n + 1); } nested(0);", limits);
+    assert_eq!(error.code, "E0109");
+    assert!(error.message.starts_with("Maximum call depth of 50 exceeded"), "{}", error.message);
+}
+
+#[test]
+fn deeply_nested_blocks_are_an_error() {
+    let source = format!("{}1;{}", "{".repeat(100_000), "}".repeat(100_000));
+    assert!(error(&source).message.starts_with("Maximum recursion depth exceeded"));
+}
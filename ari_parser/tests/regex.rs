@@ -0,0 +1,22 @@
+mod common;
+
+use common::{error, eval};
+
+#[test]
+fn matches_and_finds_every_match() {
+    assert_eq!(eval(r#"regex_match("order 66", "[0-9]+");"#), "true");
+    assert_eq!(eval(r#"regex_match("no digits", "^[0-9]+$");"#), "false");
+    assert_eq!(eval(r#"regex_find_all("a1 b22 c333", "[0-9]+");"#), r#"["1", "22", "333"]"#);
+    assert_eq!(eval(r#"regex_find_all("none", "[0-9]");"#), "[]");
+}
+
+#[test]
+fn replaces_with_capture_groups() {
+    assert_eq!(eval(r#"regex_replace("2024-05-01", "(\d+)-(\d+)-(\d+)", "$3/$2/$1");"#), "01/05/2024");
+    assert_eq!(eval(r#"regex_replace("John Smith", "(?P<first>\w+) (?P<last>\w+)", "${last}, ${first}");"#), "Smith, John");
+}
+
+#[test]
+fn invalid_patterns_are_errors() {
+    assert_eq!(error(r#"regex_match("text", "(unclosed");"#).code, "E0103");
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use ari_parser::Limits;
+use common::{error, error_with_limits, eval, run_with_limits};
+
+#[test]
+fn strings_are_repeated_by_integers() {
+    assert_eq!(eval(r#""-" * 3;"#), "---");
+    assert_eq!(eval(r#"2 * "ab";"#), "abab");
+    assert_eq!(eval(r#""x" * 0;"#), "");
+}
+
+#[test]
+fn repetition_counts_must_be_non_negative_integers() {
+    assert!(error(r#""a" * -1;"#).message.contains("non-negative integer"));
+    assert!(error(r#""a" * 1.5;"#).message.contains("non-negative integer"));
+}
+
+#[test]
+fn repetition_is_checked_against_the_memory_limit() {
+    let limits = Limits { max_memory: Some(1000), ..Limits::default() };
+    assert_eq!(error_with_limits(r#""-" * 1e18;"#, limits).code, "E0113");
+    assert_eq!(error_with_limits(r#""-" * 1001;"#, limits).code, "E0113");
+    assert_eq!(run_with_limits(r#""-" * 1000;"#, limits).0.unwrap().len(), 1000);
+}
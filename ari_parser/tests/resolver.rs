@@ -0,0 +1,16 @@
+mod common;
+
+use common::{error, eval};
+
+#[test]
+fn variables_resolve_to_the_innermost_declaration() {
+    assert_eq!(eval("let shadowed = 1; { let shadowed = 2; } shadowed;"), "1");
+    assert_eq!(eval("let outer = 1; fn read_outer(outer) { outer; return outer; } read_outer(5);"), "5");
+    assert_eq!(eval("let counted = 0; fn bump() { counted = counted + 1; } bump(); bump(); counted;"), "2");
+}
+
+#[test]
+fn block_variables_are_not_visible_outside() {
+    let error = error("{ let hidden = 1; } hidden;");
+    assert_eq!(error.code, "E0101");
+}
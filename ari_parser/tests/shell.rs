@@ -0,0 +1,8 @@
+mod common;
+
+use common::eval;
+
+#[test]
+fn exec_capture_returns_the_exit_code_as_a_number() {
+    assert_eq!(eval(r#"let result = exec_capture("echo hi; exit 3"); [result[0], result[2] + 1];"#), "[\"hi\n\", 4]");
+}
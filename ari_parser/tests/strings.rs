@@ -0,0 +1,24 @@
+mod common;
+
+use common::{error, eval};
+
+#[test]
+fn length_counts_characters() {
+    assert_eq!(eval(r#"length("héllo");"#), "5");
+    assert_eq!(eval(r#"length("日本");"#), "2");
+    assert_eq!(eval(r#"length("");"#), "0");
+}
+
+#[test]
+fn trim_replace_and_substring() {
+    assert_eq!(eval("trim(\"  padded\t \");"), "padded");
+    assert_eq!(eval(r#"replace("a-b-c", "-", "+");"#), "a+b+c");
+    assert_eq!(eval(r#"substring("héllo", 1, 4);"#), "éll");
+    assert_eq!(eval(r#"substring("abc", 0, 0);"#), "");
+}
+
+#[test]
+fn substring_checks_its_bounds() {
+    assert_eq!(error(r#"substring("abc", 2, 5);"#).code, "E0103");
+    assert_eq!(error(r#"trim(5);"#).code, "E0103");
+}
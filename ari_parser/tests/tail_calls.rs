@@ -0,0 +1,17 @@
+mod common;
+
+use common::eval;
+
+#[test]
+fn tail_calls_do_not_reach_the_call_depth_limit() {
+    let count = "fn count(n, acc) { if (n == 0) { return acc; } return count(n - 1, acc + 1); } ";
+    assert_eq!(eval(&format!("{} count(100000, 0);", count)), "100000");
+}
+
+#[test]
+fn mutual_tail_calls() {
+    let source = "fn is_even_steps(n) { if (n == 0) { return true; } return is_odd_steps(n - 1); } \
+                  fn is_odd_steps(n) { if (n == 0) { return false; } return is_even_steps(n - 1); } \
+                  is_even_steps(50001);";
+    assert_eq!(eval(source), "false");
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{eval, output};
+
+#[test]
+fn conditions_accept_any_value() {
+    let printed = output(r#"
+        if (0) { println "0 is true"; } else { println "0 is false"; }
+        if ("") { println "empty string is true"; } else { println "empty string is false"; }
+        if ([]) { println "empty array is true"; } else { println "empty array is false"; }
+        if ([0]) { println "array is true"; }
+        if ("a") { println "string is true"; }
+        if (null) { println "null is true"; } else { println "null is false"; }
+    "#);
+    assert_eq!(printed, "0 is false\nempty string is false\nempty array is false\narray is true\nstring is true\nnull is false\n");
+}
+
+#[test]
+fn while_loops_on_a_number() {
+    assert_eq!(eval("let countdown = 3; let steps = 0; while (countdown) { countdown = countdown - 1; steps = steps + 1; } steps;"), "3");
+}
+
+#[test]
+fn logical_operators_return_an_operand() {
+    assert_eq!(eval(r#"0 or "fallback";"#), "fallback");
+    assert_eq!(eval(r#""" and 1;"#), "");
+    assert_eq!(eval("[1] and 2;"), "2");
+}
+
+#[test]
+fn callbacks_return_any_value() {
+    let itself = "fn itself(x) { return x; } ";
+    assert_eq!(eval("fn odd(x) { return x % 2; } filter([0, 1, 2, 3], odd);"), "[1, 3]");
+    assert_eq!(eval(&format!(r#"{} find(["", "a"], itself);"#, itself)), "a");
+    assert_eq!(eval(&format!("{} any([[], [1]], itself);", itself)), "true");
+    assert_eq!(eval(&format!("{} all([1, 0], itself);", itself)), "false");
+    assert_eq!(eval(&format!("{} count_if([1, 0, 5], itself);", itself)), "2");
+}
@@ -0,0 +1,16 @@
+mod common;
+
+use common::eval;
+
+#[test]
+fn unary_minus_applies_elementwise() {
+    assert_eq!(eval("-[1, 2];"), "[-1, -2]");
+    assert_eq!(eval("-[1, [2, 3]];"), "[-1, [-2, -3]]");
+}
+
+#[test]
+fn negation_of_an_array_is_its_falsiness() {
+    assert_eq!(eval("![];"), "true");
+    assert_eq!(eval("![false];"), "false");
+    assert_eq!(eval("!0;"), "true");
+}
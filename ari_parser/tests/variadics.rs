@@ -0,0 +1,21 @@
+mod common;
+
+use common::{error, eval};
+
+#[test]
+fn variadic_arguments_collect_the_rest() {
+    assert_eq!(eval("fn total(...nums) { return sum(nums); } total(1, 2, 3);"), "6");
+    assert_eq!(eval("fn first_and_rest(first, ...rest) { return [first, rest]; } first_and_rest(1);"), "[1, []]");
+}
+
+#[test]
+fn arrays_spread_into_arguments() {
+    assert_eq!(eval("fn add(a, b) { return a + b; } let pair = [2, 3]; add(...pair);"), "5");
+    assert_eq!(eval("fn count(...items) { return length(items); } count(0, ...[1, 2], 3);"), "4");
+}
+
+#[test]
+fn spreading_checks_the_arity() {
+    assert_eq!(error("fn add(a, b) { return a + b; } add(...[1, 2, 3]);").code, "E0104");
+    assert_eq!(error("fn add(a, b) { return a + b; } add(...5);").code, "E0100");
+}